@@ -0,0 +1,242 @@
+use std::panic::{AssertUnwindSafe, catch_unwind};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use rayon::prelude::*;
+
+use crate::cli::Cli;
+use crate::prelude::*;
+
+/// The outcome of running a single config of a `--config-dir` batch.
+///
+/// # Fields
+///
+/// * `config` - The config file that was run.
+/// * `status` - `Ok(())` if the run succeeded, otherwise the error (or panic) message.
+/// * `elapsed` - How long the run took.
+#[derive(Debug, Clone)]
+pub struct BatchOutcome {
+    pub config: PathBuf,
+    pub status: std::result::Result<(), String>,
+    pub elapsed: Duration,
+}
+
+/// Lists the config files (see `CONFIG_FILE_EXTENSIONS`) directly inside a directory, sorted by name.
+///
+/// # Arguments
+///
+/// * `dir` - The directory to look in.
+///
+/// # Returns
+///
+/// * `Result<Vec<PathBuf>>` - Returns the config files, or an `Error` on failure.
+///
+/// # Errors
+///
+/// Returns `Error::NoMatchingFiles` if there are no config files, or an IO error if the directory cannot be read.
+///
+/// # Example
+///
+/// ```ignore
+/// let configs = config_files(Path::new("configs"))?;
+/// ```
+pub fn config_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut configs = std::fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.is_file()
+                && path.extension().is_some_and(|ext| {
+                    CONFIG_FILE_EXTENSIONS
+                        .iter()
+                        .any(|config_ext| ext.eq_ignore_ascii_case(config_ext))
+                })
+        })
+        .collect::<Vec<_>>();
+    if configs.is_empty() {
+        return Err(Error::NoMatchingFiles);
+    }
+    configs.sort();
+    Ok(configs)
+}
+
+/// Runs every config through `run`, at most `jobs` of them at a time.
+///
+/// Each run gets its own copy of the CLI arguments with `--config` pointing at its config file, and so its own `State`.
+/// A run that fails - or panics - is recorded in its `BatchOutcome` without affecting the others.
+/// Log lines of concurrent runs interleave, so every run logs its start and end with the config it belongs to.
+///
+/// # Arguments
+///
+/// * `cli` - The CLI arguments shared by every run. Any overrides (such as `--output_path`) apply to every config.
+/// * `configs` - The config files to run.
+/// * `jobs` - The maximum number of configs run at the same time.
+/// * `run` - Runs a single config, e.g. `crate::run`.
+///
+/// # Returns
+///
+/// * `Result<Vec<BatchOutcome>>` - Returns one outcome per config, in the order of `configs`.
+///
+/// # Errors
+///
+/// This function can return errors if the thread pool cannot be created.
+///
+/// # Example
+///
+/// ```ignore
+/// let outcomes = run_batch(&cli, config_files(&config_dir)?, 4, crate::run)?;
+/// ```
+pub fn run_batch<F>(cli: &Cli, configs: Vec<PathBuf>, jobs: usize, run: F) -> Result<Vec<BatchOutcome>>
+where
+    F: Fn(Cli) -> Result<()> + Sync,
+{
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(jobs.max(1))
+        .build()
+        .map_err(|e| Error::Io(std::io::Error::other(e)))?;
+
+    Ok(pool.install(|| {
+        configs
+            .into_par_iter()
+            .map(|config| {
+                let cli = Cli {
+                    config_file: Some(config.clone()),
+                    config_dir: None,
+                    ..cli.clone()
+                };
+
+                info!("[{}] Starting", config.display());
+                let started = Instant::now();
+                let status = match catch_unwind(AssertUnwindSafe(|| run(cli))) {
+                    Ok(Ok(())) => Ok(()),
+                    Ok(Err(e)) => Err(e.to_string()),
+                    Err(panic) => Err(panic_message(&*panic)),
+                };
+                let elapsed = started.elapsed();
+
+                match &status {
+                    Ok(()) => info!("[{}] Finished in {elapsed:.2?}", config.display()),
+                    Err(e) => error!("[{}] Failed: {e}", config.display()),
+                }
+                BatchOutcome {
+                    config,
+                    status,
+                    elapsed,
+                }
+            })
+            .collect()
+    }))
+}
+
+/// Renders the outcomes as an aligned `CONFIG | STATUS | TIME | ERROR` table.
+///
+/// # Arguments
+///
+/// * `outcomes` - The outcomes produced by `run_batch`.
+///
+/// # Returns
+///
+/// * `String` - Returns the table, one line per config after the header.
+pub fn render_batch_status(outcomes: &[BatchOutcome]) -> String {
+    let names = outcomes
+        .iter()
+        .map(|outcome| {
+            outcome
+                .config
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_string()
+        })
+        .collect::<Vec<_>>();
+    let name_width = names
+        .iter()
+        .map(|name| name.chars().count())
+        .chain(["CONFIG".len()])
+        .max()
+        .unwrap_or_default();
+
+    let mut table = format!("{:name_width$} | STATUS | {:>9} | ERROR\n", "CONFIG", "TIME");
+    table.push_str(&format!("{}-+--------+-{}-+-{}\n", "-".repeat(name_width), "-".repeat(9), "-".repeat(5)));
+    for (outcome, name) in outcomes.iter().zip(names) {
+        let (status, error) = match &outcome.status {
+            Ok(()) => ("ok", ""),
+            Err(e) => ("failed", e.as_str()),
+        };
+        let elapsed = format!("{:.2?}", outcome.elapsed);
+        table.push_str(&format!("{name:name_width$} | {status:6} | {elapsed:>9} | {error}\n"));
+    }
+    table
+}
+
+/// Extracts the message of a caught panic.
+fn panic_message(panic: &(dyn std::any::Any + Send)) -> String {
+    panic
+        .downcast_ref::<&str>()
+        .map(ToString::to_string)
+        .or_else(|| panic.downcast_ref::<String>().cloned())
+        .map_or_else(|| "panicked".to_string(), |msg| format!("panicked: {msg}"))
+}
+
+#[cfg(test)]
+mod batch_tests {
+    use clap::Parser;
+    use tempfile::tempdir;
+
+    use super::*;
+
+    fn write_config(dir: &Path, name: &str, source: &Path) -> PathBuf {
+        let config = serde_json::json!({
+            "source": source,
+            "output_type": "csv",
+            "output_path": dir.join(format!("{name}_out.csv")),
+            "has_headers": true,
+            "fields": ["Id", "Name"],
+            "unique_fields": ["Id"],
+            "include_cols_with": { "Name": [name] }
+        });
+        let path = dir.join(format!("{name}.json"));
+        std::fs::write(&path, config.to_string()).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_run_batch_concurrently() {
+        let dir = tempdir().unwrap();
+        let source = dir.path().join("input.csv");
+        std::fs::write(&source, "Id,Name\n1,alice\n2,bob\n3,carl\n1,alice\n").unwrap();
+
+        let names = ["alice", "bob", "carl"];
+        for name in names {
+            write_config(dir.path(), name, &source);
+        }
+        // A config that can't be parsed must not affect the others
+        std::fs::write(dir.path().join("broken.json"), "{ not json").unwrap();
+
+        let cli = Cli::parse_from(["csv_parser_rs"]);
+        let outcomes = run_batch(&cli, config_files(dir.path()).unwrap(), 3, crate::run).unwrap();
+
+        assert_eq!(outcomes.len(), 4);
+        assert!(outcomes[0].config.ends_with("alice.json") && outcomes[0].status.is_ok());
+        assert!(outcomes[1].config.ends_with("bob.json") && outcomes[1].status.is_ok());
+        assert!(outcomes[2].config.ends_with("broken.json") && outcomes[2].status.is_err());
+        assert!(outcomes[3].config.ends_with("carl.json") && outcomes[3].status.is_ok());
+
+        for (id, name) in (1..).zip(names) {
+            let output = std::fs::read_to_string(dir.path().join(format!("{name}_out.csv"))).unwrap();
+            assert_eq!(output, format!("Id,Name\n{id},{name}\n"));
+        }
+
+        let table = render_batch_status(&outcomes);
+        assert!(
+            table
+                .lines()
+                .any(|line| line.starts_with("broken.json") && line.contains("failed"))
+        );
+    }
+
+    #[test]
+    fn test_config_files_empty_dir() {
+        let dir = tempdir().unwrap();
+        assert!(matches!(config_files(dir.path()), Err(Error::NoMatchingFiles)));
+    }
+}
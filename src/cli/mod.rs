@@ -3,7 +3,7 @@ use std::fmt::{Debug, Display};
 use std::ops::Not;
 use std::path::PathBuf;
 
-use clap::{Parser, ValueEnum, command};
+use clap::{Parser, ValueEnum};
 use stderrlog::LogLevelNum;
 
 use crate::prelude::{Deserialize, Serialize, *};
@@ -19,12 +19,19 @@ use crate::prelude::{Deserialize, Serialize, *};
 ///
 /// * `source` - The source CSV file to parse. This argument is optional and overrides the source file in the configuration file if provided.
 /// * `config_file` - The configuration file to use. This option is optional and overrides the default configuration file.
+/// * `config_format` - The format of the configuration file, for when its extension doesn't say (or says wrong).
 /// * `output_type` - The output type to use. This option is optional and specifies the format of the output.
 /// * `output_path` - The output file path to use. This option is optional and specifies the path where the output file will be saved.
+/// * `fields` - The fields to retain, overriding `fields` in the configuration file.
+/// * `unique` - The fields to deduplicate on, overriding `unique_fields` in the configuration file.
+/// * `filters` - `column=value` filters, overriding the `include_cols_with` values of those columns in the configuration file.
+///
+/// With a `source` (or `latest_in`) and `fields` but no `config_file`, no configuration file is read or created at all,
+/// see `Cli::is_config_less`.
 ///
 /// # Example
 ///
-/// ```rust
+/// ```ignore
 /// let cli = Cli::parse();
 /// println!("{:?}", cli);
 /// ```
@@ -47,17 +54,133 @@ pub struct Cli {
     #[arg(name = "source", index = 1, help = "The source CSV file to parse - overrides the source file in the config file file if provided.", required = false, value_hint = clap::ValueHint::FilePath)]
     pub source: Option<PathBuf>,
 
-    /// The configuration file to use - overrides the default configuration file.
-    #[arg(name = "config_file", short = 'c', long = "config", help = "The configuration file to use - overrides the default configuration file.", required = false, value_hint = clap::ValueHint::FilePath)]
+    /// Use the most recently modified CSV file in this directory as the source.
+    #[arg(name = "latest_in", long = "latest-in", help = "Use the most recently modified .csv file in this directory as the source.", required = false, conflicts_with = "source", value_hint = clap::ValueHint::DirPath)]
+    pub latest_in: Option<PathBuf>,
+
+    /// The configuration file (or http(s) URL) to use - overrides the default configuration file.
+    #[arg(name = "config_file", short = 'c', long = "config", help = "The configuration file to use - overrides the default configuration file. May also be an http(s) URL, which is cached locally.", required = false, value_hint = clap::ValueHint::FilePath)]
     pub config_file: Option<PathBuf>,
     
+    /// How long a cached remote config stays fresh.
+    #[arg(name = "config_cache_ttl", long = "config-cache-ttl", help = "Seconds a cached remote config (--config <URL>) is used without re-fetching.", required = false, default_value_t = DEFAULT_REMOTE_CONFIG_TTL_SECS, value_hint = clap::ValueHint::Other)]
+    pub config_cache_ttl: u64,
+
+    /// Parse the config file as this format, rather than going by its extension.
+    #[arg(value_enum, name = "config_format", long = "config-format", help = "Parse the config file as this format, rather than going by its extension (.yaml/.yml, .toml, otherwise JSON).", required = false, value_hint = clap::ValueHint::Other)]
+    pub config_format: Option<ConfigFormat>,
+
     /// The output type to use.
     #[arg(name = "output_type", short = 't', long = "output_type", help = "The output type to use.", required = false, value_hint = clap::ValueHint::Other, value_enum,)]
     pub output_type: Option<OutputType>,
 
     #[arg(name = "output_path", short = 'o', long = "output_path", help = "The output file path to use.", required = false, value_hint = clap::ValueHint::FilePath)]
     pub output_path: Option<PathBuf>,
+
+    /// The fields to retain, as a comma separated list or by repeating the flag.
+    #[arg(name = "fields", short = 'f', long = "fields", help = "The fields to retain, in output order, as a comma separated list (or repeated) - overrides fields in the config file. Together with a source, no config file is needed.", required = false, value_delimiter = ',', value_hint = clap::ValueHint::Other)]
+    pub fields: Vec<String>,
+
+    /// The fields to deduplicate on, as a comma separated list.
+    #[arg(name = "unique", long = "unique", help = "The fields to deduplicate on, as a comma separated list - overrides unique_fields in the config file.", required = false, value_delimiter = ',', value_hint = clap::ValueHint::Other)]
+    pub unique: Vec<String>,
+
+    /// Only keep rows where a column has a value, may be repeated.
+    #[arg(name = "filter", short = 'F', long = "filter", help = "Only keep rows whose COL is VAL, given as COL=VAL. Repeat it to filter on more columns, or to allow more values of one column - replaces the include_cols_with values of COL in the config file.", required = false, value_parser = parse_filter, value_hint = clap::ValueHint::Other)]
+    pub filters: Vec<(String, String)>,
     
+    /// Stop reading after this many rows have been kept.
+    #[arg(name = "limit", short = 'n', long = "limit", help = "Stop reading the source once N rows have passed the filters (applied before deduplication).", required = false, value_hint = clap::ValueHint::Other)]
+    pub limit: Option<usize>,
+
+    /// Only process the data records in this half-open index range.
+    #[arg(name = "rows", long = "rows", help = "Only process the data records with a zero-based index in START:END (END excluded), counted from the first row after the headers. Reading stops at END.", required = false, value_parser = parse_row_range, value_hint = clap::ValueHint::Other)]
+    pub rows: Option<(usize, usize)>,
+
+    /// Keep only the first N rows.
+    #[arg(name = "head", long = "head", help = "Keep only the first N rows that pass the filters, stopping the read once they have been kept.", required = false, conflicts_with = "tail", value_hint = clap::ValueHint::Other)]
+    pub head: Option<usize>,
+
+    /// Keep only the last N rows.
+    #[arg(name = "tail", long = "tail", help = "Keep only the last N rows that pass the filters. The whole source is read (and held in memory) to find them.", required = false, value_hint = clap::ValueHint::Other)]
+    pub tail: Option<usize>,
+
+    /// Keep N randomly chosen rows.
+    #[arg(name = "sample", long = "sample", help = "Keep N randomly chosen rows, after filtering and deduplication. Fewer than N rows are all kept.", required = false, value_hint = clap::ValueHint::Other)]
+    pub sample: Option<usize>,
+
+    /// The seed for --sample.
+    #[arg(name = "seed", long = "seed", help = "The seed for --sample - the same seed picks the same rows from the same data.", required = false, value_hint = clap::ValueHint::Other)]
+    pub seed: Option<u64>,
+
+    /// Preview the effect of deduplication before it runs.
+    #[arg(name = "dedup_preview", long = "dedup-preview", help = "Before deduplicating, print the top N duplicated keys with their before/after row counts.", required = false, value_hint = clap::ValueHint::Other)]
+    pub dedup_preview: Option<usize>,
+
+    /// Wrap html output in a complete HTML document.
+    #[arg(name = "html_standalone", long = "html-standalone", help = "With html output, wrap the table in a complete HTML document rather than writing the bare <table>.", required = false, action = clap::ArgAction::SetTrue)]
+    pub html_standalone: bool,
+
+    /// Show the progress of reading the source on stderr.
+    #[arg(name = "progress", long = "progress", help = "Show a progress bar of the source read so far on stderr (a spinner with a record count for stdin or named pipes).", required = false, action = clap::ArgAction::SetTrue)]
+    pub progress: bool,
+
+    /// Sort and deduplicate via temporary files on disk rather than in memory.
+    #[arg(name = "external", long = "external", help = "Sort & deduplicate via temporary chunk files on disk - for files too large to fit in memory.", required = false, action = clap::ArgAction::SetTrue)]
+    pub external: bool,
+
+    /// Only output the rows that are new or changed compared to a previous output file.
+    #[arg(name = "delta_against", long = "delta-against", help = "Only output rows that were added or changed compared to this previous output file, tagged in a trailing _delta column.", required = false, value_hint = clap::ValueHint::FilePath)]
+    pub delta_against: Option<PathBuf>,
+
+    /// Also write per-column statistics of the output to this file.
+    #[arg(name = "with_stats", long = "with-stats", help = "Also write per-column statistics (null count, distinct count, min, max) of the output to this CSV file.", required = false, value_hint = clap::ValueHint::FilePath)]
+    pub with_stats: Option<PathBuf>,
+
+    /// Write every `validations` violation in the output to this file.
+    #[arg(name = "validate_report", long = "validate-report", help = "Check the output against the configured validations and write every violation (row, column, value, violation) to this CSV or .json file.", required = false, value_hint = clap::ValueHint::FilePath)]
+    pub validate_report: Option<PathBuf>,
+
+    /// Generate a config from the headers of this CSV file, then exit.
+    #[arg(name = "init_from_source", long = "init-from-source", help = "Generate a config from the headers of this CSV file (written to --config, or the default location) and exit.", required = false, value_hint = clap::ValueHint::FilePath)]
+    pub init_from_source: Option<PathBuf>,
+
+    /// Sample this many rows to suggest filters for low-cardinality columns in the generated config.
+    #[arg(name = "with_filters", long = "with-filters", help = "With --init-from-source, sample N rows and add commented out (__ prefixed) include_cols_with filters for low-cardinality columns.", required = false, requires = "init_from_source", value_hint = clap::ValueHint::Other)]
+    pub with_filters: Option<usize>,
+
+    /// Run every config in this directory.
+    #[arg(name = "config_dir", long = "config-dir", help = "Run every .json, .yaml, .yml or .toml config in this directory (each with its own source and output), then print a status table.", required = false, conflicts_with = "config_file", value_hint = clap::ValueHint::DirPath)]
+    pub config_dir: Option<PathBuf>,
+
+    /// How many configs of --config-dir to run at the same time.
+    #[arg(name = "jobs", short = 'j', long = "jobs", help = "With --config-dir, the number of configs processed at the same time.", required = false, default_value_t = 1, requires = "config_dir", value_hint = clap::ValueHint::Other)]
+    pub jobs: usize,
+
+    /// Report where each configuration value came from.
+    #[arg(name = "explain_config", long = "explain-config", help = "Print a table of every config key, its final value and the layer (default, file, cli) it came from.", required = false, action = clap::ArgAction::SetTrue)]
+    pub explain_config: bool,
+
+    /// Resolve the config and source, report what a run would do, then exit without processing.
+    #[arg(name = "dry_run", long = "dry-run", help = "Resolve the config and the source's headers, print the source, retained columns, filter columns and output target, then exit without reading the rows or writing any output.", required = false, action = clap::ArgAction::SetTrue)]
+    pub dry_run: bool,
+
+    /// Write the run's row counts as a JSON sidecar next to the output.
+    #[arg(name = "stats", long = "stats", help = "Also write the run's row counts (read, malformed, filtered out, removed as duplicates, kept) to <output_path>.stats.json.", required = false, action = clap::ArgAction::SetTrue)]
+    pub stats: bool,
+
+    /// Only print the number of rows that pass the filters.
+    #[arg(name = "count", long = "count", help = "Only print the number of rows that pass the filters to stdout, without writing any output. fields may be left empty.", required = false, conflicts_with = "dry_run", action = clap::ArgAction::SetTrue)]
+    pub count: bool,
+
+    /// Never create a default config file.
+    #[arg(name = "no_config", long = "no-config", help = "Don't create a default config file when there is none (e.g. in a read-only directory or CI), error instead. Without --config, every setting then has to be given on the command line.", required = false, action = clap::ArgAction::SetTrue)]
+    pub no_config: bool,
+
+    /// Update to the latest release, then exit.
+    #[arg(name = "update", long = "update", help = "Update the tool to its latest release, print the version it's now at and exit. No config file is needed.", required = false, action = clap::ArgAction::SetTrue)]
+    pub update: bool,
+
     /// Optional verbosity level of the logger.
     /// You may provide this as either a string or a number.
     ///
@@ -88,6 +211,18 @@ pub enum VerbosityLevel {
     Trace,
 }
 
+impl From<VerbosityLevel> for log::LevelFilter {
+    fn from(value: VerbosityLevel) -> Self {
+        match value {
+            VerbosityLevel::Error => log::LevelFilter::Error,
+            VerbosityLevel::Warn => log::LevelFilter::Warn,
+            VerbosityLevel::Info => log::LevelFilter::Info,
+            VerbosityLevel::Debug => log::LevelFilter::Debug,
+            VerbosityLevel::Trace => log::LevelFilter::Trace,
+        }
+    }
+}
+
 impl From<VerbosityLevel> for LogLevelNum {
     fn from(value: VerbosityLevel) -> Self {
         match value {
@@ -100,19 +235,50 @@ impl From<VerbosityLevel> for LogLevelNum {
     }
 }
 
+/// The format a config file is written in, see `--config-format`.
+///
+/// # Variants
+///
+/// * `Json` - A JSON config, the default for unknown extensions.
+/// * `Yaml` - A YAML config, detected from a `.yaml` or `.yml` extension.
+/// * `Toml` - A TOML config, detected from a `.toml` extension.
+#[derive(Debug, ValueEnum, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigFormat {
+    Json,
+    Yaml,
+    Toml,
+}
+
+impl From<ConfigFormat> for config::FileFormat {
+    fn from(format: ConfigFormat) -> Self {
+        match format {
+            ConfigFormat::Json => config::FileFormat::Json,
+            ConfigFormat::Yaml => config::FileFormat::Yaml,
+            ConfigFormat::Toml => config::FileFormat::Toml,
+        }
+    }
+}
+
 /// Represents the output type for the `parse_csv_rs` tool.
 ///
-/// This enum defines the possible output types for the tool, which can be either `Stdout` or `Csv`.
+/// This enum defines the possible output types for the tool, which can be either `Stdout`, `Csv`, `Tsv`, `Json`, `Ndjson`,
+/// `Table`, `Markdown` or `Html`.
 /// It supports serialization and deserialization using `serde`, and can be used as a value enum in CLI arguments.
 ///
 /// # Variants
 ///
 /// * `Stdout` - Represents output to the standard output.
 /// * `Csv` - Represents output to a CSV file.
+/// * `Tsv` - Represents output to a tab-separated file.
+/// * `Json` - Represents output to a JSON file (or stdout), as an array of objects keyed by column.
+/// * `Ndjson` - Represents output to a newline-delimited JSON file (or stdout), one object per line.
+/// * `Table` - Represents a column-aligned table for reading in a terminal (or written to a text file), see `RetainedData::to_table`.
+/// * `Markdown` - Represents a GitHub-flavored Markdown table (to a file or stdout), see `RetainedData::to_markdown`.
+/// * `Html` - Represents an HTML table (to a file or stdout), see `RetainedData::to_html`.
 ///
 /// # Example
 ///
-/// ```rust
+/// ```ignore
 /// use crate::OutputType;
 ///
 /// let output_type = OutputType::Stdout;
@@ -127,6 +293,30 @@ pub enum OutputType {
     #[value(name = "csv", alias = "csv", alias = "Csv", alias = "1")]
     #[serde(rename = "csv")]
     Csv,
+
+    #[value(name = "tsv", alias = "tsv", alias = "Tsv", alias = "2")]
+    #[serde(rename = "tsv")]
+    Tsv,
+
+    #[value(name = "json", alias = "json", alias = "Json", alias = "3")]
+    #[serde(rename = "json")]
+    Json,
+
+    #[value(name = "ndjson", alias = "ndjson", alias = "Ndjson", alias = "4")]
+    #[serde(rename = "ndjson")]
+    Ndjson,
+
+    #[value(name = "table", alias = "table", alias = "Table", alias = "5")]
+    #[serde(rename = "table")]
+    Table,
+
+    #[value(name = "markdown", alias = "md", alias = "Markdown", alias = "6")]
+    #[serde(rename = "markdown")]
+    Markdown,
+
+    #[value(name = "html", alias = "html", alias = "Html", alias = "7")]
+    #[serde(rename = "html")]
+    Html,
 }
 
 impl Debug for OutputType {
@@ -144,7 +334,7 @@ impl Debug for OutputType {
     ///
     /// # Example
     ///
-    /// ```rust
+    /// ```ignore
     /// let output_type = OutputType::Stdout;
     /// println!("{:?}", output_type); // prints "OutputType::Stdout"
     /// ```
@@ -152,6 +342,12 @@ impl Debug for OutputType {
         match self {
             OutputType::Stdout => write!(f, "OutputType::Stdout"),
             OutputType::Csv => write!(f, "OutputType::Csv"),
+            OutputType::Tsv => write!(f, "OutputType::Tsv"),
+            OutputType::Json => write!(f, "OutputType::Json"),
+            OutputType::Ndjson => write!(f, "OutputType::Ndjson"),
+            OutputType::Table => write!(f, "OutputType::Table"),
+            OutputType::Markdown => write!(f, "OutputType::Markdown"),
+            OutputType::Html => write!(f, "OutputType::Html"),
         }
     }
 }
@@ -171,7 +367,7 @@ impl Display for OutputType {
     ///
     /// # Example
     ///
-    /// ```rust
+    /// ```ignore
     /// let output_type = OutputType::Stdout;
     /// println!("{}", output_type); // prints "stdout"
     /// ```
@@ -179,6 +375,12 @@ impl Display for OutputType {
         match self {
             OutputType::Stdout => write!(f, "stdout"),
             OutputType::Csv => write!(f, "csv"),
+            OutputType::Tsv => write!(f, "tsv"),
+            OutputType::Json => write!(f, "json"),
+            OutputType::Ndjson => write!(f, "ndjson"),
+            OutputType::Table => write!(f, "table"),
+            OutputType::Markdown => write!(f, "markdown"),
+            OutputType::Html => write!(f, "html"),
         }
     }
 }
@@ -198,7 +400,7 @@ impl From<OutputType> for String {
     ///
     /// # Example
     ///
-    /// ```rust
+    /// ```ignore
     /// let output_type = OutputType::Stdout;
     /// let output_type_str: String = output_type.into();
     /// println!("{}", output_type_str); // prints "stdout"
@@ -207,6 +409,12 @@ impl From<OutputType> for String {
         match output_type {
             OutputType::Stdout => "stdout".to_string(),
             OutputType::Csv => "csv".to_string(),
+            OutputType::Tsv => "tsv".to_string(),
+            OutputType::Json => "json".to_string(),
+            OutputType::Ndjson => "ndjson".to_string(),
+            OutputType::Table => "table".to_string(),
+            OutputType::Markdown => "markdown".to_string(),
+            OutputType::Html => "html".to_string(),
         }
     }
 }
@@ -226,7 +434,7 @@ impl AsRef<OsStr> for OutputType {
     ///
     /// # Example
     ///
-    /// ```rust
+    /// ```ignore
     /// let output_type = OutputType::Stdout;
     /// let output_type_os_str: &OsStr = output_type.as_ref();
     /// println!("{:?}", output_type_os_str); // prints "stdout"
@@ -235,6 +443,12 @@ impl AsRef<OsStr> for OutputType {
         match self {
             OutputType::Stdout => OsStr::new("stdout"),
             OutputType::Csv => OsStr::new("csv"),
+            OutputType::Tsv => OsStr::new("tsv"),
+            OutputType::Json => OsStr::new("json"),
+            OutputType::Ndjson => OsStr::new("ndjson"),
+            OutputType::Table => OsStr::new("table"),
+            OutputType::Markdown => OsStr::new("markdown"),
+            OutputType::Html => OsStr::new("html"),
         }
     }
 }
@@ -251,7 +465,7 @@ impl Default for OutputType {
     ///
     /// # Example
     ///
-    /// ```rust
+    /// ```ignore
     /// let default_output_type = OutputType::default();
     /// asser
     fn default() -> Self {
@@ -262,7 +476,7 @@ impl Default for OutputType {
 impl PartialEq for OutputType {
     /// Compares two `OutputType` instances for equality.
     ///
-    /// This implementation checks if both instances are either `OutputType::Stdout` or `OutputType::Csv`.
+    /// This implementation checks if both instances are the same `OutputType` variant.
     ///
     /// # Arguments
     ///
@@ -275,13 +489,23 @@ impl PartialEq for OutputType {
     ///
     /// # Example
     ///
-    /// ```rust
+    /// ```ignore
     /// let output_type1 = OutputType::Stdout;
     /// let output_type2 = OutputType::Stdout;
     /// assert_eq!(output_type1, output_type2);
     /// ```
     fn eq(&self, other: &Self) -> bool {
-        matches!((self, other), (OutputType::Stdout, OutputType::Stdout) | (OutputType::Csv, OutputType::Csv))
+        matches!(
+            (self, other),
+            (OutputType::Stdout, OutputType::Stdout)
+                | (OutputType::Csv, OutputType::Csv)
+                | (OutputType::Tsv, OutputType::Tsv)
+                | (OutputType::Json, OutputType::Json)
+                | (OutputType::Ndjson, OutputType::Ndjson)
+                | (OutputType::Table, OutputType::Table)
+                | (OutputType::Markdown, OutputType::Markdown)
+                | (OutputType::Html, OutputType::Html)
+        )
     }
 }
 
@@ -292,15 +516,16 @@ impl Not for OutputType {
 
     /// Implements the logical NOT operator for `OutputType`.
     ///
-    /// This implementation toggles between `OutputType::Stdout` and `OutputType::Csv`.
+    /// This implementation toggles between writing to the terminal and writing to a file.
     ///
     /// # Returns
     ///
-    /// * `OutputType` - Returns `OutputType::Csv` if the current instance is `OutputType::Stdout`, and vice versa.
+    /// * `OutputType` - Returns `OutputType::Csv` if the current instance is `OutputType::Stdout`,
+    ///   and `OutputType::Stdout` for any file-based output type.
     ///
     /// # Example
     ///
-    /// ```rust
+    /// ```ignore
     /// let output_type = OutputType::Stdout;
     /// let toggled_output_type = !output_type;
     /// assert_eq!(toggled_output_type, OutputType::Csv);
@@ -308,16 +533,21 @@ impl Not for OutputType {
     fn not(self) -> Self::Output {
         match self {
             OutputType::Stdout => OutputType::Csv,
-            OutputType::Csv => OutputType::Stdout,
+            OutputType::Csv
+            | OutputType::Tsv
+            | OutputType::Json
+            | OutputType::Ndjson
+            | OutputType::Table
+            | OutputType::Markdown
+            | OutputType::Html => OutputType::Stdout,
         }
     }
 }
 
 impl Cli {
-    /// Creates a new `Cli` instance by parsing the command-line arguments and setting environment variables.
+    /// Creates a new `Cli` instance by parsing the command-line arguments.
     ///
-    /// This implementation parses the CLI arguments and attempts to set the corresponding environment variables.
-    /// If setting the environment variables fails, an error message is printed.
+    /// The values are passed on to `Config` as the CLI layer (see `Config::new`), nothing is written to the environment.
     ///
     /// # Returns
     ///
@@ -325,15 +555,83 @@ impl Cli {
     ///
     /// # Example
     ///
-    /// ```rust
+    /// ```ignore
     /// let cli = Cli::new();
     /// println!("{:?}", cli);
     /// ```
     pub fn new() -> Self {
-        let s = Self::parse();
-        s.to_env()
-            .unwrap_or_else(|e| error!("Error setting environment variables: {e}"));
-        s
+        Self::parse()
+    }
+
+    /// Whether the command line says enough to run without a configuration file.
+    ///
+    /// That is when a source (`source` or `latest_in`) and `fields` are given, and neither `config_file` nor `config_dir` is.
+    /// The config is then built from the defaults, the `CSV_CLI_*` environment variables and the CLI alone, and no
+    /// configuration file is read or created.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - Returns `true` if no configuration file is needed, otherwise `false`.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let cli = Cli::try_parse_from(["csv_parser_rs", "data.csv", "--fields", "Id,Status"])?;
+    /// assert!(cli.is_config_less());
+    /// ```
+    pub fn is_config_less(&self) -> bool {
+        self.config_file.is_none()
+            && self.config_dir.is_none()
+            && (self.source.is_some() || self.latest_in.is_some())
+            && !self.fields.is_empty()
+    }
+}
+
+/// Parses a `--rows` value, `START:END`, into the start and (exclusive) end of the range.
+///
+/// Either bound may be left out, for the first record or no end at all (e.g. `:100`, `1000:`).
+///
+/// # Arguments
+///
+/// * `range` - The value given to `--rows`.
+///
+/// # Returns
+///
+/// * `std::result::Result<(usize, usize), String>` - Returns the start and end, or a message for clap if they aren't
+///   numbers or the start is past the end.
+fn parse_row_range(range: &str) -> std::result::Result<(usize, usize), String> {
+    let (start, end) = range
+        .split_once(':')
+        .ok_or_else(|| format!("expected START:END, got {range:?}"))?;
+    let bound = |bound: &str, default: usize| match bound.trim() {
+        "" => Ok(default),
+        bound => bound
+            .parse::<usize>()
+            .map_err(|e| format!("invalid bound {bound:?} in {range:?}: {e}")),
+    };
+    let (start, end) = (bound(start, 0)?, bound(end, usize::MAX)?);
+    if start > end {
+        return Err(format!("the start of {range:?} is past its end"));
+    }
+    Ok((start, end))
+}
+
+/// Parses a `--filter` value, `COL=VAL`, into the column and the value.
+///
+/// Only the first `=` separates the two, so the value may contain more of them.
+///
+/// # Arguments
+///
+/// * `filter` - The value given to `--filter`.
+///
+/// # Returns
+///
+/// * `std::result::Result<(String, String), String>` - Returns the column and the value, or a message for clap if there's
+///   no `=` or the column is empty.
+fn parse_filter(filter: &str) -> std::result::Result<(String, String), String> {
+    match filter.split_once('=') {
+        Some((column, value)) if !column.is_empty() => Ok((column.to_string(), value.to_string())),
+        _ => Err(format!("expected COL=VAL, got {filter:?}")),
     }
 }
 
@@ -348,7 +646,7 @@ impl Default for Cli {
     ///
     /// # Example
     ///
-    /// ```rust
+    /// ```ignore
     /// let default_cli = Cli::default();
     /// println!("{:?}", default_cli);
     /// ```
@@ -357,6 +655,9 @@ impl Default for Cli {
     }
 }
 
+#[deprecated(
+    note = "CLI values are passed to `Config` directly, `CSV_CLI_*` variables are only read as an optional override layer"
+)]
 pub trait ToEnv {
     /// Trait to set environment variables based on the CLI arguments.
     ///
@@ -368,13 +669,14 @@ pub trait ToEnv {
     ///
     /// # Example
     ///
-    /// ```rust
+    /// ```ignore
     /// let cli = Cli::parse();
     /// cli.to_env().expect("Failed to set environment variables");
     /// ```
     fn to_env(&self) -> Result<()>;
 }
 
+#[allow(deprecated)]
 impl ToEnv for Cli {
     /// Implementation of the `ToEnv` trait for the `Cli` struct.
     ///
@@ -383,7 +685,7 @@ impl ToEnv for Cli {
     ///
     /// # Example
     ///
-    /// ```rust
+    /// ```ignore
     /// let cli = Cli::parse();
     /// cli.to_env().expect("Failed to set environment variables");
     /// ```
@@ -448,7 +750,7 @@ impl ToEnv for Cli {
 ///
 /// # Example
 ///
-/// ```rust
+/// ```ignore
 /// let styles = get_styles();
 /// ```
 pub fn get_styles() -> clap::builder::Styles {
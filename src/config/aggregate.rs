@@ -0,0 +1,72 @@
+use crate::prelude::{Deserialize, Serialize};
+
+/// How the values of the aggregated column are combined within each group (see `Aggregate`).
+///
+/// # Variants
+///
+/// * `Sum` - The sum of the values.
+/// * `Count` - The number of non-empty values.
+/// * `Avg` - The mean of the values.
+/// * `Min` - The smallest value.
+/// * `Max` - The largest value.
+///
+/// Every op but `Count` is numeric: values that aren't numbers are skipped (with a warning), and a group without a single
+/// number gets an empty cell.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AggregateOp {
+    Sum,
+    Count,
+    Avg,
+    Min,
+    Max,
+}
+
+impl AggregateOp {
+    /// The name of the op, as written in the config.
+    pub fn name(self) -> &'static str {
+        match self {
+            AggregateOp::Sum => "sum",
+            AggregateOp::Count => "count",
+            AggregateOp::Avg => "avg",
+            AggregateOp::Min => "min",
+            AggregateOp::Max => "max",
+        }
+    }
+}
+
+/// Rolls the output up into one row per group, holding the group's columns and an aggregate of another column.
+///
+/// # Fields
+///
+/// * `group_by` - The retained columns the rows are grouped on. Without any, every row is in one group.
+/// * `column` - The retained column whose values are aggregated.
+/// * `op` - How the values are combined, see `AggregateOp`.
+/// * `into` - The header of the aggregate column, `<op>_<column>` (e.g. `sum_Amount`) when unset.
+///
+/// # Example
+///
+/// ```json
+/// "aggregate": { "group_by": ["Region", "Status"], "column": "Amount", "op": "sum", "into": "Total" }
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Aggregate {
+    #[serde(default)]
+    pub group_by: Vec<String>,
+
+    pub column: String,
+
+    pub op: AggregateOp,
+
+    #[serde(default)]
+    pub into: Option<String>,
+}
+
+impl Aggregate {
+    /// The header of the aggregate column: `into`, or `<op>_<column>` when that's unset.
+    pub fn header(&self) -> String {
+        self.into
+            .clone()
+            .unwrap_or_else(|| format!("{}_{}", self.op.name(), self.column))
+    }
+}
@@ -0,0 +1,187 @@
+use std::path::PathBuf;
+
+use crate::cli::OutputType;
+use crate::config::core::clear_placeholder_keys;
+use crate::config::{Config, Delimiter};
+use crate::prelude::*;
+
+/// Builds a `Config` in code, for library users that don't have (or want) a JSON config file and CLI arguments.
+///
+/// Not to be confused with the `config` crate's own `ConfigBuilder`, which layers the config file and CLI overrides.
+/// Every setting not set here keeps its default, as if it were left out of the config file.
+///
+/// # Example
+///
+/// ```ignore
+/// let config = ConfigBuilder::new()
+///     .source("data/export.csv")
+///     .field("Id")
+///     .field("Status")
+///     .include_col("Status", ["Open", "Pending"])
+///     .unique_field("Id")
+///     .output(OutputType::Json, "output.json")
+///     .build()?;
+/// let data = csv_parser_rs::process_config(&config)?;
+/// ```
+#[derive(Debug, Clone)]
+pub struct ConfigBuilder {
+    config: Config,
+}
+
+impl ConfigBuilder {
+    /// Creates a builder with no `source` or `fields` and every other setting at its default.
+    pub fn new() -> Self {
+        let mut config = clear_placeholder_keys(Config::default());
+        config.source = PathBuf::new();
+        config.output_path = PathBuf::from("output.csv");
+
+        Self { config }
+    }
+
+    /// Sets the path of the source CSV file, or `-` to read from stdin.
+    pub fn source(mut self, path: impl Into<PathBuf>) -> Self {
+        self.config.source = path.into();
+        self
+    }
+
+    /// Adds a column to retain in the output, in the order they're added.
+    pub fn field(mut self, name: impl Into<String>) -> Self {
+        self.config.fields.push(name.into());
+        self
+    }
+
+    /// Keeps only the rows whose `col` holds one of `values` (see `include_cols_with`).
+    pub fn include_col<I, S>(mut self, col: impl Into<String>, values: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.config
+            .include_cols_with
+            .entry(col.into())
+            .or_default()
+            .extend(values.into_iter().map(Into::into));
+        self
+    }
+
+    /// Drops the rows whose `col` holds one of `values` (see `exclude_cols_with`).
+    pub fn exclude_col<I, S>(mut self, col: impl Into<String>, values: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.config
+            .exclude_cols_with
+            .entry(col.into())
+            .or_default()
+            .extend(values.into_iter().map(Into::into));
+        self
+    }
+
+    /// Adds a column that rows are deduplicated on, it must also be retained with `field`.
+    pub fn unique_field(mut self, name: impl Into<String>) -> Self {
+        self.config.unique_fields.push(name.into());
+        self
+    }
+
+    /// Sets where the output is written, `path` is given the extension of `output_type` when it doesn't have it.
+    pub fn output(mut self, output_type: OutputType, path: impl Into<PathBuf>) -> Self {
+        self.config.output_type = output_type;
+        self.config.output_path = path.into();
+        self
+    }
+
+    /// Sets whether the first row of the source is its header row (the default).
+    pub fn has_headers(mut self, has_headers: bool) -> Self {
+        self.config.has_headers = has_headers;
+        self
+    }
+
+    /// Sets the field delimiter of the source.
+    pub fn delimiter(mut self, delimiter: Delimiter) -> Self {
+        self.config.delimiter = delimiter;
+        self
+    }
+
+    /// Stops reading after this many rows have passed the filters.
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.config.limit = Some(limit);
+        self
+    }
+
+    /// Validates the settings and returns the finished `Config`.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Config>` - The `Config` on success, or an `Error` when a required setting is missing.
+    ///
+    /// # Errors
+    ///
+    /// * `Error::ConfigSource` - No `source` was set.
+    /// * `Error::InvalidConfig` - No `field` was added, or a `unique_field` isn't one of the `field`s.
+    pub fn build(self) -> Result<Config> {
+        let config = self.config;
+
+        if config.source.as_os_str().is_empty() {
+            return Err(Error::ConfigSource);
+        }
+        if config.fields.is_empty() {
+            return Err(Error::InvalidConfig("at least one field must be retained".to_string()));
+        }
+        if let Some(missing) = config.unique_fields.iter().find(|f| !config.fields.contains(f)) {
+            return Err(Error::InvalidConfig(format!("the unique field {missing:?} isn't one of the retained fields")));
+        }
+
+        Ok(config.with_output_type_extension())
+    }
+}
+
+impl Default for ConfigBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod builder_tests {
+    use super::*;
+
+    #[test]
+    fn test_build_config() {
+        let config = ConfigBuilder::new()
+            .source("data/export.csv")
+            .field("Id")
+            .field("Status")
+            .include_col("Status", ["Open"])
+            .include_col("Status", ["Pending"])
+            .exclude_col("Id", ["0"])
+            .unique_field("Id")
+            .output(OutputType::Json, "out")
+            .limit(10)
+            .build()
+            .unwrap();
+
+        assert_eq!(config.source, PathBuf::from("data/export.csv"));
+        assert_eq!(config.fields, vec!["Id", "Status"]);
+        assert_eq!(config.include_cols_with["Status"], vec!["Open", "Pending"]);
+        assert_eq!(config.exclude_cols_with["Id"], vec!["0"]);
+        assert_eq!(config.unique_fields, vec!["Id"]);
+        assert_eq!(config.output_path, PathBuf::from("out.json"));
+        assert_eq!(config.limit, Some(10));
+        assert!(config.has_headers);
+    }
+
+    #[test]
+    fn test_build_validates_required_settings() {
+        assert!(matches!(ConfigBuilder::new().field("Id").build(), Err(Error::ConfigSource)));
+        assert!(matches!(ConfigBuilder::new().source("data.csv").build(), Err(Error::InvalidConfig(_))));
+        assert!(matches!(
+            ConfigBuilder::new()
+                .source("data.csv")
+                .field("Id")
+                .unique_field("Name")
+                .build(),
+            Err(Error::InvalidConfig(_))
+        ));
+    }
+}
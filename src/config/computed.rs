@@ -0,0 +1,131 @@
+use crate::prelude::*;
+
+/// A column added to the output, computed for every row from the source columns.
+///
+/// The `expr` is one or more terms joined by `+`, which are concatenated. A term is either a string literal in double
+/// quotes (`\"` and `\\` escape a quote and a backslash), or the name of a source column, which doesn't have to be in
+/// `fields`. Like `fields`, a column can also be referenced by its index, as `#N` or `$N`.
+///
+/// # Fields
+///
+/// * `name` - The header of the computed column, which is appended after the retained columns.
+/// * `expr` - The expression the column's value is computed with.
+///
+/// # Example
+///
+/// ```json
+/// "computed": [
+///   { "name": "FullName", "expr": "FirstName + \" \" + LastName" }
+/// ]
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ComputedColumn {
+    pub name: String,
+
+    pub expr: String,
+}
+
+/// A single term of a `ComputedColumn` expression.
+///
+/// # Variants
+///
+/// * `Column` - The value of the source column with this name (or `#N` / `$N` index).
+/// * `Literal` - A constant string.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExprTerm {
+    Column(String),
+    Literal(String),
+}
+
+impl ComputedColumn {
+    /// Parses `expr` into the terms that are concatenated.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Vec<ExprTerm>>` - Returns the terms in order, or an `Error` if `expr` can't be parsed.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an `Error::InvalidConfig` if `expr` has an empty term (e.g. a trailing `+`),
+    /// an unterminated string literal, or anything but a `+` after a string literal.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let column = ComputedColumn { name: "FullName".into(), expr: r#"FirstName + " " + LastName"#.into() };
+    /// assert_eq!(column.terms()?.len(), 3);
+    /// ```
+    pub fn terms(&self) -> Result<Vec<ExprTerm>> {
+        let invalid = |reason: &str| Error::InvalidConfig(format!("the computed column {:?} {reason}", self.name));
+
+        let mut terms = Vec::new();
+        let mut chars = self.expr.chars().peekable();
+        loop {
+            while chars.next_if(|c| c.is_whitespace()).is_some() {}
+
+            if chars.next_if_eq(&'"').is_some() {
+                let mut literal = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => literal.extend(chars.next()),
+                        Some(c) => literal.push(c),
+                        None => return Err(invalid("has an unterminated string literal")),
+                    }
+                }
+                terms.push(ExprTerm::Literal(literal));
+            } else {
+                let mut column = String::new();
+                while let Some(c) = chars.next_if(|&c| c != '+') {
+                    column.push(c);
+                }
+                if column.trim().is_empty() {
+                    return Err(invalid("has an empty term"));
+                }
+                terms.push(ExprTerm::Column(column.trim().to_string()));
+            }
+
+            while chars.next_if(|c| c.is_whitespace()).is_some() {}
+            match chars.next() {
+                None => return Ok(terms),
+                Some('+') => {}
+                Some(_) => return Err(invalid("has something other than `+` after a string literal")),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod computed_tests {
+    use super::*;
+
+    fn terms(expr: &str) -> Result<Vec<ExprTerm>> {
+        ComputedColumn {
+            name: "Computed".to_string(),
+            expr: expr.to_string(),
+        }
+        .terms()
+    }
+
+    #[test]
+    fn test_terms() {
+        assert_eq!(
+            terms(r#"First Name + " " + LastName+"\"x\\y\"""#).unwrap(),
+            vec![
+                ExprTerm::Column("First Name".to_string()),
+                ExprTerm::Literal(" ".to_string()),
+                ExprTerm::Column("LastName".to_string()),
+                ExprTerm::Literal(r#""x\y""#.to_string()),
+            ]
+        );
+        assert_eq!(terms(r#""a+b""#).unwrap(), vec![ExprTerm::Literal("a+b".to_string())]);
+        assert_eq!(terms("#0").unwrap(), vec![ExprTerm::Column("#0".to_string())]);
+    }
+
+    #[test]
+    fn test_invalid_terms() {
+        for expr in ["", "Id +", "+ Id", "Id + + Name", r#""open"#, r#""a" Id"#] {
+            assert!(matches!(terms(expr), Err(Error::InvalidConfig(_))), "{expr:?}");
+        }
+    }
+}
@@ -2,14 +2,25 @@
 use std::collections::HashMap;
 use std::fmt::{Debug, Display};
 // use std::hash::Hash;
-use std::path::PathBuf;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 use config::builder::DefaultState;
 
-use crate::cli::{Cli, OutputType};
-use crate::config::extract_cached_config_value;
-use crate::config::file_path_finds::parse_user_variable_path;
+use crate::cli::{Cli, ConfigFormat, OutputType};
+use crate::config::explain::{explain_config, render_explained};
+use crate::config::file_path_finds::{
+    expand_glob, is_glob_pattern, is_stream_file, latest_in_directory, parse_user_variable_path, with_output_extension,
+};
+use crate::config::remote::{RemoteConfigCache, is_remote};
+use crate::config::{
+    AggOp, Aggregate, BooleanField, ColumnValidation, ComputedColumn, DateRange, DedupBloom, DedupKeep, Delimiter,
+    FilterLogic, FilterRule, InputType, MatchSelect, OnError, RunningTotal, SortKey, Trim, extract_cached_config_value,
+    is_relative,
+};
 use crate::prelude::{Deserialize, Serialize, *};
+use crate::processing::OutPathShape;
 
 /// Represents the configuration settings for the application.
 ///
@@ -18,13 +29,86 @@ use crate::prelude::{Deserialize, Serialize, *};
 ///
 /// # Fields
 ///
-/// * `source` - The path to the source CSV file.
+/// * `source` - The path to the source CSV file, a directory (the newest `.csv` within it is used), or `-` to read from stdin.
+///   A glob (e.g. `data/export_*.csv`) or a list of paths reads several files with the same headers, one after another.
+/// * `sources` - Every file a glob or list `source` resolved to, in the order they are read. Empty for a single source.
 /// * `output_type` - The type of output (e.g., stdout, CSV file).
-/// * `output_path` - The path to the output file.
+/// * `output_path` - The path to the output file, or a folder to write a file named after the source into
+///   (see `resolve_output_path`).
+/// * `json_pretty` - Whether JSON output is pretty-printed rather than compact.
+/// * `max_col_width` - The width `table` output cells are truncated to, so wide columns don't wrap in the terminal.
+/// * `html_standalone` - Whether `html` output is a complete HTML document, rather than just the `<table>`.
+/// * `empty_placeholder` - What empty cells are written as (e.g. `NULL` or `\N`), in every output format. Filters and
+///   deduplication still see the empty cells.
+/// * `write_headers` - Whether CSV, TSV and stdout output start with the header row (the default), or only hold the rows.
+///   Output of a source without `has_headers` never starts with a header row.
+/// * `append` - Add the rows to the end of an existing CSV or TSV output file, rather than replacing it.
+/// * `output_subdir_date` - A chrono format (e.g. `%Y-%m-%d`) for a dated subdirectory the output file is placed in.
+/// * `source_scan_limit` - The maximum number of directory entries scanned when `source` contains a `{regex}`.
+/// * `fuzzy_match` - When nothing matches a `{regex}` in `source`, use the file with the closest name instead of failing.
+/// * `match_select` - Which file is used when several match a `{regex}` in `source` (newest by default), see `MatchSelect`.
 /// * `has_headers` - A boolean indicating whether the CSV file has headers.
-/// * `fields` - A vector of field names to be retained from the CSV file.
+/// * `input_type` - The format of the source, CSV (the default) or a JSON array of objects, see `InputType`.
+/// * `gzip` - Decompress the source as gzip, even without a `.gz` extension (e.g. when reading from stdin).
+/// * `encoding` - The text encoding of the source (e.g. `utf-16le`, `latin1`), it's transcoded to UTF-8 as it's read.
+///   Unset, a UTF-16 byte order mark is detected and anything else is read as UTF-8.
+/// * `skip_rows` - The number of leading lines (e.g. banners or metadata) to skip before the header row.
+/// * `delimiter` - The single-character field delimiter of the source file (default `,`, use `\t` for tab).
+/// * `output_delimiter` - The single-character field delimiter the output is written with, instead of `,` (or tab for `tsv`).
+/// * `quote` - The quote character of the source file, e.g. `'`. Defaults to `"`.
+/// * `escape` - When set, the character that escapes a quote inside a quoted field, e.g. `\`.
+/// * `double_quote` - Whether a doubled quote (`""`) inside a quoted field is read as one quote (default `true`).
+/// * `flexible` - Accept records with a different number of fields than the header row, missing fields read as empty.
+/// * `on_error` - Whether a malformed record aborts the run (default), or is skipped quietly or with a warning, see `OnError`.
+/// * `trim` - Whether the whitespace around header names and/or values is trimmed as they're read, see `Trim`.
+/// * `fields` - A vector of field names to be retained from the CSV file. `#N` or `$N` selects the column at zero-based index `N`.
+/// * `strict_fields` - Fail when a `fields` entry, `include_cols_with` key, `require_non_empty` / `require_empty` column or `computed` column reference is not a column of the source, rather than warn.
 /// * `unique_fields` - A vector of field names to be used for deduplication.
+/// * `dedup_keep` - Whether the `first` (default) or `last` row of each duplicated `unique_fields` key is kept.
+/// * `dedup_bloom` - When set, likely-duplicate `unique_fields` keys are already dropped as the source is read, see `DedupBloom`.
+/// * `dedup_all` - Without any `unique_fields`, deduplicate on the whole retained row instead of not at all.
+/// * `dedup_count` - Instead of silently dropping duplicates, append a `_count` column with how many rows shared each key.
+/// * `dedup_aggregate` - A hashmap of column name to the `AggOp` combining its values across each duplicated key, kept in the surviving row.
 /// * `include_cols_with` - A hashmap where the key is a column name and the value is a vector of valid values for filtering.
+/// * `numeric_filters` - A hashmap of column name to inclusive `[min, max]` bounds, where a `null` bound is unbounded.
+/// * `date_filters` - A hashmap of column name to the inclusive `DateRange` its dates must fall within.
+/// * `date_format` - The chrono format the `date_filters` cells are parsed with (default `%Y-%m-%d`).
+/// * `case_insensitive_filters` - Compare `include_cols_with` values ignoring case. Column names are still matched exactly,
+///   unless `case_insensitive_headers` is set.
+/// * `case_insensitive_headers` - Match the column names in the config against the source headers ignoring case.
+/// * `exclude_cols_with` - The inverse of `include_cols_with`, a row is dropped if a column's value is in that column's blacklist.
+/// * `require_non_empty` - Columns that must hold a value (not just whitespace) for a row to be kept, whether or not they're retained.
+/// * `require_empty` - Columns that must be empty (or only whitespace) for a row to be kept, whether or not they're retained.
+/// * `filters` - A list of per-column filters, each with its own `MatchMode` (exact, contains, regex, numeric range, prefix).
+/// * `filter_logic` - Whether a row must pass all filters (`and`) or any filter (`or`).
+/// * `filter_min_match` - When set, a row must pass at least this many filters, taking the place of `filter_logic`.
+/// * `post_filters` - Like `filters`, but evaluated against the output columns after projection.
+/// * `force_quote_fields` - Output columns whose values are always quoted, e.g. to keep leading zeros in Excel.
+/// * `rename` - A hashmap of source column name to the name its retained column is given in the output headers.
+/// * `boolean_fields` - A hashmap of column name to a `BooleanField` mapping used to normalize boolean-like values on output.
+/// * `computed` - Columns appended after the retained ones, each computed per row from the source columns, see `ComputedColumn`.
+/// * `limit` - When set, stop reading the source once this many rows have been kept (before deduplication).
+/// * `head` - When set, only the first N rows are kept. Like `limit`, reading stops once they have been kept.
+/// * `row_range` - When set, only the data records with a (zero-based) index in `[start, end)` are processed, and reading stops at `end`.
+/// * `tail` - When set, only the last N rows are kept. Every row has to be read (and held) to find them.
+/// * `sample` - When set, keep this many randomly chosen rows, after filtering and deduplication.
+/// * `seed` - The seed for `sample`, making the pick reproducible. A random seed is used when unset.
+/// * `sort_by` - The keys to sort the rows by, in order of priority. Each is a column name, or a `SortKey` with its own direction and kind.
+/// * `sort_desc` - Sort the `sort_by` keys without a `direction` from largest to smallest.
+/// * `aggregate` - When set, the output is rolled up into one row per group with an aggregate column, see `Aggregate`.
+/// * `running_total` - When set, appends a column holding the cumulative sum of a numeric column, see `RunningTotal`.
+/// * `delta_against` - When set, only rows that are new or changed compared to this previous output are kept.
+/// * `delta_report_removed` - With `delta_against`, also output the keys that no longer exist, tagged `removed`.
+/// * `with_stats` - When set, per-column statistics of the output are written to this path as well.
+/// * `validations` - A hashmap of column name to the `ColumnValidation` its output values are checked against.
+/// * `validate_report` - When set, every `validations` violation is written to this path (CSV, or JSON for `.json`).
+/// * `expect_rows` - When set, the run fails unless exactly this many rows are output.
+/// * `expect_rows_range` - When set, the run fails unless the number of output rows is within `[min, max]` (inclusive).
+/// * `dedup_preview` - When set, the top N duplicated keys are reported before deduplicating.
+/// * `external` - Sort and deduplicate via temporary chunk files on disk, for inputs too large to hold in memory.
+/// * `external_memory_mb` - The approximate memory budget (in MiB) for `external` mode before a chunk is spilled to disk.
+/// * `parallel` - Filter and project batches of records on all cores, instead of one record at a time.
+/// * `progress` - Show the progress of reading the source on stderr, see `SourceProgress`.
 ///
 /// # Example
 ///
@@ -58,23 +142,227 @@ use crate::prelude::{Deserialize, Serialize, *};
 /// ```
 #[derive(Serialize, Deserialize, Clone)]
 pub struct Config {
-    #[serde(rename = "source")]
+    #[serde(rename = "source", deserialize_with = "path_or_first_of_list")]
     pub source: PathBuf,
 
+    #[serde(skip)]
+    pub sources: Vec<PathBuf>,
+
     #[serde(rename = "output_type", default)]
     pub output_type: OutputType,
 
     #[serde(rename = "output_path", default)]
     pub output_path: PathBuf,
 
+    #[serde(default)]
+    pub json_pretty: bool,
+
+    #[serde(default)]
+    pub max_col_width: Option<usize>,
+
+    #[serde(default)]
+    pub html_standalone: bool,
+
+    #[serde(default)]
+    pub empty_placeholder: Option<String>,
+
+    #[serde(default = "default_write_headers")]
+    pub write_headers: bool,
+
+    #[serde(default)]
+    pub append: bool,
+
+    #[serde(default)]
+    pub output_subdir_date: Option<String>,
+
+    #[serde(default)]
+    pub source_scan_limit: Option<usize>,
+
+    #[serde(default)]
+    pub fuzzy_match: bool,
+
+    #[serde(default)]
+    pub match_select: MatchSelect,
+
     #[serde(rename = "has_headers", default)]
     pub has_headers: bool,
 
+    #[serde(default)]
+    pub input_type: InputType,
+
+    #[serde(default)]
+    pub gzip: bool,
+
+    #[serde(default)]
+    pub encoding: Option<String>,
+
+    #[serde(default)]
+    pub skip_rows: usize,
+
+    #[serde(default)]
+    pub delimiter: Delimiter,
+
+    #[serde(default)]
+    pub output_delimiter: Option<Delimiter>,
+
+    #[serde(default)]
+    pub quote: Option<char>,
+
+    #[serde(default)]
+    pub escape: Option<char>,
+
+    #[serde(default = "default_double_quote")]
+    pub double_quote: bool,
+
+    #[serde(default)]
+    pub flexible: bool,
+
+    #[serde(default)]
+    pub on_error: OnError,
+
+    #[serde(default)]
+    pub trim: Trim,
+
+    #[serde(default)]
     pub fields: Vec<String>,
 
+    #[serde(default)]
+    pub strict_fields: bool,
+
+    #[serde(default)]
     pub unique_fields: Vec<String>,
 
+    #[serde(default)]
+    pub dedup_keep: DedupKeep,
+
+    #[serde(default)]
+    pub dedup_all: bool,
+
+    #[serde(default)]
+    pub dedup_count: bool,
+
+    #[serde(default, deserialize_with = "map_or_unit")]
+    pub dedup_aggregate: HashMap<String, AggOp>,
+
+    #[serde(default)]
+    pub dedup_bloom: Option<DedupBloom>,
+
+    #[serde(deserialize_with = "map_or_unit")]
     pub include_cols_with: HashMap<String, Vec<String>>,
+
+    #[serde(default, deserialize_with = "map_or_unit")]
+    pub numeric_filters: HashMap<String, (Option<f64>, Option<f64>)>,
+
+    #[serde(default, deserialize_with = "map_or_unit")]
+    pub date_filters: HashMap<String, DateRange>,
+
+    #[serde(default = "default_date_format")]
+    pub date_format: String,
+
+    #[serde(default)]
+    pub case_insensitive_filters: bool,
+
+    #[serde(default)]
+    pub case_insensitive_headers: bool,
+
+    #[serde(default, deserialize_with = "map_or_unit")]
+    pub exclude_cols_with: HashMap<String, Vec<String>>,
+
+    #[serde(default)]
+    pub require_non_empty: Vec<String>,
+
+    #[serde(default)]
+    pub require_empty: Vec<String>,
+
+    #[serde(default)]
+    pub filters: Vec<FilterRule>,
+
+    #[serde(default)]
+    pub filter_logic: FilterLogic,
+
+    #[serde(default)]
+    pub filter_min_match: Option<usize>,
+
+    #[serde(default)]
+    pub post_filters: Vec<FilterRule>,
+
+    #[serde(default, deserialize_with = "map_or_unit")]
+    pub rename: HashMap<String, String>,
+
+    #[serde(default, deserialize_with = "map_or_unit")]
+    pub boolean_fields: HashMap<String, BooleanField>,
+
+    #[serde(default)]
+    pub computed: Vec<ComputedColumn>,
+
+    #[serde(default)]
+    pub force_quote_fields: Vec<String>,
+
+    #[serde(default)]
+    pub limit: Option<usize>,
+
+    #[serde(default)]
+    pub head: Option<usize>,
+
+    #[serde(default)]
+    pub row_range: Option<(usize, usize)>,
+
+    #[serde(default)]
+    pub tail: Option<usize>,
+
+    #[serde(default)]
+    pub sample: Option<usize>,
+
+    #[serde(default)]
+    pub seed: Option<u64>,
+
+    #[serde(default)]
+    pub sort_by: Vec<SortKey>,
+
+    #[serde(default)]
+    pub sort_desc: bool,
+
+    #[serde(default)]
+    pub aggregate: Option<Aggregate>,
+
+    #[serde(default)]
+    pub running_total: Option<RunningTotal>,
+
+    #[serde(default)]
+    pub delta_against: Option<PathBuf>,
+
+    #[serde(default)]
+    pub delta_report_removed: bool,
+
+    #[serde(default)]
+    pub with_stats: Option<PathBuf>,
+
+    #[serde(default, deserialize_with = "map_or_unit")]
+    pub validations: HashMap<String, ColumnValidation>,
+
+    #[serde(default)]
+    pub validate_report: Option<PathBuf>,
+
+    #[serde(default)]
+    pub expect_rows: Option<usize>,
+
+    #[serde(default)]
+    pub expect_rows_range: Option<(usize, usize)>,
+
+    #[serde(default)]
+    pub dedup_preview: Option<usize>,
+
+    #[serde(default)]
+    pub external: bool,
+
+    #[serde(default = "default_external_memory_mb")]
+    pub external_memory_mb: usize,
+
+    #[serde(default)]
+    pub parallel: bool,
+
+    #[serde(default)]
+    pub progress: bool,
 }
 
 impl Config {
@@ -82,8 +370,10 @@ impl Config {
     ///
     /// This function creates a new `Config` instance - if CLI Arguments are provided they're used to override the configuration file.
     /// If no CLI arguments are provided, the function will search in the default location for one (or create one if it doesn't exist).
-    /// If the conversion is successful, it checks if the `output_path` ends with a `.csv` extension.
-    /// If not, it sets the extension to `.csv`.
+    /// If the conversion is successful, it checks if the `output_path` ends with the extension matching
+    /// the `output_type` (`.tsv`, `.json` or `.ndjson` for those outputs, `.txt` for a table, `.md` for Markdown, `.html` for HTML,
+    /// `.csv` otherwise). If not, it sets
+    /// that extension. An `output_path` of `-` (JSON, NDJSON, a table, Markdown or HTML to stdout) is left as is.
     ///
     /// # Arguments
     ///
@@ -95,27 +385,105 @@ impl Config {
     ///
     /// # Example
     ///
-    /// ```rust
+    /// ```ignore
     /// let cli = Cli::parse();
     /// let config = Config::new(cli).expect("Failed to create config");
     /// ```
     pub fn new(cli: Cli) -> Result<Self> {
         let config_from_cli = Self::try_from(cli);
 
-        let mut config = match config_from_cli {
+        let config = match config_from_cli {
             Ok(c) => c,
             Err(e) => {
                 return Err(e);
             }
         };
 
-        if config.output_path.ends_with(".csv") {
-            Ok(config)
+        Ok(config.with_output_type_extension())
+    }
+
+    /// Gives `output_path` the extension of `output_type` (e.g. `output.json` for `json`), unless it already has it,
+    /// the output is written to stdout, or it names a folder (whose file name is derived from the source later on,
+    /// see `resolve_output_path`).
+    pub(crate) fn with_output_type_extension(mut self) -> Self {
+        let extension = match self.output_type {
+            OutputType::Tsv => "tsv",
+            OutputType::Json => "json",
+            OutputType::Ndjson => "ndjson",
+            OutputType::Table => "txt",
+            OutputType::Markdown => "md",
+            OutputType::Html => "html",
+            OutputType::Stdout | OutputType::Csv => "csv",
+        };
+
+        if !self.output_path.ends_with(format!(".{extension}"))
+            && !self.writes_to_stdout()
+            && OutPathShape::of(&self.output_path) != OutPathShape::Folder
+        {
+            self.output_path = with_output_extension(&self.output_path, extension);
+        }
+        self
+    }
+
+    /// Returns `true` when the output should be written to stdout rather than a file (`output_path` is `-`).
+    pub fn writes_to_stdout(&self) -> bool {
+        self.output_path.as_os_str() == STDIN_SOURCE
+    }
+
+    /// Returns `true` when the source should be read from stdin rather than a file (`source` is `-`).
+    pub fn reads_from_stdin(&self) -> bool {
+        self.source.as_os_str() == STDIN_SOURCE
+    }
+
+    /// Returns `true` when a source is gzip compressed, going by the `gzip` setting or a `.gz` extension.
+    pub fn reads_gzip(&self, path: &Path) -> bool {
+        self.gzip || path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("gz"))
+    }
+
+    /// Returns `true` when the first row of the source names its columns, as it always does for a JSON source.
+    pub fn has_header_row(&self) -> bool {
+        self.has_headers || self.input_type == InputType::Json
+    }
+
+    /// Returns `true` when the (already normalized) `header` is the column `name` refers to: the same name once
+    /// normalized with `normalize_header`, or with `case_insensitive_headers`, the same name ignoring case.
+    pub fn header_matches(&self, header: &str, name: &str) -> bool {
+        let name = normalize_header(name);
+        if self.case_insensitive_headers {
+            header.to_lowercase() == name.to_lowercase()
         } else {
-            config.output_path.set_extension("csv");
-            Ok(config)
+            header == name
         }
     }
+
+    /// Returns `true` when the rows are to be deduplicated: on the `unique_fields` when there are any, or on the whole
+    /// row when `dedup_all` is set.
+    pub fn deduplicates(&self) -> bool {
+        !self.unique_fields.is_empty() || self.dedup_all
+    }
+
+    /// Returns `true` when the source is a FIFO (named pipe) or other special file, which can only be read as a stream.
+    pub fn reads_from_stream(&self) -> bool {
+        is_stream_file(&self.source)
+    }
+}
+
+/// Normalizes a column name for matching, removing control characters (e.g. a stray `\r` from CRLF line endings)
+/// and surrounding whitespace, so that `"ClaimReason "` and `"ClaimReason"` name the same column.
+///
+/// # Arguments
+///
+/// * `name` - A header of the source, or a column name from the config.
+///
+/// # Returns
+///
+/// * `String` - The normalized name.
+pub(crate) fn normalize_header(name: &str) -> String {
+    name.chars()
+        .filter(|c| !c.is_control())
+        .collect::<String>()
+        .trim()
+        .to_string()
 }
 
 /// Ensures the existence of a configuration file in the specified directory.
@@ -125,30 +493,44 @@ impl Config {
 /// configuration file. If the file already exists and is not empty, it simply returns the path
 /// to the configuration file.
 ///
+/// Without `create` (`--no-config`), nothing is written: a missing or empty configuration file is an error instead.
+///
 /// # Arguments
 ///
 /// * `current_dir` - A `PathBuf` representing the current directory where the configuration file should be located.
+/// * `create` - Whether a default configuration file is written when there is none.
 ///
 /// # Returns
 ///
 /// * `Result<PathBuf>` - Returns the path to the configuration file on success, or an `Error` on failure.
 ///
+/// # Errors
+///
+/// Returns `Error::NoConfigFile` if there is no configuration file and `create` is off, or `Error::Io` if one can't be
+/// written (e.g. in a read-only directory).
+///
 /// # Example
 ///
-/// ```rust
+/// ```ignore
 /// let current_dir = std::env::current_dir().unwrap();
-/// let config_path = config_file(current_dir).expect("Failed to ensure config file");
+/// let config_path = config_file(current_dir, true).expect("Failed to ensure config file");
 /// println!("Config file is located at: {:?}", config_path);
 /// ```
-pub(crate) fn config_file(current_dir: PathBuf) -> Result<PathBuf> {
+pub(crate) fn config_file(current_dir: PathBuf, create: bool) -> Result<PathBuf> {
     let def_config = Config::default();
     let config_folder = current_dir.join(DEFAULT_CONFIG_DIR);
+    let config_file = config_folder.join(DEFAULT_CONFIG_FILE);
+    let missing = !config_file.exists() || config_file.metadata()?.len() == 0;
+    if missing && !create {
+        error!("\n{}", NO_CONFIG_FILE_MSG);
+        return Err(Error::NoConfigFile(config_file));
+    }
+
     if !config_folder.exists() {
-        std::fs::create_dir_all(&config_folder)?;
+        std::fs::create_dir_all(&config_folder).inspect_err(|_| warn_read_only(&config_folder))?;
     }
-    let config_file = config_folder.join(DEFAULT_CONFIG_FILE);
-    if !config_file.exists() || config_file.metadata()?.len() == 0 {
-        std::fs::write(&config_file, def_config.to_string())?;
+    if missing {
+        std::fs::write(&config_file, def_config.to_string()).inspect_err(|_| warn_read_only(&config_folder))?;
         let msg = "Config file could not be found or had no content, one has been generated for you at:";
         error!("{}\n{:?}", msg, config_file.display());
         return Ok(current_dir);
@@ -157,6 +539,15 @@ pub(crate) fn config_file(current_dir: PathBuf) -> Result<PathBuf> {
     Ok(config_file)
 }
 
+/// Points at the alternatives when the default configuration file can't be written, e.g. in a read-only directory.
+fn warn_read_only(config_folder: &Path) {
+    warn!(
+        "Couldn't write a default config file to {}, pass a --config, or the source and --fields on the command line \
+         (with --no-config, writing one isn't tried)",
+        config_folder.display()
+    );
+}
+
 impl TryFrom<PathBuf> for Config {
     type Error = Error;
 
@@ -164,6 +555,7 @@ impl TryFrom<PathBuf> for Config {
     ///
     /// This function reads the configuration file from the specified path and deserializes it
     /// into a `Config` instance. If the file cannot be read or deserialized, an error is returned.
+    /// The file is parsed as JSON, YAML or TOML depending on its extension, see `config_file_format`.
     ///
     /// # Arguments
     ///
@@ -175,12 +567,13 @@ impl TryFrom<PathBuf> for Config {
     ///
     /// # Example
     ///
-    /// ```rust
+    /// ```ignore
     /// let config_path = PathBuf::from("config.json");
     /// let config = Config::try_from(config_path).expect("Failed to load config");
     /// ```
     fn try_from(path: PathBuf) -> Result<Self> {
-        let builder = config::Config::builder().add_source(config::File::from(path));
+        let format = config_file_format(&path, None);
+        let builder = config::Config::builder().add_source(config::File::from(path).format(format));
         let config = builder
             .build()
             .map_err(Error::ConfigParse)
@@ -197,7 +590,10 @@ impl TryFrom<Cli> for Config {
     /// Attempts to create a `Config` instance from the provided CLI arguments.
     ///
     /// This function first creates a default `Config` instance and then overrides its values
-    /// with the CLI arguments. It also ensures that the configuration file exists and is valid.
+    /// with the CLI arguments. It also ensures that the configuration file exists and is valid,
+    /// unless the CLI arguments are enough on their own (see `Cli::is_config_less`), in which case
+    /// no configuration file is read or created. With `--no-config`, a missing configuration file isn't created
+    /// either, but is an error.
     ///
     /// # Arguments
     ///
@@ -209,29 +605,32 @@ impl TryFrom<Cli> for Config {
     ///
     /// # Example
     ///
-    /// ```rust
+    /// ```ignore
     /// let cli = Cli::parse();
     /// let config = Config::try_from(cli).expect("Failed to create config from CLI");
     /// ```
     fn try_from(cli: Cli) -> Result<Self> {
-        let default_config_base = Config::default();
-
-        let builder = config::Config::builder()
-            .add_source(config::Config::try_from(&default_config_base).map_err(Error::ConfigParse)?);
-
-        let mut builder = cli_valid(builder, &cli)?;
+        // Use the one the user provided (fetching it first if it's a URL), otherwise the default location -
+        // or none at all when the CLI says enough on its own
+        let config_path = match &cli.config_file {
+            Some(cli_config_file) if is_remote(cli_config_file) => {
+                let cache_dir = crate::config::current_dir()?
+                    .join(DEFAULT_CONFIG_DIR)
+                    .join(REMOTE_CONFIG_CACHE_DIR);
+                Some(
+                    RemoteConfigCache::new(cache_dir, Duration::from_secs(cli.config_cache_ttl))
+                        .fetch(cli_config_file.to_str().unwrap_or_default())?,
+                )
+            }
+            Some(cli_config_file) => Some(cli_config_file.clone()),
+            None if cli.is_config_less() => {
+                debug!("Source and fields given on the command line, not using a config file");
+                None
+            }
+            None => Some(config_file(crate::config::current_dir()?, !cli.no_config)?),
+        };
 
-        let config_file = config_file(crate::config::current_dir()?)?;
-        // and finally - we attempt to parse the config file
-
-        if let Some(cli_config_file) = &cli.config_file {
-            builder = builder.set_override("config_file", cli_config_file.to_str().unwrap())?; // Set the new one the user provided
-            builder = builder.add_source(config::File::from(cli_config_file.clone()));
-        // Use the new one as the base
-        } else {
-            builder = builder.set_override("config_file", config_file.to_str().unwrap())?;
-            builder = builder.add_source(config::File::from(config_file));
-        }
+        let builder = layered_builder(&cli, config_path.as_deref())?;
 
         let config = match builder.build() {
             Ok(c) => c,
@@ -241,23 +640,54 @@ impl TryFrom<Cli> for Config {
             }
         };
 
-        let fixed = fix_multiple_path_subs(&config, vec!["source", "output_path"]).unwrap_or_else(|e| {
-            match e {
-                Error::ParsingPath(e) => warn!("Failed to fix multiple path substitutions: {}", e),
-                _ => panic!("Failed to fix multiple path substitutions"),
+        // A glob or list `source` is expanded into every file it names, the first of which stands in as `source`
+        let sources = expand_sources(&config)?;
+        let keys = if sources.is_empty() {
+            vec!["source", "output_path"]
+        } else {
+            vec!["output_path"]
+        };
+
+        let fixed = match fix_multiple_path_subs(&config, keys) {
+            Ok(fixed) => fixed,
+            Err(e @ Error::ScanLimitExceeded(_)) => return Err(e),
+            Err(Error::ParsingPath(e)) => {
+                warn!("Failed to fix multiple path substitutions: {}", e);
+                vec![]
             }
-            vec![]
-        });
+            Err(_) => panic!("Failed to fix multiple path substitutions"),
+        };
 
-        let fixed_source = fixed.to_owned().first().unwrap().clone();
-        let fixed_output_path = fixed.get(1).unwrap_or(&PathBuf::from("output.csv")).clone();
+        let (fixed_source, fixed_output_path) = match sources.first() {
+            Some(first) => (first.clone(), fixed.first()),
+            None => (fixed.first().unwrap().clone(), fixed.get(1)),
+        };
+        let fixed_output_path = fixed_output_path
+            .cloned()
+            .unwrap_or_else(|| PathBuf::from("output.csv"));
 
         debug!("Fixed source: {:#?}", fixed_source);
         debug!("Fixed output path: {:#?}", fixed_output_path);
 
+        if cli.explain_config {
+            let mut explained = explain_config(&cli, config_path.as_deref())?;
+            for entry in &mut explained {
+                match entry.key.as_str() {
+                    "source" => entry.value = fixed_source.display().to_string(),
+                    "output_path" => entry.value = fixed_output_path.display().to_string(),
+                    _ => {}
+                }
+            }
+            info!("Config explained (highest precedence first: cli > env > file > default):");
+            for line in render_explained(&explained).lines() {
+                info!("{line}");
+            }
+        }
+
         let mut config: Config = config.try_deserialize().expect("Failed to deserialize config");
 
         config.source.clone_from(&fixed_source);
+        config.sources = sources;
         config.output_path.clone_from(&fixed_output_path);
 
         config = clear_placeholder_keys(config);
@@ -266,23 +696,162 @@ impl TryFrom<Cli> for Config {
     }
 }
 
+/// Assembles every configuration layer into a single builder, in precedence order.
+///
+/// The layers are, from lowest to highest precedence:
+/// 1. The built-in defaults (`Config::default()`).
+/// 2. The configuration file at `config_path`. Without one, only `output_path` is defaulted (to `output.csv`),
+///    in place of the placeholder path of the defaults.
+/// 3. The `CSV_CLI_*` environment variables, see `env_layer`.
+/// 4. The CLI arguments, applied as overrides.
+///
+/// # Arguments
+///
+/// * `cli` - A reference to the `Cli` instance containing the command-line arguments.
+/// * `config_path` - The path to the configuration file to layer in, if any.
+///
+/// # Returns
+///
+/// * `Result<config::ConfigBuilder<DefaultState>>` - Returns the builder, ready to `build`, or an `Error` on failure.
+///
+/// # Example
+///
+/// ```ignore
+/// let config = layered_builder(&cli, Some(&config_path))?.build()?;
+/// ```
+pub(crate) fn layered_builder(cli: &Cli, config_path: Option<&Path>) -> Result<config::ConfigBuilder<DefaultState>> {
+    layered_builder_with_env(cli, config_path, env_layer())
+}
+
+/// `layered_builder` with the given environment layer, so tests don't have to set process-wide variables.
+fn layered_builder_with_env(
+    cli: &Cli,
+    config_path: Option<&Path>,
+    env: config::Environment,
+) -> Result<config::ConfigBuilder<DefaultState>> {
+    let default_config_base = Config::default();
+
+    let builder = config::Config::builder()
+        .add_source(config::Config::try_from(&default_config_base).map_err(Error::ConfigParse)?);
+
+    let builder = cli_valid(builder, cli)?;
+
+    // and finally - we attempt to parse the config file
+    let builder = match config_path {
+        Some(config_path) => builder
+            .set_override("config_file", config_path.to_str().unwrap())?
+            .add_source(config::File::from(config_path).format(config_file_format(config_path, cli.config_format))),
+        None => {
+            builder.add_source(config::File::from_str(r#"{ "output_path": "output.csv" }"#, config::FileFormat::Json))
+        }
+    };
+    let builder = builder.add_source(env);
+
+    Ok(builder)
+}
+
+/// The optional `CSV_CLI_*` environment variable layer, e.g. `CSV_CLI_OUTPUT_TYPE=tsv` sets `output_type`.
+///
+/// Each variable is the `CLI_ENV_PREFIX` followed by a config key in upper case. They override the config file,
+/// but not the CLI arguments. Empty variables are ignored.
+///
+/// # Returns
+///
+/// * `config::Environment` - The layer, ready to add as a source.
+pub(crate) fn env_layer() -> config::Environment {
+    config::Environment::with_prefix(CLI_ENV_PREFIX)
+        .try_parsing(true)
+        .ignore_empty(true)
+}
+
+/// Picks the format a config file is parsed as, from `--config-format` or otherwise its extension.
+///
+/// # Arguments
+///
+/// * `path` - The path of the config file.
+/// * `format` - The format given with `--config-format`, which wins over the extension.
+///
+/// # Returns
+///
+/// * `config::FileFormat` - YAML for a `.yaml` or `.yml` file, TOML for `.toml` (and the other formats of the `config` crate
+///   for their extensions), otherwise JSON.
+pub(crate) fn config_file_format(path: &Path, format: Option<ConfigFormat>) -> config::FileFormat {
+    if let Some(format) = format {
+        return format.into();
+    }
+
+    match path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(str::to_ascii_lowercase)
+        .as_deref()
+    {
+        Some("yaml" | "yml") => config::FileFormat::Yaml,
+        Some("toml") => config::FileFormat::Toml,
+        Some("ini") => config::FileFormat::Ini,
+        Some("ron") => config::FileFormat::Ron,
+        Some("json5") => config::FileFormat::Json5,
+        _ => config::FileFormat::Json,
+    }
+}
+
 #[allow(unused_assignments, clippy::redundant_else, clippy::manual_let_else)]
 fn fix_multiple_path_subs(config: &config::Config, keys: Vec<&str>) -> Result<Vec<PathBuf>> {
     let mut extracted = vec![];
 
     let mut last_path: Box<&str> = Box::default();
     for path in keys {
-        last_path = Box::new(path);
+        *last_path = path;
         debug!("Attempting to extract path: {}", path);
 
         let extracted_path = extract_cached_config_value(config, path)?;
         debug!("Extracted path: {}", extracted_path);
-        let fixed_path = match parse_user_variable_path(&extracted_path) {
+
+        if path == "source" && is_stdin_source(&extracted_path) {
+            debug!("Reading source from stdin");
+            extracted.push(PathBuf::from(STDIN_SOURCE));
+            continue;
+        }
+        // A named pipe is read as-is - matching it against a `{regex}` or scanning its folder makes no sense
+        if path == "source" && is_stream_file(Path::new(&extracted_path)) {
+            debug!("Reading source as a stream from: {extracted_path}");
+            extracted.push(PathBuf::from(extracted_path));
+            continue;
+        }
+        if path == "output_path" && extracted_path == STDIN_SOURCE {
+            debug!("Writing output to stdout");
+            extracted.push(PathBuf::from(STDIN_SOURCE));
+            continue;
+        }
+
+        let scan_limit = if path == "source" {
+            config.get::<Option<usize>>("source_scan_limit").ok().flatten()
+        } else {
+            None
+        };
+
+        let fuzzy_match = path == "source" && config.get::<bool>("fuzzy_match").unwrap_or_default();
+        let match_select = if path == "source" {
+            config.get::<MatchSelect>("match_select").unwrap_or_default()
+        } else {
+            MatchSelect::default()
+        };
+
+        let fixed_path = match parse_user_variable_path(&extracted_path, scan_limit, fuzzy_match, match_select) {
             Ok(f) => f,
+            Err(e @ Error::ScanLimitExceeded(_)) => return Err(e),
             Err(_) => {
                 trace!("LAST PATH: {}", last_path);
                 trace!("CURRENT PATH: {}", path);
-                if *last_path == path {
+                if path == "output_path" {
+                    // An output path that can't be resolved is written into the folder it names instead,
+                    // with a file name derived from the source (see `resolve_output_path`)
+                    let resolved = is_relative(&extracted_path)?;
+                    let folder = resolved.parent().unwrap_or(Path::new("."));
+                    warn!("Failed to extract path: {}, writing to the folder: {}", path, folder.display());
+                    extracted.push(folder.join(""));
+                    continue;
+                } else if *last_path == path {
                     warn!("Failed to extract path: {}", path);
                     let extension_idx = extracted_path.rfind('.');
                     // we want to append "out" after the filename, but before the .extension
@@ -302,11 +871,84 @@ fn fix_multiple_path_subs(config: &config::Config, keys: Vec<&str>) -> Result<Ve
             }
         };
         debug!("Fixed path: {:#?}", fixed_path);
+
+        // A source pointing at a folder is shorthand for "the newest csv in this folder"
+        let fixed_path = if path == "source" && fixed_path.is_dir() {
+            latest_in_directory(&fixed_path)?
+        } else {
+            fixed_path
+        };
         extracted.push(fixed_path);
     }
     Ok(extracted)
 }
 
+/// Expands a `source` that is a list of paths, or a glob, into every file it names.
+///
+/// Each entry of a list may itself be a glob or a `{regex}` pattern (which resolves to its newest match).
+///
+/// # Arguments
+///
+/// * `config` - The built configuration layers.
+///
+/// # Returns
+///
+/// * `Result<Vec<PathBuf>>` - Returns the files in the order they are read, or an empty `Vec` when `source` is a single path.
+///
+/// # Errors
+///
+/// Returns `Error::NoMatchingFiles` if a glob matches no files, or the errors of `parse_user_variable_path` for an entry.
+fn expand_sources(config: &config::Config) -> Result<Vec<PathBuf>> {
+    let scan_limit = config.get::<Option<usize>>("source_scan_limit").ok().flatten();
+    let fuzzy_match = config.get::<bool>("fuzzy_match").unwrap_or_default();
+    let match_select = config.get::<MatchSelect>("match_select").unwrap_or_default();
+    let patterns = match config.get::<Vec<String>>("source") {
+        Ok(list) => list,
+        Err(_) => match config.get::<String>("source") {
+            Ok(source) if is_glob_pattern(&source) => vec![source],
+            _ => return Ok(Vec::new()),
+        },
+    };
+
+    let mut sources = Vec::with_capacity(patterns.len());
+    for pattern in &patterns {
+        if is_glob_pattern(pattern) {
+            sources.extend(expand_glob(pattern, scan_limit)?);
+        } else {
+            sources.push(parse_user_variable_path(pattern, scan_limit, fuzzy_match, match_select)?);
+        }
+    }
+    if sources.is_empty() {
+        return Err(Error::NoMatchingFiles);
+    }
+
+    debug!("Reading {} sources: {:?}", sources.len(), sources);
+    Ok(sources)
+}
+
+/// Deserializes `source` from either a single path, or a list of paths of which the first is taken (see `expand_sources`).
+fn path_or_first_of_list<'de, D>(deserializer: D) -> std::result::Result<PathBuf, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum SourceRepr {
+        One(PathBuf),
+        Many(Vec<PathBuf>),
+    }
+
+    Ok(match SourceRepr::deserialize(deserializer)? {
+        SourceRepr::One(path) => path,
+        SourceRepr::Many(paths) => paths.into_iter().next().unwrap_or_default(),
+    })
+}
+
+/// `-` always means stdin, while an empty source only means stdin when something is being piped in.
+fn is_stdin_source(source: &str) -> bool {
+    source == STDIN_SOURCE || (source.trim().is_empty() && !std::io::stdin().is_terminal())
+}
+
 /// Validates and overrides configuration settings with CLI arguments.
 ///
 /// This function takes a `ConfigBuilder` and a `Cli` instance, and overrides the configuration
@@ -324,12 +966,15 @@ fn fix_multiple_path_subs(config: &config::Config, keys: Vec<&str>) -> Result<Ve
 ///
 /// # Example
 ///
-/// ```rust
+/// ```ignore
 /// let cli = Cli::parse();
 /// let builder = config::Config::builder();
 /// let builder = cli_valid(builder, &cli).expect("Failed to validate CLI arguments");
 /// ```
-fn cli_valid(builder: config::ConfigBuilder<DefaultState>, cli: &Cli) -> Result<config::ConfigBuilder<DefaultState>> {
+pub(crate) fn cli_valid(
+    builder: config::ConfigBuilder<DefaultState>,
+    cli: &Cli,
+) -> Result<config::ConfigBuilder<DefaultState>> {
     let mut builder = builder;
     // handling anything that came in via the CLI
     if let Some(source) = &cli.source {
@@ -351,13 +996,122 @@ fn cli_valid(builder: config::ConfigBuilder<DefaultState>, cli: &Cli) -> Result<
                 .ok_or_else(|| Error::Io(std::io::Error::new(std::io::ErrorKind::NotFound, "No output path found")))?,
         )?;
     };
+    if !cli.fields.is_empty() {
+        builder = builder.set_override("fields", cli.fields.clone())?;
+    }
+    if !cli.unique.is_empty() {
+        builder = builder.set_override("unique_fields", cli.unique.clone())?;
+    }
+    // Repeated filters on one column allow any of their values, like the values of an `include_cols_with` entry.
+    // They replace the file's filter on that column, the file's filters on other columns still apply
+    if !cli.filters.is_empty() {
+        let mut include_cols_with: config::Map<String, Vec<String>> = config::Map::new();
+        for (column, value) in &cli.filters {
+            include_cols_with.entry(column.clone()).or_default().push(value.clone());
+        }
+        builder = builder.set_override("include_cols_with", include_cols_with)?;
+    }
+    if let Some(latest_in) = &cli.latest_in {
+        builder = builder.set_override(
+            "source",
+            latest_in
+                .to_str()
+                .ok_or_else(|| Error::Io(std::io::Error::new(std::io::ErrorKind::NotFound, "No source path found")))?,
+        )?;
+    }
+    if let Some(delta_against) = &cli.delta_against {
+        builder = builder.set_override(
+            "delta_against",
+            delta_against
+                .to_str()
+                .ok_or_else(|| Error::Io(std::io::Error::new(std::io::ErrorKind::NotFound, "No delta path found")))?,
+        )?;
+    }
+    if let Some(with_stats) = &cli.with_stats {
+        builder = builder.set_override(
+            "with_stats",
+            with_stats
+                .to_str()
+                .ok_or_else(|| Error::Io(std::io::Error::new(std::io::ErrorKind::NotFound, "No stats path found")))?,
+        )?;
+    }
+    if let Some(validate_report) = &cli.validate_report {
+        builder = builder.set_override(
+            "validate_report",
+            validate_report.to_str().ok_or_else(|| {
+                Error::Io(std::io::Error::new(std::io::ErrorKind::NotFound, "No validation report path found"))
+            })?,
+        )?;
+    }
+    if let Some(limit) = cli.limit {
+        builder = builder.set_override("limit", limit as u64)?;
+    }
+    if let Some((start, end)) = cli.rows {
+        builder = builder.set_override("row_range", vec![start as u64, end as u64])?;
+    }
+    // `head` and `tail` are mutually exclusive, so one from the CLI replaces either from the file
+    if let Some(head) = cli.head {
+        builder = builder
+            .set_override("head", head as u64)?
+            .set_override("tail", None::<u64>)?;
+    }
+    if let Some(tail) = cli.tail {
+        builder = builder
+            .set_override("tail", tail as u64)?
+            .set_override("head", None::<u64>)?;
+    }
+    if let Some(sample) = cli.sample {
+        builder = builder.set_override("sample", sample as u64)?;
+    }
+    if let Some(seed) = cli.seed {
+        builder = builder.set_override("seed", seed)?;
+    }
+    if let Some(dedup_preview) = cli.dedup_preview {
+        builder = builder.set_override("dedup_preview", dedup_preview as u64)?;
+    }
+    if cli.external {
+        builder = builder.set_override("external", true)?;
+    }
+    if cli.progress {
+        builder = builder.set_override("progress", true)?;
+    }
+    if cli.html_standalone {
+        builder = builder.set_override("html_standalone", true)?;
+    }
     Ok(builder)
 }
 
+fn default_external_memory_mb() -> usize {
+    256
+}
+
+fn default_double_quote() -> bool {
+    true
+}
+
+fn default_write_headers() -> bool {
+    true
+}
+
+fn default_date_format() -> String {
+    "%Y-%m-%d".to_string()
+}
+
+/// Deserializes a map that may also be given as unit, as the `config` crate reads an empty TOML table (e.g. `[rename]`) as unit.
+fn map_or_unit<'de, D, K, V>(deserializer: D) -> std::result::Result<HashMap<K, V>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    K: Deserialize<'de> + Eq + std::hash::Hash,
+    V: Deserialize<'de>,
+{
+    Ok(Option::<HashMap<K, V>>::deserialize(deserializer)?.unwrap_or_default())
+}
+
 /// remove any keys & values that start with __ as these are the 'default' filler keys
-fn clear_placeholder_keys(mut config: Config) -> Config {
+pub(crate) fn clear_placeholder_keys(mut config: Config) -> Config {
     config.fields.retain(|f| !f.starts_with("__"));
     config.include_cols_with.retain(|k, _| !k.starts_with("__"));
+    config.exclude_cols_with.retain(|k, _| !k.starts_with("__"));
     config
 }
 
@@ -373,7 +1127,7 @@ impl Default for Config {
     ///
     /// # Example
     ///
-    /// ```rust
+    /// ```ignore
     /// let default_config = Config::default();
     /// ```
     fn default() -> Self {
@@ -399,7 +1153,7 @@ impl TryFrom<&str> for Config {
     ///
     /// # Example
     ///
-    /// ```rust
+    /// ```ignore
     /// let json_str = r#"{"source": "data.csv", "output_type": "Csv", "output_path": "output.csv", "has_headers": true, "fields": ["field1", "field2"], "unique_fields": ["field1"], "include_cols_with": {"field1": ["value1", "value2"]}}"#;
     /// let config = Config::try_from(json_str).expect("Failed to create config from JSON string");
     /// ```
@@ -427,7 +1181,7 @@ impl Display for Config {
     ///
     /// # Example
     ///
-    /// ```rust
+    /// ```ignore
     /// let config = Config::default();
     /// println!("{}", config);
     /// ```
@@ -452,7 +1206,7 @@ impl Debug for Config {
     ///
     /// # Example
     ///
-    /// ```rust
+    /// ```ignore
     /// let config = Config::default();
     /// println!("{:?}", config);
     /// ```
@@ -460,3 +1214,207 @@ impl Debug for Config {
         write!(f, "{}", serde_json::to_string_pretty(self).map_err(|_| std::fmt::Error)?)
     }
 }
+
+#[cfg(test)]
+mod core_tests {
+    use clap::Parser;
+    use tempfile::tempdir;
+
+    use super::*;
+
+    #[test]
+    fn test_deduplicates() {
+        let config = |unique_fields: &[&str], dedup_all: bool| Config {
+            unique_fields: unique_fields.iter().map(ToString::to_string).collect(),
+            dedup_all,
+            ..Default::default()
+        };
+
+        assert!(!config(&[], false).deduplicates());
+        assert!(config(&[], true).deduplicates());
+        assert!(config(&["Id"], false).deduplicates());
+        assert!(config(&["Id", "Kind"], false).deduplicates());
+        assert!(config(&["Id"], true).deduplicates());
+    }
+
+    #[test]
+    fn test_toml_config_round_trip() {
+        let config = Config::try_from(
+            r#"{
+                "source": "data/input.csv",
+                "output_type": "csv",
+                "output_path": "data/output.csv",
+                "has_headers": true,
+                "delimiter": ";",
+                "fields": ["Id", "Name", "Status"],
+                "unique_fields": ["Id"],
+                "include_cols_with": { "Status": ["Active", "Pending"], "Region": ["EU"] },
+                "numeric_filters": { "Amount": [0.0, 100.0] },
+                "sort_by": ["Name", { "column": "Id", "direction": "desc", "kind": "numeric" }],
+                "limit": 10
+            }"#,
+        )
+        .unwrap();
+
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        std::fs::write(&path, toml::to_string(&config).unwrap()).unwrap();
+        let read_back = Config::try_from(path).unwrap();
+
+        assert_eq!(serde_json::to_value(&read_back).unwrap(), serde_json::to_value(&config).unwrap());
+    }
+
+    #[test]
+    fn test_config_file_format() {
+        assert_eq!(config_file_format(Path::new("config.yml"), None), config::FileFormat::Yaml);
+        assert_eq!(config_file_format(Path::new("config.TOML"), None), config::FileFormat::Toml);
+        assert_eq!(config_file_format(Path::new("config"), None), config::FileFormat::Json);
+        assert_eq!(config_file_format(Path::new("config"), Some(ConfigFormat::Toml)), config::FileFormat::Toml);
+    }
+
+    #[test]
+    fn test_env_layer_between_file_and_cli() {
+        let dir = tempdir().unwrap();
+        let config_path = dir.path().join("config.json");
+        std::fs::write(
+            &config_path,
+            r#"{"source": "data.csv", "output_type": "csv", "output_path": "out.csv", "has_headers": false}"#,
+        )
+        .unwrap();
+        let env = env_layer().source(Some(
+            [
+                ("CSV_CLI_OUTPUT_TYPE".to_string(), "json".to_string()),
+                ("CSV_CLI_OUTPUT_PATH".to_string(), "env.json".to_string()),
+                ("CSV_CLI_HAS_HEADERS".to_string(), "true".to_string()),
+            ]
+            .into(),
+        ));
+
+        let cli = Cli::try_parse_from(["csv_parser_rs", "--output_type", "tsv"]).unwrap();
+        let config = layered_builder_with_env(&cli, Some(&config_path), env)
+            .unwrap()
+            .build()
+            .unwrap();
+
+        assert_eq!(config.get::<String>("output_type").unwrap(), "tsv");
+        assert_eq!(config.get::<String>("output_path").unwrap(), "env.json");
+        assert!(config.get::<bool>("has_headers").unwrap());
+        assert_eq!(config.get::<String>("source").unwrap(), "data.csv");
+    }
+
+    #[test]
+    fn test_cli_filters_override_the_file_per_column() {
+        let dir = tempdir().unwrap();
+        let config_path = dir.path().join("config.json");
+        std::fs::write(
+            &config_path,
+            r#"{"fields": ["Id"], "unique_fields": ["Id"], "include_cols_with": {"Kind": ["A"], "Region": ["EU"], "Status": ["Closed"]}}"#,
+        )
+        .unwrap();
+
+        let cli = Cli::try_parse_from([
+            "csv_parser_rs",
+            "--fields",
+            "Id,Status",
+            "--unique",
+            "Status",
+            "--filter",
+            "Status=Open",
+            "-F",
+            "Status=a=b",
+            "-F",
+            "Region=",
+        ])
+        .unwrap();
+        let config: Config =
+            layered_builder_with_env(&cli, Some(&config_path), env_layer().source(Some(config::Map::new())))
+                .unwrap()
+                .build()
+                .unwrap()
+                .try_deserialize()
+                .unwrap();
+
+        assert_eq!(config.fields, vec!["Id", "Status"]);
+        assert_eq!(config.unique_fields, vec!["Status"]);
+        let config = clear_placeholder_keys(config);
+        assert_eq!(
+            config.include_cols_with,
+            HashMap::from([
+                ("Kind".to_string(), vec!["A".to_string()]),
+                ("Region".to_string(), vec![String::new()]),
+                ("Status".to_string(), vec!["Open".to_string(), "a=b".to_string()]),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_no_config_doesnt_create_a_config_file() {
+        let dir = tempdir().unwrap();
+        let expected = dir.path().join(DEFAULT_CONFIG_DIR).join(DEFAULT_CONFIG_FILE);
+
+        let missing = config_file(dir.path().to_path_buf(), false);
+        assert!(matches!(missing, Err(Error::NoConfigFile(path)) if path == expected));
+        assert!(!dir.path().join(DEFAULT_CONFIG_DIR).exists());
+
+        // Without --no-config one is generated, which --no-config then uses
+        config_file(dir.path().to_path_buf(), true).unwrap();
+        assert_eq!(config_file(dir.path().to_path_buf(), false).unwrap(), expected);
+        assert!(Cli::try_parse_from(["csv_parser_rs", "--no-config"]).unwrap().no_config);
+    }
+
+    #[test]
+    fn test_config_less_cli() {
+        assert!(Cli::try_parse_from(["csv_parser_rs", "--filter", "Status"]).is_err());
+        assert!(Cli::try_parse_from(["csv_parser_rs", "-F", "=Open"]).is_err());
+        assert!(
+            !Cli::try_parse_from(["csv_parser_rs", "data.csv"])
+                .unwrap()
+                .is_config_less()
+        );
+        assert!(
+            !Cli::try_parse_from(["csv_parser_rs", "data.csv", "--fields", "Id", "-c", "config.json"])
+                .unwrap()
+                .is_config_less()
+        );
+
+        let cli = Cli::try_parse_from(["csv_parser_rs", "data.csv", "-f", "Status", "-f", "Id,#2"]).unwrap();
+        assert!(cli.is_config_less());
+        assert_eq!(cli.fields, vec!["Status", "Id", "#2"]);
+
+        let dir = tempdir().unwrap();
+        let source = dir.path().join("data.csv");
+        std::fs::write(&source, "Id,Status\n1,Open\n").unwrap();
+
+        let cli = Cli::try_parse_from([
+            "csv_parser_rs",
+            source.to_str().unwrap(),
+            "-t",
+            "csv",
+            "--fields",
+            "Id,Status",
+            "--filter",
+            "Status=Open",
+        ])
+        .unwrap();
+        assert!(cli.is_config_less());
+
+        let config = Config::try_from(cli).unwrap();
+        assert_eq!(config.source, source);
+        assert_eq!(config.output_type, OutputType::Csv);
+        assert!(config.output_path.to_string_lossy().ends_with("output.csv"));
+        assert_eq!(config.fields, vec!["Id", "Status"]);
+        assert!(config.unique_fields.is_empty());
+        assert_eq!(config.include_cols_with, HashMap::from([("Status".to_string(), vec!["Open".to_string()])]));
+    }
+
+    #[test]
+    fn test_match_select_from_config() {
+        let config = config::Config::builder()
+            .set_override("match_select", "name_desc")
+            .unwrap()
+            .build()
+            .unwrap();
+
+        assert_eq!(config.get::<MatchSelect>("match_select").unwrap(), MatchSelect::NameDesc);
+    }
+}
@@ -0,0 +1,48 @@
+use crate::prelude::{Deserialize, Serialize};
+
+/// Which row is kept when several rows share the same `unique_fields` key.
+///
+/// # Variants
+///
+/// * `First` - Keep the earliest row in input order (the default).
+/// * `Last` - Keep the latest row in input order, e.g. the most recent entry of time-ordered data.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DedupKeep {
+    #[default]
+    First,
+    Last,
+}
+
+/// How the values of a column are combined across the rows sharing a `unique_fields` key (see `dedup_aggregate`).
+///
+/// # Variants
+///
+/// * `Sum` - The sum of the values that are numbers, other values are ignored.
+/// * `Max` - The largest non-empty value, compared numerically when both values are numbers.
+/// * `Min` - The smallest non-empty value, compared numerically when both values are numbers.
+/// * `First` - The value of the first row in input order, whichever row is kept.
+/// * `Last` - The value of the last row in input order, whichever row is kept.
+/// * `Concat` - Every non-empty value in input order, joined with `;`.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AggOp {
+    Sum,
+    Max,
+    Min,
+    First,
+    Last,
+    Concat,
+}
+
+/// Settings for the approximate, bloom filter backed deduplication done while the source is read.
+///
+/// # Fields
+///
+/// * `capacity` - The number of distinct `unique_fields` keys expected.
+/// * `fp_rate` - The acceptable fraction of unique rows wrongly dropped as duplicates, e.g. `0.001`.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct DedupBloom {
+    pub capacity: usize,
+    pub fp_rate: f64,
+}
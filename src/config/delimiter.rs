@@ -0,0 +1,89 @@
+use std::fmt::{Debug, Display};
+
+use crate::prelude::{Deserialize, Serialize};
+
+/// A single-byte field delimiter, deserialized from a one-character string.
+///
+/// The string `"\t"` (either a literal tab, or a backslash followed by `t`) is accepted for tab-separated files.
+/// Anything longer than a single byte is rejected rather than silently truncated.
+///
+/// # Example
+///
+/// ```json
+/// "delimiter": ";"
+/// ```
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(try_from = "String", into = "String")]
+pub struct Delimiter(u8);
+
+impl Delimiter {
+    /// Returns the delimiter as the byte expected by `csv::ReaderBuilder`/`csv::WriterBuilder`.
+    pub fn as_byte(self) -> u8 {
+        self.0
+    }
+}
+
+impl Default for Delimiter {
+    fn default() -> Self {
+        Self(b',')
+    }
+}
+
+impl TryFrom<String> for Delimiter {
+    type Error = String;
+
+    fn try_from(value: String) -> std::result::Result<Self, Self::Error> {
+        match value.as_bytes() {
+            [byte] => Ok(Self(*byte)),
+            b"\\t" => Ok(Self(b'\t')),
+            _ => Err(format!("delimiter must be a single-byte character (or \"\\t\" for tab), got {value:?}")),
+        }
+    }
+}
+
+impl From<Delimiter> for String {
+    fn from(delimiter: Delimiter) -> Self {
+        char::from(delimiter.0).to_string()
+    }
+}
+
+impl Display for Delimiter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", char::from(self.0))
+    }
+}
+
+impl Debug for Delimiter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Delimiter({:?})", char::from(self.0))
+    }
+}
+
+#[cfg(test)]
+mod delimiter_tests {
+    use super::*;
+
+    #[test]
+    fn test_single_byte_delimiters() {
+        assert_eq!(Delimiter::try_from(";".to_string()).unwrap().as_byte(), b';');
+        assert_eq!(Delimiter::try_from("|".to_string()).unwrap().as_byte(), b'|');
+        assert_eq!(Delimiter::try_from("\t".to_string()).unwrap().as_byte(), b'\t');
+    }
+
+    #[test]
+    fn test_escaped_tab() {
+        assert_eq!(Delimiter::try_from(r"\t".to_string()).unwrap().as_byte(), b'\t');
+    }
+
+    #[test]
+    fn test_rejects_invalid_delimiters() {
+        assert!(Delimiter::try_from(String::new()).is_err());
+        assert!(Delimiter::try_from(";;".to_string()).is_err());
+        assert!(Delimiter::try_from("§".to_string()).is_err());
+    }
+
+    #[test]
+    fn test_default_is_comma() {
+        assert_eq!(Delimiter::default().as_byte(), b',');
+    }
+}
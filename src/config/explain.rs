@@ -0,0 +1,220 @@
+use std::collections::HashSet;
+use std::fmt::Display;
+use std::path::Path;
+
+use config::ValueKind;
+use config::builder::DefaultState;
+
+use crate::cli::Cli;
+use crate::config::core::{cli_valid, env_layer, layered_builder};
+use crate::prelude::*;
+
+const MAX_VALUE_WIDTH: usize = 60;
+
+/// The configuration layer a value was taken from.
+///
+/// # Variants
+///
+/// * `Default` - The built-in defaults (`Config::default()`).
+/// * `File` - The configuration file.
+/// * `Env` - A `CSV_CLI_*` environment variable.
+/// * `Cli` - A command-line argument.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigLayer {
+    Default,
+    File,
+    Env,
+    Cli,
+}
+
+impl Display for ConfigLayer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Default => write!(f, "default"),
+            Self::File => write!(f, "file"),
+            Self::Env => write!(f, "env"),
+            Self::Cli => write!(f, "cli"),
+        }
+    }
+}
+
+/// The final value of a single top-level config key, and the layer it came from.
+///
+/// # Fields
+///
+/// * `key` - The top-level config key.
+/// * `value` - The winning value, rendered as compact JSON (strings are left unquoted).
+/// * `origin` - The `ConfigLayer` the winning value came from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExplainedValue {
+    pub key: String,
+    pub value: String,
+    pub origin: ConfigLayer,
+}
+
+/// Works out the winning value and origin layer of every top-level config key.
+///
+/// Each layer of `layered_builder` is also built on its own, so that a key can be attributed to the
+/// highest precedence layer that sets it (CLI, then the environment, then the file, then the defaults).
+/// Values are reported as they are merged, before any path substitution is applied.
+///
+/// # Arguments
+///
+/// * `cli` - A reference to the `Cli` instance containing the command-line arguments.
+/// * `config_path` - The path to the configuration file that is layered in, if any.
+///
+/// # Returns
+///
+/// * `Result<Vec<ExplainedValue>>` - Returns one entry per key, sorted by key, or an `Error` on failure.
+///
+/// # Errors
+///
+/// This function can return errors if any of the layers fail to build.
+///
+/// # Example
+///
+/// ```ignore
+/// let explained = explain_config(&cli, Some(&config_path))?;
+/// println!("{}", render_explained(&explained));
+/// ```
+pub fn explain_config(cli: &Cli, config_path: Option<&Path>) -> Result<Vec<ExplainedValue>> {
+    let merged = layered_builder(cli, config_path)?.build()?;
+
+    let file_keys = match config_path {
+        Some(config_path) => layer_keys(config::Config::builder().add_source(config::File::from(config_path)))?,
+        None => Default::default(),
+    };
+    let env_keys = layer_keys(config::Config::builder().add_source(env_layer()))?;
+
+    let mut cli_builder = cli_valid(config::Config::builder(), cli)?;
+    if let Some(cli_config_file) = &cli.config_file {
+        cli_builder = cli_builder.set_override("config_file", cli_config_file.to_str().unwrap())?;
+    }
+    let cli_keys = layer_keys(cli_builder)?;
+
+    let mut explained = merged
+        .cache
+        .into_table()?
+        .into_iter()
+        .map(|(key, value)| {
+            let origin = if cli_keys.contains(&key) {
+                ConfigLayer::Cli
+            } else if env_keys.contains(&key) {
+                ConfigLayer::Env
+            } else if file_keys.contains(&key) {
+                ConfigLayer::File
+            } else {
+                ConfigLayer::Default
+            };
+
+            let value = match value.try_deserialize::<serde_json::Value>()? {
+                serde_json::Value::String(s) => s,
+                other => other.to_string(),
+            };
+
+            Ok(ExplainedValue { key, value, origin })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    explained.sort_by(|a, b| a.key.cmp(&b.key));
+    Ok(explained)
+}
+
+/// Renders the explained values as an aligned `KEY | VALUE | ORIGIN` table.
+///
+/// Values longer than 60 characters are truncated with a trailing `...`.
+///
+/// # Arguments
+///
+/// * `explained` - The entries produced by `explain_config`.
+///
+/// # Returns
+///
+/// * `String` - Returns the table, one line per entry after the header.
+pub fn render_explained(explained: &[ExplainedValue]) -> String {
+    let key_width = explained
+        .iter()
+        .map(|e| e.key.len())
+        .chain(["KEY".len()])
+        .max()
+        .unwrap_or_default();
+    let values = explained.iter().map(|e| truncate_value(&e.value)).collect::<Vec<_>>();
+    let value_width = values
+        .iter()
+        .map(|v| v.chars().count())
+        .chain(["VALUE".len()])
+        .max()
+        .unwrap_or_default();
+
+    let mut table = format!("{:key_width$} | {:value_width$} | ORIGIN\n", "KEY", "VALUE");
+    table.push_str(&format!("{}-+-{}-+-{}\n", "-".repeat(key_width), "-".repeat(value_width), "-".repeat(7)));
+    for (entry, value) in explained.iter().zip(values) {
+        table.push_str(&format!("{:key_width$} | {:value_width$} | {}\n", entry.key, value, entry.origin));
+    }
+    table
+}
+
+/// Collects the keys a single layer sets. Empty values (such as a `{}` that the `config` crate reads as nil)
+/// don't replace the lower layers when merged, so they aren't counted.
+fn layer_keys(builder: config::ConfigBuilder<DefaultState>) -> Result<HashSet<String>> {
+    Ok(builder
+        .build()?
+        .cache
+        .into_table()?
+        .into_iter()
+        .filter(|(_, value)| !matches!(value.kind, ValueKind::Nil))
+        .map(|(key, _)| key)
+        .collect())
+}
+
+/// Shortens long values (such as large `include_cols_with` maps) so the table stays readable.
+fn truncate_value(value: &str) -> String {
+    if value.chars().count() <= MAX_VALUE_WIDTH {
+        return value.to_string();
+    }
+    let truncated = value.chars().take(MAX_VALUE_WIDTH - 3).collect::<String>();
+    format!("{truncated}...")
+}
+
+#[cfg(test)]
+mod explain_tests {
+    use clap::Parser;
+    use tempfile::tempdir;
+
+    use super::*;
+
+    fn find<'e>(explained: &'e [ExplainedValue], key: &str) -> &'e ExplainedValue {
+        explained.iter().find(|e| e.key == key).unwrap()
+    }
+
+    #[test]
+    fn test_explain_config_reports_origin_layer() {
+        let dir = tempdir().unwrap();
+        let config_path = dir.path().join("config.json");
+        std::fs::write(
+            &config_path,
+            r#"{"source": "data.csv", "output_type": "csv", "has_headers": true, "fields": ["Id"], "unique_fields": [], "include_cols_with": {"a": []}}"#,
+        )
+        .unwrap();
+
+        let cli = Cli::try_parse_from(["csv_parser_rs", "--output_type", "tsv"]).unwrap();
+        let explained = explain_config(&cli, Some(&config_path)).unwrap();
+
+        let output_type = find(&explained, "output_type");
+        assert_eq!(output_type.value, "tsv");
+        assert_eq!(output_type.origin, ConfigLayer::Cli);
+
+        let has_headers = find(&explained, "has_headers");
+        assert_eq!(has_headers.value, "true");
+        assert_eq!(has_headers.origin, ConfigLayer::File);
+
+        assert_eq!(find(&explained, "external").origin, ConfigLayer::Default);
+
+        let table = render_explained(&explained);
+        assert!(
+            table
+                .lines()
+                .any(|line| line.starts_with("output_type") && line.ends_with("| cli"))
+        );
+    }
+}
@@ -5,7 +5,7 @@ use std::time::SystemTime;
 
 use regex::Regex;
 
-use crate::config::{UserDefinedParts, UserDefinedRegex, compare_criteria, is_relative};
+use crate::config::{MatchSelect, UserDefinedParts, UserDefinedRegex, compare_criteria, is_relative};
 use crate::prelude::*;
 
 /// Regex tests at bottom of the file - see `#[cfg(test)] mod regex_filename`
@@ -40,23 +40,43 @@ pub static USER_PATH_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\{(.
 const _S: usize = 1;
 
 // TODO: Test
-//// Substitute the `var` variable in a string with the given `val` value.
-////
-//// Variable format: `{{ var }}`
+// Substitute the `var` variable in a string with the given `val` value.
+//
+// Variable format: `{{ var }}`
 // fn substitute<'a: 'b, 'b>(str: &'a str, var: &str, val: &str) -> std::borrow::Cow<'b, str> {
 //     let format = format!(r"\{{\{{[[:space:]]*{}[[:space:]]*\}}\}}", var);
 //     Regex::new(&format).unwrap().replace_all(str, val)
 // }
 
-pub fn parse_user_variable_path(path_str: &str) -> Result<PathBuf> {
+/// Resolves a path that may contain a `{regex}` in its file name to the newest matching file.
+///
+/// # Arguments
+///
+/// * `path_str` - The path as provided by the user, e.g. `data\\export_{.*}.csv`.
+/// * `scan_limit` - The maximum number of directory entries to look at when searching for matches, or `None` for no limit.
+/// * `fuzzy_match` - When no file matches the pattern, fall back to the file whose name is closest to it (see `closest_file`).
+/// * `match_select` - Which of several matching files is picked, see `MatchSelect`.
+///
+/// # Returns
+///
+/// * `Result<PathBuf>` - Returns the resolved path, or an `Error` on failure.
+///
+/// # Errors
+///
+/// * `Error::ScanLimitExceeded` - If `scan_limit` entries were scanned without finding a match.
+/// * `Error::NoMatchingFiles` - If no file matches the pattern, or with `fuzzy_match`, if the directory holds no files at all.
+/// * `Error::AmbiguousFileMatch` - If the best matches cannot be told apart, carrying every tied candidate.
+/// * `Error::Io` - If a relative path can't be resolved, as the current directory can't be determined.
+pub fn parse_user_variable_path(
+    path_str: &str,
+    scan_limit: Option<usize>,
+    fuzzy_match: bool,
+    match_select: MatchSelect,
+) -> Result<PathBuf> {
     let user_defined_parts = match extract_user_regex(path_str) {
         Some(mut parts) => {
             trace!("User defined parts INNER: {:?}", parts);
-            parts.base_path = if is_relative(parts.base_path.to_str().unwrap()).is_ok() {
-                is_relative(parts.base_path.to_str().unwrap())?
-            } else {
-                parts.base_path
-            };
+            parts.base_path = is_relative(&parts.base_path)?;
             parts
         }
         None => return is_relative(path_str),
@@ -71,38 +91,312 @@ pub fn parse_user_variable_path(path_str: &str) -> Result<PathBuf> {
 
     trace!("Base path parent: {:?}", base_path_parent);
 
-    let before_reg_filename =
-        &user_defined_parts.before_regex[(user_defined_parts.before_regex.rfind('\\').unwrap_or_default() + 1)..];
+    let before_reg_filename = &user_defined_parts.before_regex[user_defined_parts
+        .before_regex
+        .rfind(['/', '\\'])
+        .map_or(0, |idx| idx + 1)..];
     trace!("Before regex filename: {:?}", before_reg_filename);
 
     let mut matching_files = Box::new(
-        find_match_files_from_regex_path(base_path_parent, &user_defined_parts, before_reg_filename)
-            .unwrap_or_default(),
+        match find_match_files_from_regex_path(base_path_parent, &user_defined_parts, before_reg_filename, scan_limit) {
+            Ok(matching_files) => matching_files,
+            Err(e @ Error::ScanLimitExceeded(_)) => return Err(e),
+            Err(_) => Vec::new(),
+        },
     );
 
+    if matching_files.is_empty() && fuzzy_match {
+        let before_filename = &user_defined_parts.before_regex[user_defined_parts
+            .before_regex
+            .rfind(['/', '\\'])
+            .map_or(0, |idx| idx + 1)..];
+        let intended = format!("{before_filename}{}", user_defined_parts.suffix_ext.unwrap_or_default());
+        return closest_file(base_path_parent, &intended);
+    }
+
     // let stored = Box::new(matching_files.iter().map(|f| f.path()).collect::<Vec<_>>());
     let stored = &matching_files.iter().map(DirEntry::path).collect::<Vec<_>>();
 
-    let sorted_matching_files = sort_by_modification_time::<_S>(matching_files.as_mut_slice());
+    let sorted_matching_files = sort_by_match_select(matching_files.as_mut_slice(), match_select);
 
     let first_match = sorted_matching_files.first().ok_or_else(|| {
         error!("We found these files: {:?}", *stored);
         Error::NoMatchingFiles
     })?;
-    let second_match = sorted_matching_files.get(1); // Keep the Option to safe match on Some(_)
-
-    // This is... Not a great way to do this, should probably be something like fold() or reduce()
-    if let Some(second_match) = second_match {
-        let d = compare_criteria(first_match, second_match, "date");
-        let n = compare_criteria(first_match, second_match, "name");
-        let s = compare_criteria(first_match, second_match, "size");
-        if d == std::cmp::Ordering::Equal && n == std::cmp::Ordering::Equal && s == std::cmp::Ordering::Equal {
-            return Err(Error::AmbiguousFileMatch);
-        }
+
+    let tied = tied_with_first(sorted_matching_files);
+    if tied.len() > 1 {
+        error!("These files can't be told apart: {:?}", tied);
+        return Err(Error::AmbiguousFileMatch(tied));
     }
     Ok(first_match.path())
 }
 
+/// Collects every file whose (date, name, size) equals that of the first file, the first file included.
+///
+/// # Arguments
+///
+/// * `files` - The candidate files, best first.
+///
+/// # Returns
+///
+/// * `Vec<PathBuf>` - The paths of the files tied with the first, empty when there are no files.
+fn tied_with_first(files: &[DirEntry]) -> Vec<PathBuf> {
+    let Some(first) = files.first() else {
+        return Vec::new();
+    };
+
+    files
+        .iter()
+        .filter(|candidate| {
+            ["date", "name", "size"]
+                .iter()
+                .all(|criteria| compare_criteria(first, candidate, criteria) == std::cmp::Ordering::Equal)
+        })
+        .map(DirEntry::path)
+        .collect()
+}
+
+/// Picks the file in a directory whose name has the smallest edit (Levenshtein) distance to the intended name.
+///
+/// Used as the `fuzzy_match` fallback when nothing matches a `{regex}` pattern, the intended name being the pattern
+/// with the `{regex}` taken out - e.g. `export_{\d+}.csv` looks for the file closest to `export_.csv`.
+/// Ties go to the file that sorts first by name, so the pick doesn't depend on the directory order.
+///
+/// # Arguments
+///
+/// * `directory` - The directory to pick a file from.
+/// * `intended` - The file name that was being looked for.
+///
+/// # Returns
+///
+/// * `Result<PathBuf>` - Returns the closest file, or an `Error` on failure.
+///
+/// # Errors
+///
+/// * `Error::NoMatchingFiles` - If the directory holds no files.
+fn closest_file(directory: &Path, intended: &str) -> Result<PathBuf> {
+    let mut closest: Option<(usize, String, PathBuf)> = None;
+
+    for entry in std::fs::read_dir(directory).map_err(Error::Io)? {
+        let entry = entry?;
+        if entry.metadata()?.is_dir() {
+            continue;
+        }
+        let filename = entry.file_name().to_string_lossy().into_owned();
+        let distance = levenshtein_distance(intended, &filename);
+        trace!("Edit distance from {intended:?} to {filename:?}: {distance}");
+
+        if closest
+            .as_ref()
+            .is_none_or(|(best, best_name, _)| (distance, &filename) < (*best, best_name))
+        {
+            closest = Some((distance, filename, entry.path()));
+        }
+    }
+
+    let (distance, _, path) = closest.ok_or(Error::NoMatchingFiles)?;
+    warn!("No file matched {intended:?}, using the closest file name instead (edit distance {distance}): {path:?}");
+    Ok(path)
+}
+
+/// Computes the Levenshtein distance between two strings: the number of single character insertions,
+/// deletions or substitutions it takes to turn one into the other. Characters are compared as `char`s, not bytes.
+fn levenshtein_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    // Only the previous row of the distance matrix is needed to fill in the next one
+    let mut previous = (0..=b.len()).collect::<Vec<_>>();
+    let mut current = vec![0; b.len() + 1];
+
+    for (i, a_char) in a.chars().enumerate() {
+        current[0] = i + 1;
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != *b_char);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    previous[b.len()]
+}
+
+/// Checks whether a `source` is a glob (a `*` or `?` in its file name) rather than a path or `{regex}` pattern.
+pub fn is_glob_pattern(path_str: &str) -> bool {
+    let file_name = &path_str[path_str.rfind(['/', '\\']).map_or(0, |idx| idx + 1)..];
+    !USER_PATH_REGEX.is_match(path_str) && file_name.contains(['*', '?'])
+}
+
+/// Expands a glob in the file name of a path into every file it matches, sorted by name.
+///
+/// `*` matches any run of characters and `?` a single character, e.g. `data/export_*.csv` matches `data/export_2025-01.csv`.
+/// Only the file name may hold wildcards, the directory is taken as-is.
+///
+/// # Arguments
+///
+/// * `pattern` - The path with a glob in its file name.
+/// * `scan_limit` - The maximum number of directory entries to look at when searching for matches, or `None` for no limit.
+///
+/// # Returns
+///
+/// * `Result<Vec<PathBuf>>` - Returns the matching files, or an `Error` on failure.
+///
+/// # Errors
+///
+/// * `Error::ScanLimitExceeded` - If `scan_limit` entries were scanned without finding a match.
+/// * `Error::NoMatchingFiles` - If no file matches the pattern.
+///
+/// # Example
+///
+/// ```ignore
+/// let monthly = expand_glob("data/export_*.csv", None)?;
+/// ```
+pub fn expand_glob(pattern: &str, scan_limit: Option<usize>) -> Result<Vec<PathBuf>> {
+    let (directory, file_glob) = match pattern.rfind(['/', '\\']) {
+        Some(idx) => (&pattern[..=idx], &pattern[idx + 1..]),
+        None => (".", pattern),
+    };
+
+    let regex = file_glob
+        .split_inclusive(['*', '?'])
+        .map(|part| match part.strip_suffix('*') {
+            Some(literal) => format!("{}.*", regex::escape(literal)),
+            None => match part.strip_suffix('?') {
+                Some(literal) => format!("{}.", regex::escape(literal)),
+                None => regex::escape(part),
+            },
+        })
+        .collect::<String>();
+    let parts = UserDefinedParts {
+        base_path: PathBuf::from(pattern),
+        before_regex: "",
+        user_regex: UserDefinedRegex {
+            regex: Regex::new(&format!("^{regex}$")).map_err(|e| Error::ParsingPath(e.to_string()))?,
+            _phantom: std::marker::PhantomData,
+        },
+        suffix_ext: Some(""),
+        raw_ext: None,
+    };
+
+    let mut matches = find_match_files_from_regex_path(Path::new(directory), &parts, "", scan_limit)?;
+    if matches.is_empty() {
+        error!("No files match the source pattern {pattern:?}");
+        return Err(Error::NoMatchingFiles);
+    }
+    matches.sort_by(|first, second| compare_criteria(first, second, "name"));
+
+    debug!("Source pattern {pattern:?} matched {} files", matches.len());
+    Ok(matches.iter().map(DirEntry::path).collect())
+}
+
+/// Picks the most recently modified `.csv` (or `.csv.gz`) file directly inside `directory`.
+///
+/// This is the shorthand for "the newest export in this folder", used when `source` points at a
+/// directory (or `--latest-in` is provided) rather than at a file or a `{regex}` pattern.
+///
+/// # Arguments
+///
+/// * `directory` - The directory to search, sub-directories are not descended into.
+///
+/// # Returns
+///
+/// * `Result<PathBuf>` - Returns the path of the newest `.csv` or `.csv.gz` file, or an `Error` on failure.
+///
+/// # Errors
+///
+/// * `Error::Io` - If the directory cannot be read.
+/// * `Error::NoMatchingFiles` - If the directory contains no `.csv` files.
+///
+/// # Example
+///
+/// ```ignore
+/// let newest = latest_in_directory(Path::new("data"))?;
+/// ```
+pub fn latest_in_directory(directory: &Path) -> Result<PathBuf> {
+    let mut csv_files = Vec::new();
+
+    for entry in std::fs::read_dir(directory).map_err(Error::Io)? {
+        let entry = entry?;
+        let path = entry.path();
+
+        let name = path
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_ascii_lowercase();
+        if entry.metadata()?.is_file() && (name.ends_with(".csv") || name.ends_with(".csv.gz")) {
+            csv_files.push(entry);
+        }
+    }
+
+    let sorted = sort_by_modification_time::<_S>(csv_files.as_mut_slice());
+    let newest = sorted.first().ok_or(Error::NoMatchingFiles)?;
+
+    debug!("Latest csv in {:?} is {:?}", directory, newest.path());
+    Ok(newest.path())
+}
+
+/// Gives an output path the extension of its output type, keeping a trailing `.gz` so compressed output stays compressed.
+///
+/// # Arguments
+///
+/// * `path` - The configured output path.
+/// * `extension` - The extension of the output type, without the dot (e.g. `csv`).
+///
+/// # Returns
+///
+/// * `PathBuf` - The path with the extension set, e.g. `out.txt.gz` becomes `out.csv.gz`.
+///
+/// # Example
+///
+/// ```ignore
+/// assert_eq!(with_output_extension(Path::new("out.csv.gz"), "csv"), PathBuf::from("out.csv.gz"));
+/// ```
+pub fn with_output_extension(path: &Path, extension: &str) -> PathBuf {
+    if !path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("gz")) {
+        return path.with_extension(extension);
+    }
+
+    let mut inner = path.with_extension("");
+    inner.set_extension(extension);
+    inner.as_mut_os_string().push(".gz");
+    inner
+}
+
+/// Checks whether a path is a FIFO (named pipe), socket or character device rather than a regular file.
+///
+/// Such sources can only be read once, front to back - they have no size or modification time worth comparing,
+/// and opening one to inspect it would consume data meant for the reader. They're always non-special on non-Unix platforms.
+///
+/// # Arguments
+///
+/// * `path` - The path to check. Symlinks are followed.
+///
+/// # Returns
+///
+/// * `bool` - Returns `true` if the path exists and is a special file.
+///
+/// # Example
+///
+/// ```ignore
+/// assert!(is_stream_file(Path::new("/dev/stdin")));
+/// ```
+#[cfg(unix)]
+pub fn is_stream_file(path: &Path) -> bool {
+    use std::os::unix::fs::FileTypeExt;
+
+    std::fs::metadata(path).is_ok_and(|meta| {
+        let file_type = meta.file_type();
+        file_type.is_fifo() || file_type.is_socket() || file_type.is_char_device()
+    })
+}
+
+/// Checks whether a path is a FIFO (named pipe), socket or character device rather than a regular file.
+///
+/// Named pipes aren't regular paths on Windows, so this is always `false` there.
+#[cfg(not(unix))]
+pub fn is_stream_file(_path: &Path) -> bool {
+    false
+}
+
 fn extract_user_regex(base_path: &str) -> Option<UserDefinedParts<'_, PathBuf>> {
     let re = &USER_PATH_REGEX;
 
@@ -137,6 +431,19 @@ fn extract_user_regex(base_path: &str) -> Option<UserDefinedParts<'_, PathBuf>>
     None
 }
 
+/// Sorts the files matching a `{regex}` so the one `match_select` picks comes first.
+fn sort_by_match_select(files: &mut [DirEntry], match_select: MatchSelect) -> &mut [DirEntry] {
+    files.sort_by(|a, b| match match_select {
+        MatchSelect::Newest => compare_criteria(b, a, "date"),
+        MatchSelect::Oldest => compare_criteria(a, b, "date"),
+        MatchSelect::Largest => compare_criteria(b, a, "size"),
+        MatchSelect::Smallest => compare_criteria(a, b, "size"),
+        MatchSelect::NameAsc => compare_criteria(a, b, "name"),
+        MatchSelect::NameDesc => compare_criteria(b, a, "name"),
+    });
+    files
+}
+
 #[rustfmt::skip]
 fn sort_by_modification_time<const S: usize>(files: &mut [DirEntry]) -> &mut [DirEntry]
 where
@@ -151,14 +458,33 @@ where
     files
 }
 
+/// Collects the files in `base_directory` whose name matches the user's `{regex}` pattern.
+///
+/// When `scan_limit` is set, at most that many directory entries are looked at. Hitting the limit is an
+/// error if nothing matched yet, otherwise the matches found so far are used and a warning is logged.
 fn find_match_files_from_regex_path(
     base_directory: &Path,
     parts: &UserDefinedParts<'_, PathBuf>,
     before_reg_filename: &str,
+    scan_limit: Option<usize>,
 ) -> Result<Vec<DirEntry>> {
     let mut matches: Vec<DirEntry> = Vec::new();
 
-    for entry in std::fs::read_dir(base_directory).map_err(Error::Io)? {
+    for (scanned, entry) in std::fs::read_dir(base_directory).map_err(Error::Io)?.enumerate() {
+        if let Some(limit) = scan_limit
+            && scanned >= limit
+        {
+            if matches.is_empty() {
+                return Err(Error::ScanLimitExceeded(limit));
+            }
+            warn!(
+                "Stopped scanning {:?} after {limit} entries (source_scan_limit), selecting from the {} matches found so far",
+                base_directory,
+                matches.len()
+            );
+            break;
+        }
+
         trace!("Entry: {:?}", entry);
         let entry = entry?;
         let metadata = entry.metadata()?;
@@ -183,11 +509,13 @@ fn find_match_files_from_regex_path(
 
 #[cfg(test)]
 mod regex_filename {
+    use std::borrow::Cow;
     use std::fs::File;
 
     use tempfile::tempdir;
 
     use super::*;
+    use crate::config::{config_path_components, current_dir, resolve_if_relative};
     #[test]
     fn test_extract_user_regex() {
         let base_path = r"C:\data\file_{.*}.csv";
@@ -198,6 +526,48 @@ mod regex_filename {
         assert!(parts.user_regex.regex.is_match("look Ma regex in production"));
     }
 
+    #[test]
+    fn test_with_output_extension_keeps_gz() {
+        assert_eq!(with_output_extension(Path::new("out.txt"), "csv"), PathBuf::from("out.csv"));
+        assert_eq!(with_output_extension(Path::new("out.csv.gz"), "csv"), PathBuf::from("out.csv.gz"));
+        assert_eq!(with_output_extension(Path::new("out.gz"), "tsv"), PathBuf::from("out.tsv.gz"));
+        assert_eq!(with_output_extension(Path::new("out.csv.gz"), "json"), PathBuf::from("out.json.gz"));
+    }
+
+    #[test]
+    fn test_expand_glob() {
+        let dir = tempdir().unwrap();
+        for name in [
+            "export_2025-02.csv",
+            "export_2025-01.csv",
+            "export_old.txt",
+            "other.csv",
+        ] {
+            File::create(dir.path().join(name)).unwrap();
+        }
+        let pattern = format!("{}/export_*.csv", dir.path().display());
+
+        assert!(is_glob_pattern(&pattern));
+        assert!(!is_glob_pattern(r"data\export_{.*}.csv"));
+        assert_eq!(
+            expand_glob(&pattern, None).unwrap(),
+            vec![
+                dir.path().join("export_2025-01.csv"),
+                dir.path().join("export_2025-02.csv")
+            ]
+        );
+        assert_eq!(
+            expand_glob(&format!("{}/export_2025-0?.csv", dir.path().display()), None)
+                .unwrap()
+                .len(),
+            2
+        );
+        assert!(matches!(
+            expand_glob(&format!("{}/missing_*.csv", dir.path().display()), None),
+            Err(Error::NoMatchingFiles)
+        ));
+    }
+
     #[test]
     fn test_extract_user_regex_no_regex() {
         let base_path = r"C:\data\file.csv";
@@ -230,7 +600,7 @@ mod regex_filename {
         };
 
         let matches =
-            find_match_files_from_regex_path(dir.path(), &parts, "file_").expect("Failed to find matching files");
+            find_match_files_from_regex_path(dir.path(), &parts, "file_", None).expect("Failed to find matching files");
 
         let matched_filenames: Vec<_> = matches
             .iter()
@@ -243,6 +613,37 @@ mod regex_filename {
         assert!(!matched_filenames.contains(&"not_a_match.txt".to_string()));
     }
 
+    #[test]
+    fn test_find_match_files_scan_limit() {
+        let dir = tempdir().expect("Failed to create temp directory");
+        for i in 0..200 {
+            File::create(dir.path().join(format!("unrelated_{i}.txt"))).expect("Failed to create file");
+        }
+
+        let parts = UserDefinedParts {
+            base_path: dir.path().to_path_buf(),
+            before_regex: "file_",
+            user_regex: UserDefinedRegex {
+                regex: Regex::new(r"\d+").expect("Invalid regex"),
+                _phantom: std::marker::PhantomData,
+            },
+            suffix_ext: Some(".csv"),
+            raw_ext: Some(".csv"),
+        };
+
+        // The scan gives up after 50 non-matching entries rather than walking all 200
+        let bounded = find_match_files_from_regex_path(dir.path(), &parts, "file_", Some(50));
+        assert!(matches!(bounded, Err(Error::ScanLimitExceeded(50))));
+
+        // Without a (reached) limit, the whole directory is scanned and simply has no matches
+        let unbounded = find_match_files_from_regex_path(dir.path(), &parts, "file_", Some(1_000)).unwrap();
+        assert!(unbounded.is_empty());
+
+        File::create(dir.path().join("file_1.csv")).expect("Failed to create file");
+        let found = find_match_files_from_regex_path(dir.path(), &parts, "file_", Some(201)).unwrap();
+        assert_eq!(found.len(), 1);
+    }
+
     #[ignore]
     #[test]
     fn test_parse_user_variable_path_with_regex() {
@@ -254,12 +655,157 @@ mod regex_filename {
 
         let binding = dir.path().join("file_{.*}.csv");
         let path_str = binding.to_str().unwrap();
-        let resolved_path = parse_user_variable_path(path_str).expect("Failed to parse user variable path");
+        let resolved_path = parse_user_variable_path(path_str, None, false, MatchSelect::default())
+            .expect("Failed to parse user variable path");
 
         // Ensure the most recent file is chosen
         assert_eq!(resolved_path.file_name().unwrap(), "file_123.csv");
     }
 
+    #[test]
+    fn test_find_match_files_gzip_suffix() {
+        let dir = tempdir().expect("Failed to create temp directory");
+        File::create(dir.path().join("file_123.csv.gz")).expect("Failed to create file1");
+        File::create(dir.path().join("file_456.csv")).expect("Failed to create file2");
+
+        let parts = extract_user_regex(r"C:\data\file_{\d+}.csv.gz").expect("Failed to extract user regex");
+        assert_eq!(parts.suffix_ext, Some(".csv.gz"));
+
+        // The whole `.csv.gz` suffix has to match, so the plain `.csv` file is never picked
+        let found = find_match_files_from_regex_path(dir.path(), &parts, "file_", None).unwrap();
+        let names = found.iter().map(DirEntry::file_name).collect::<Vec<_>>();
+        assert_eq!(names, vec!["file_123.csv.gz"]);
+    }
+
+    #[test]
+    fn test_latest_in_directory() {
+        let dir = tempdir().expect("Failed to create temp directory");
+        let now = SystemTime::now();
+        let day = std::time::Duration::from_secs(60 * 60 * 24);
+
+        for (name, age_days) in [
+            ("export_a.csv", 3),
+            ("export_b.csv", 1),
+            ("export_c.csv", 2),
+            ("notes.txt", 0),
+        ] {
+            let file = File::create(dir.path().join(name)).expect("Failed to create file");
+            file.set_modified(now - day * age_days)
+                .expect("Failed to set modified time");
+        }
+        std::fs::create_dir(dir.path().join("newer_subdir.csv")).expect("Failed to create subdirectory");
+
+        let latest = latest_in_directory(dir.path()).expect("Failed to find latest csv");
+
+        assert_eq!(latest.file_name().unwrap(), "export_b.csv");
+    }
+
+    #[test]
+    fn test_latest_in_directory_empty() {
+        let dir = tempdir().expect("Failed to create temp directory");
+        File::create(dir.path().join("notes.txt")).expect("Failed to create file");
+
+        assert!(matches!(latest_in_directory(dir.path()), Err(Error::NoMatchingFiles)));
+    }
+
+    #[test]
+    fn test_sort_by_match_select() {
+        let dir = tempdir().expect("Failed to create temp directory");
+        let now = SystemTime::now();
+        let day = std::time::Duration::from_secs(60 * 60 * 24);
+        for (name, contents, age_days) in [
+            ("file_b.csv", "bb", 1),
+            ("file_a.csv", "a", 2),
+            ("file_c.csv", "ccc", 3),
+        ] {
+            let path = dir.path().join(name);
+            std::fs::write(&path, contents).expect("Failed to create file");
+            File::options()
+                .write(true)
+                .open(&path)
+                .and_then(|file| file.set_modified(now - day * age_days))
+                .expect("Failed to set modified time");
+        }
+
+        for (match_select, expected) in [
+            (MatchSelect::Newest, "file_b.csv"),
+            (MatchSelect::Oldest, "file_c.csv"),
+            (MatchSelect::Largest, "file_c.csv"),
+            (MatchSelect::Smallest, "file_a.csv"),
+            (MatchSelect::NameAsc, "file_a.csv"),
+            (MatchSelect::NameDesc, "file_c.csv"),
+        ] {
+            let mut files = std::fs::read_dir(dir.path())
+                .unwrap()
+                .map(|entry| entry.unwrap())
+                .collect::<Vec<_>>();
+            let sorted = sort_by_match_select(&mut files, match_select);
+            assert_eq!(sorted[0].file_name(), expected, "{match_select:?}");
+        }
+    }
+
+    #[test]
+    fn test_tied_with_first() {
+        let dir = tempdir().expect("Failed to create temp directory");
+        std::fs::write(dir.path().join("file_1.csv"), "a").expect("Failed to create file1");
+        std::fs::write(dir.path().join("file_2.csv"), "a").expect("Failed to create file2");
+        let entries = || std::fs::read_dir(dir.path()).unwrap().map(|entry| entry.unwrap());
+
+        // A directory can't hold two files of the same name, so the same file is listed twice to make a tie
+        let file_1 = || entries().find(|entry| entry.file_name() == "file_1.csv").unwrap();
+        let files = vec![
+            file_1(),
+            entries().find(|entry| entry.file_name() == "file_2.csv").unwrap(),
+            file_1(),
+        ];
+
+        let tied = tied_with_first(&files);
+        assert_eq!(tied, vec![dir.path().join("file_1.csv"), dir.path().join("file_1.csv")]);
+        assert_eq!(tied_with_first(&files[..2]).len(), 1);
+        assert!(tied_with_first(&[]).is_empty());
+
+        let message = Error::AmbiguousFileMatch(tied).to_string();
+        assert!(message.contains("2 files") && message.contains("file_1.csv"), "{message}");
+    }
+
+    #[test]
+    fn test_levenshtein_distance() {
+        assert_eq!(levenshtein_distance("kitten", "sitting"), 3);
+        assert_eq!(levenshtein_distance("", "abc"), 3);
+        assert_eq!(levenshtein_distance("same", "same"), 0);
+        assert_eq!(levenshtein_distance("naïve", "naive"), 1);
+    }
+
+    #[test]
+    fn test_parse_user_variable_path_fuzzy_match() {
+        let dir = tempdir().expect("Failed to create temp directory");
+        File::create(dir.path().join("exprot_2025.csv")).expect("Failed to create file1");
+        File::create(dir.path().join("notes.txt")).expect("Failed to create file2");
+        std::fs::create_dir(dir.path().join("export_.csv")).expect("Failed to create subdirectory");
+
+        let binding = dir.path().join(r"export_{\d+}.csv");
+        let path_str = binding.to_str().unwrap();
+
+        assert!(matches!(
+            parse_user_variable_path(path_str, None, false, MatchSelect::default()),
+            Err(Error::NoMatchingFiles)
+        ));
+        let resolved_path = parse_user_variable_path(path_str, None, true, MatchSelect::default())
+            .expect("Failed to pick the closest file");
+        assert_eq!(resolved_path.file_name().unwrap(), "exprot_2025.csv");
+    }
+
+    #[test]
+    fn test_parse_user_variable_path_fuzzy_match_empty_directory() {
+        let dir = tempdir().expect("Failed to create temp directory");
+        let binding = dir.path().join(r"export_{\d+}.csv");
+
+        assert!(matches!(
+            parse_user_variable_path(binding.to_str().unwrap(), None, true, MatchSelect::default()),
+            Err(Error::NoMatchingFiles)
+        ));
+    }
+
     #[test]
     fn test_parse_user_variable_path_without_regex() {
         let dir = tempdir().expect("Failed to create temp directory");
@@ -267,8 +813,59 @@ mod regex_filename {
         File::create(&file_path).expect("Failed to create file");
 
         let path_str = file_path.to_str().unwrap();
-        let resolved_path = parse_user_variable_path(path_str).expect("Failed to parse user variable path");
+        let resolved_path = parse_user_variable_path(path_str, None, false, MatchSelect::default())
+            .expect("Failed to parse user variable path");
 
         assert_eq!(resolved_path, file_path);
     }
+
+    #[test]
+    fn test_config_path_components() {
+        assert_eq!(config_path_components(r"\data\file.csv"), vec!["data", "file.csv"]);
+        assert_eq!(config_path_components("data/file.csv"), vec!["data", "file.csv"]);
+        assert_eq!(config_path_components(r"data\export_{\d+}.csv"), vec!["data", r"export_{\d+}.csv"]);
+    }
+
+    #[test]
+    fn test_resolve_if_relative_forward_slashes() {
+        let resolved = resolve_if_relative(Path::new("data/file.csv")).unwrap();
+        assert_eq!(resolved, current_dir().unwrap().join("data").join("file.csv"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_resolve_if_relative_backslashes_on_unix() {
+        let resolved = resolve_if_relative(Path::new(r"\data\file.csv")).unwrap();
+        assert_eq!(resolved, current_dir().unwrap().join("data").join("file.csv"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_parse_user_variable_path_with_regex_on_unix() {
+        let dir = tempdir().expect("Failed to create temp directory");
+        File::create(dir.path().join("file_123.csv")).expect("Failed to create file");
+        File::create(dir.path().join("other_456.csv")).expect("Failed to create file");
+
+        let path_str = format!(r"{}/file_{{\d+}}.csv", dir.path().display());
+        let resolved_path = parse_user_variable_path(&path_str, None, false, MatchSelect::default())
+            .expect("Failed to parse user variable path");
+
+        assert_eq!(resolved_path, dir.path().join("file_123.csv"));
+    }
+
+    #[test]
+    fn test_resolve_if_relative_absolute() {
+        let dir = tempdir().expect("Failed to create temp directory");
+        assert_eq!(resolve_if_relative(dir.path()).unwrap(), Cow::Borrowed(dir.path()));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_resolve_if_relative_non_utf8() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        let path = Path::new(OsStr::from_bytes(b"data/file_\xff.csv"));
+        assert_eq!(resolve_if_relative(path).unwrap(), current_dir().unwrap().join(path));
+    }
 }
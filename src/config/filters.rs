@@ -0,0 +1,98 @@
+use crate::prelude::{Deserialize, Serialize};
+
+/// Describes how the values of a [`FilterRule`] are compared against a cell.
+///
+/// # Variants
+///
+/// * `Exact` - The cell must equal one of the values (the legacy `include_cols_with` behaviour).
+/// * `Contains` - The cell must contain one of the values as a substring.
+/// * `Regex` - The cell must match one of the values, each compiled as a regular expression.
+/// * `NumericRange` - The cell must parse as a number within `[values[0], values[1]]`; an empty bound is unbounded.
+/// * `Prefix` - The cell must start with one of the values.
+///
+/// # Example
+///
+/// ```json
+/// { "column": "Amount", "mode": "numeric_range", "values": ["10", ""] }
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchMode {
+    #[default]
+    Exact,
+    Contains,
+    Regex,
+    NumericRange,
+    Prefix,
+}
+
+/// A single filter applied to a source column.
+///
+/// # Fields
+///
+/// * `column` - The name of the column to check.
+/// * `mode` - How the `values` are compared against the cell, see [`MatchMode`].
+/// * `values` - The values to compare against.
+///
+/// # Example
+///
+/// ```json
+/// "filters": [
+///   { "column": "Description", "mode": "contains", "values": ["refund"] },
+///   { "column": "MemberId", "mode": "prefix", "values": ["AB", "CD"] }
+/// ]
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct FilterRule {
+    pub column: String,
+
+    #[serde(default)]
+    pub mode: MatchMode,
+
+    #[serde(default)]
+    pub values: Vec<String>,
+}
+
+/// Inclusive date bounds for a `date_filters` column.
+///
+/// The cells are parsed with the config's `date_format`, while the bounds are always written as `YYYY-MM-DD`.
+///
+/// # Fields
+///
+/// * `from` - The earliest date kept, unbounded when unset.
+/// * `to` - The latest date kept, unbounded when unset.
+/// * `keep_unparseable` - When `false` (default), rows whose cell isn't a date in `date_format` are dropped.
+///   When `true`, they're kept.
+///
+/// # Example
+///
+/// ```json
+/// "date_filters": {
+///   "PostedOn": { "from": "2024-01-01", "to": "2024-03-31" }
+/// }
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct DateRange {
+    #[serde(default)]
+    pub from: Option<String>,
+
+    #[serde(default)]
+    pub to: Option<String>,
+
+    #[serde(default)]
+    pub keep_unparseable: bool,
+}
+
+/// How the results of the individual filters are combined for a row.
+///
+/// # Variants
+///
+/// * `And` - A row is kept only if every filter matches.
+/// * `Or` - A row is kept if any filter matches.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FilterLogic {
+    #[default]
+    And,
+    Or,
+}
@@ -0,0 +1,163 @@
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+
+use crate::cli::Cli;
+use crate::config::Config;
+use crate::prelude::*;
+
+/// Builds a starter `Config` from the headers of a source CSV file.
+///
+/// Every column becomes one of the `fields`. With `with_filters`, up to that many data rows are sampled and
+/// each low-cardinality column (at most `INIT_FILTER_MAX_DISTINCT` distinct values, fewer than the rows sampled)
+/// gets an `include_cols_with` entry listing its observed values. These entries are "commented out" with the
+/// `__` placeholder prefix, so they are ignored until the prefix is removed from the column name.
+///
+/// # Arguments
+///
+/// * `source` - The CSV file to read the headers (and sample rows) from.
+/// * `with_filters` - When set, the number of data rows to sample for filter suggestions.
+///
+/// # Returns
+///
+/// * `Result<Config>` - Returns the generated config, or an `Error` on failure.
+///
+/// # Errors
+///
+/// This function can return errors if the source cannot be opened or is not valid CSV.
+///
+/// # Example
+///
+/// ```ignore
+/// let config = init_from_source(Path::new("data.csv"), Some(1000))?;
+/// println!("{config}");
+/// ```
+pub fn init_from_source(source: &Path, with_filters: Option<usize>) -> Result<Config> {
+    let mut rdr = csv::Reader::from_path(source).map_err(|e| Error::CsvRead(format!("{}: {e}", source.display())))?;
+    let headers = rdr.headers()?.iter().map(ToString::to_string).collect::<Vec<_>>();
+
+    let mut include_cols_with = HashMap::new();
+    if let Some(sample_rows) = with_filters {
+        let mut distinct = vec![BTreeSet::new(); headers.len()];
+        let mut sampled = 0;
+        for record in rdr.records().take(sample_rows) {
+            let record = record?;
+            for (values, cell) in distinct.iter_mut().zip(record.iter()) {
+                if !cell.trim().is_empty() {
+                    values.insert(cell.to_string());
+                }
+            }
+            sampled += 1;
+        }
+
+        for (header, values) in headers.iter().zip(distinct) {
+            if !values.is_empty() && values.len() <= INIT_FILTER_MAX_DISTINCT && values.len() < sampled {
+                include_cols_with.insert(format!("__{header}"), values.into_iter().collect());
+            }
+        }
+        debug!("Suggested filters for {} of {} columns", include_cols_with.len(), headers.len());
+    }
+
+    Ok(Config {
+        source: source.to_path_buf(),
+        fields: headers,
+        unique_fields: vec![],
+        include_cols_with,
+        ..Default::default()
+    })
+}
+
+/// Generates a config for `--init-from-source` and writes it out.
+///
+/// The config is written to the `--config` path if given, otherwise to the default config location.
+/// An existing, non-empty config file is never overwritten.
+///
+/// # Arguments
+///
+/// * `cli` - The CLI arguments, holding `init_from_source` and `with_filters`.
+///
+/// # Returns
+///
+/// * `Result<PathBuf>` - Returns the path the config was written to, or an `Error` on failure.
+///
+/// # Errors
+///
+/// This function can return errors if the source cannot be read, the target config already exists,
+/// or the config cannot be written.
+///
+/// # Example
+///
+/// ```ignore
+/// let written = write_init_config(&cli)?;
+/// info!("Config written to: {}", written.display());
+/// ```
+pub fn write_init_config(cli: &Cli) -> Result<PathBuf> {
+    let source = cli.init_from_source.as_deref().ok_or(Error::ConfigSource)?;
+    let config = init_from_source(source, cli.with_filters)?;
+
+    let target = match &cli.config_file {
+        Some(config_file) => config_file.clone(),
+        None => crate::config::current_dir()?
+            .join(DEFAULT_CONFIG_DIR)
+            .join(DEFAULT_CONFIG_FILE),
+    };
+    if target.metadata().is_ok_and(|meta| meta.len() > 0) {
+        return Err(Error::Io(std::io::Error::new(
+            std::io::ErrorKind::AlreadyExists,
+            format!("Refusing to overwrite existing config: {}", target.display()),
+        )));
+    }
+    if let Some(parent) = target.parent()
+        && !parent.as_os_str().is_empty()
+    {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    std::fs::write(&target, config.to_string())?;
+    Ok(target)
+}
+
+#[cfg(test)]
+mod init_tests {
+    use tempfile::tempdir;
+
+    use super::*;
+
+    fn fixture(dir: &Path) -> PathBuf {
+        let source = dir.join("data.csv");
+        let mut contents = String::from("Id,Name,Status\n");
+        for id in 1..=20 {
+            let status = if id % 3 == 0 { "Closed" } else { "Open" };
+            contents.push_str(&format!("{id},Name{id},{status}\n"));
+        }
+        std::fs::write(&source, contents).unwrap();
+        source
+    }
+
+    #[test]
+    fn test_init_from_source_suggests_low_cardinality_filters() {
+        let dir = tempdir().unwrap();
+        let source = fixture(dir.path());
+
+        let config = init_from_source(&source, Some(100)).unwrap();
+
+        assert_eq!(config.fields, vec!["Id", "Name", "Status"]);
+        assert_eq!(config.include_cols_with.len(), 1);
+        assert_eq!(config.include_cols_with["__Status"], vec!["Closed", "Open"]);
+
+        // And they make it into the generated file, commented out
+        let written = config.to_string();
+        assert!(written.contains("\"__Status\""));
+        assert!(!written.contains("\"__Id\""));
+    }
+
+    #[test]
+    fn test_init_from_source_without_filters() {
+        let dir = tempdir().unwrap();
+        let source = fixture(dir.path());
+
+        let config = init_from_source(&source, None).unwrap();
+
+        assert_eq!(config.fields, vec!["Id", "Name", "Status"]);
+        assert!(config.include_cols_with.is_empty());
+    }
+}
@@ -0,0 +1,16 @@
+use crate::prelude::{Deserialize, Serialize};
+
+/// The format the source is read as.
+///
+/// # Variants
+///
+/// * `Csv` - Delimited text, read with the `delimiter`, quoting and `has_headers` settings (the default).
+/// * `Json` - A JSON array of objects. The columns are the union of the objects' keys, in the order they're first seen,
+///   and a key an object doesn't have is an empty cell.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum InputType {
+    #[default]
+    Csv,
+    Json,
+}
@@ -0,0 +1,23 @@
+use crate::prelude::{Deserialize, Serialize};
+
+/// Which file is picked when several files match a `{regex}` in `source`.
+///
+/// # Variants
+///
+/// * `Newest` - The most recently modified file (the default).
+/// * `Oldest` - The least recently modified file.
+/// * `Largest` - The biggest file.
+/// * `Smallest` - The smallest file.
+/// * `NameAsc` - The file whose name sorts first.
+/// * `NameDesc` - The file whose name sorts last.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchSelect {
+    #[default]
+    Newest,
+    Oldest,
+    Largest,
+    Smallest,
+    NameAsc,
+    NameDesc,
+}
@@ -1,10 +1,42 @@
+mod aggregate;
+mod builder;
+mod computed;
 mod core;
+mod dedup;
+mod delimiter;
+mod explain;
 mod file_path_finds;
-
+mod filters;
+mod init;
+mod input_type;
+mod match_select;
+mod on_error;
+mod remote;
+mod sort;
+mod transforms;
+mod trim;
+mod validation;
+
+pub use aggregate::{Aggregate, AggregateOp};
+pub use builder::ConfigBuilder;
+pub use computed::{ComputedColumn, ExprTerm};
 pub use core::Config;
+pub(crate) use core::normalize_header;
+pub use dedup::{AggOp, DedupBloom, DedupKeep};
+pub use delimiter::Delimiter;
+pub use file_path_finds::is_stream_file;
+pub use filters::{DateRange, FilterLogic, FilterRule, MatchMode};
+pub use init::write_init_config;
+pub use input_type::InputType;
+pub use match_select::MatchSelect;
+pub use on_error::OnError;
+pub use sort::{SortDirection, SortKey, SortKind};
 use std::borrow::Cow;
 use std::fs::DirEntry;
 use std::path::{Path, PathBuf};
+pub use transforms::{BooleanField, RunningTotal};
+pub use trim::Trim;
+pub use validation::{ColumnValidation, ValueType};
 
 use config::Value;
 use regex::Regex;
@@ -32,36 +64,75 @@ where
     raw_ext: Option<&'a str>,
 }
 
-#[allow(clippy::unnecessary_wraps)] // TODO: will need to change it over at some point
-fn is_relative(prov_path_str: impl AsRef<str>) -> crate::prelude::Result<PathBuf> {
-    if PathBuf::from(&prov_path_str.as_ref()).is_absolute() {
-        Ok(PathBuf::from(prov_path_str.as_ref()))
-    } else {
-        let prov_path_str = resolve_if_relative(Path::new(prov_path_str.as_ref()));
-        Ok(PathBuf::from(prov_path_str.as_ref()))
-    }
+fn is_relative(prov_path: impl AsRef<Path>) -> crate::prelude::Result<PathBuf> {
+    Ok(resolve_if_relative(prov_path.as_ref())?.into_owned())
 }
 
-pub(crate) fn resolve_if_relative(path: &'_ Path) -> Cow<'_, Path> {
-    let mut current_dir_str = current_dir().unwrap().to_str().unwrap().to_string();
-    let path_str = path.to_str().unwrap().to_string();
+/// Resolves a path from the config against the current directory (see `current_dir`), unless it is absolute.
+///
+/// # Arguments
+///
+/// * `path` - The path as provided by the user.
+///
+/// # Returns
+///
+/// * `Result<Cow<Path>>` - Returns the path itself when absolute, otherwise the path within the current directory.
+///
+/// # Errors
+///
+/// Returns `Error::Io` if the current directory can't be determined.
+pub(crate) fn resolve_if_relative(path: &'_ Path) -> crate::prelude::Result<Cow<'_, Path>> {
+    if path.is_absolute() {
+        return Ok(Cow::Borrowed(path));
+    }
 
+    let current_dir = current_dir()?;
     if path.eq(Path::new(".")) {
-        return Cow::Owned(PathBuf::from(current_dir_str));
+        return Ok(Cow::Owned(current_dir));
     }
 
-    if path.as_os_str().eq(current_dir_str.as_str()) {
-        current_dir_str.clone().push_str(&path_str);
-        return Cow::Owned(PathBuf::from(current_dir_str));
-    }
+    // For if user provides \\data\\required_name.csv vs data\\required_name.csv (or data/required_name.csv)
+    // in the config file at runtime - the components are joined with the separator of the platform.
+    // A path that isn't valid UTF-8 can't be written with the other separator, so it's joined as-is
+    let resolved = match path.to_str() {
+        Some(path_str) => config_path_components(path_str)
+            .into_iter()
+            .fold(current_dir, |resolved, component| resolved.join(component)),
+        None => current_dir.join(path),
+    };
+    Ok(Cow::Owned(resolved))
+}
 
-    // For if user provides \\data\\required_name.csv vs data\\required_name.csv in the config file at runtime
-    if !path_str.starts_with('\\') {
-        current_dir_str.push('\\');
+/// Splits a path as written in the config into its components, on `/` as well as `\`, so that a path written with
+/// either separator resolves on every platform. A separator within a `{regex}` is part of the pattern (e.g. `{\d+}`).
+///
+/// # Arguments
+///
+/// * `path_str` - The path as provided by the user, e.g. `\data\export_{\d+}.csv`.
+///
+/// # Returns
+///
+/// * `Vec<&str>` - The non-empty components of the path, e.g. `data` and `export_{\d+}.csv`.
+pub(crate) fn config_path_components(path_str: &str) -> Vec<&str> {
+    let mut components = Vec::new();
+    let mut depth = 0_usize;
+    let mut start = 0;
+
+    for (idx, c) in path_str.char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' => depth = depth.saturating_sub(1),
+            '/' | '\\' if depth == 0 => {
+                components.push(&path_str[start..idx]);
+                start = idx + 1;
+            }
+            _ => {}
+        }
     }
-    current_dir_str.push_str(&path_str);
+    components.push(&path_str[start..]);
 
-    Cow::Owned(PathBuf::from(current_dir_str))
+    components.retain(|component| !component.is_empty());
+    components
 }
 
 pub fn extract_cached_config_value(config: &config::Config, find_key_for: &str) -> crate::prelude::Result<String> {
@@ -125,7 +196,7 @@ fn compare_files_by_sizelen(first: &DirEntry, second: &DirEntry) -> std::cmp::Or
 ///
 /// # Example
 ///
-/// ```rust
+/// ```ignore
 /// let dir = current_dir().expect("Failed to get current directory");
 /// println!("Current directory: {:?}", dir);
 /// ```
@@ -146,7 +217,7 @@ pub fn current_dir() -> crate::Result<PathBuf> {
 ///
 /// # Example
 ///
-/// ```rust
+/// ```ignore
 /// let dir = current_dir().expect("Failed to get current directory");
 /// println!("Current directory: {:?}", dir);
 /// ```
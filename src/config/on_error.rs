@@ -0,0 +1,19 @@
+use crate::prelude::{Deserialize, Serialize};
+
+/// What happens when a source record can't be parsed, e.g. a row with more fields than the header row.
+///
+/// # Variants
+///
+/// * `Abort` - Stop the run with the parse error (the default).
+/// * `Skip` - Drop the malformed record and carry on, only logging it at debug verbosity.
+/// * `Warn` - Drop the malformed record and carry on, logging a warning for every one.
+///
+/// Either way the number of dropped records is reported once the source has been read.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum OnError {
+    #[default]
+    Abort,
+    Skip,
+    Warn,
+}
@@ -0,0 +1,251 @@
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use crate::prelude::*;
+
+/// Returns `true` if the provided config location is an `http://` or `https://` URL rather than a file path.
+pub fn is_remote(location: impl AsRef<Path>) -> bool {
+    location
+        .as_ref()
+        .to_str()
+        .is_some_and(|s| s.starts_with("http://") || s.starts_with("https://"))
+}
+
+/// A local, on-disk cache of config files fetched from a URL.
+///
+/// Each URL is cached as its own file under `dir`, so repeated runs within the `ttl` need no network
+/// access, and a stale copy can still be used when the fetch fails (e.g. when offline).
+///
+/// # Fields
+///
+/// * `dir` - The directory the cached files are stored in.
+/// * `ttl` - How long a cached file is used before it is fetched again.
+///
+/// # Example
+///
+/// ```ignore
+/// let cache = RemoteConfigCache::new(config_dir.join(REMOTE_CONFIG_CACHE_DIR), Duration::from_secs(3600));
+/// let config_path = cache.fetch("https://example.com/config.json")?;
+/// ```
+#[derive(Debug, Clone)]
+pub struct RemoteConfigCache {
+    dir: PathBuf,
+    ttl: Duration,
+}
+
+impl RemoteConfigCache {
+    /// Creates a new `RemoteConfigCache` storing its files in `dir`.
+    ///
+    /// # Arguments
+    ///
+    /// * `dir` - The directory to cache fetched configs in, created on first use.
+    /// * `ttl` - How long a cached config is used before it is fetched again.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - Returns a new `RemoteConfigCache` instance.
+    pub fn new(dir: PathBuf, ttl: Duration) -> Self {
+        Self { dir, ttl }
+    }
+
+    /// Returns a local path holding the config at `url`, fetching it over HTTP only when required.
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - The URL of the config file.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<PathBuf>` - Returns the path of the cached copy, or an `Error` on failure.
+    ///
+    /// # Errors
+    ///
+    /// See `fetch_with`.
+    pub fn fetch(&self, url: &str) -> Result<PathBuf> {
+        self.fetch_with(url, fetch_url)
+    }
+
+    /// Returns a local path holding the config at `url`, using `fetch` to download it when required.
+    ///
+    /// * A cached copy younger than the `ttl` is returned without calling `fetch`.
+    /// * Otherwise `fetch` is called and its result is written to the cache.
+    /// * If `fetch` fails and a (stale) cached copy exists, that copy is used and a warning is logged.
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - The URL of the config file, used as the cache key.
+    /// * `fetch` - Downloads the contents of `url`.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<PathBuf>` - Returns the path of the cached copy, or an `Error` on failure.
+    ///
+    /// # Errors
+    ///
+    /// * `Error::RemoteConfig` - If the fetch fails and there is no cached copy to fall back on.
+    /// * `Error::Io` - If the cache directory or file cannot be written.
+    pub fn fetch_with(&self, url: &str, fetch: impl FnOnce(&str) -> Result<String>) -> Result<PathBuf> {
+        let cache_path = self.cache_path(url);
+
+        if self.is_fresh(&cache_path) {
+            debug!("Using cached remote config for {url}: {cache_path:?}");
+            return Ok(cache_path);
+        }
+
+        match fetch(url) {
+            Ok(contents) => {
+                std::fs::create_dir_all(&self.dir)?;
+                std::fs::write(&cache_path, contents)?;
+                debug!("Fetched remote config {url} into {cache_path:?}");
+                Ok(cache_path)
+            }
+            Err(e) if cache_path.is_file() => {
+                warn!("Failed to fetch remote config ({e}), falling back to the cached copy at {cache_path:?}");
+                Ok(cache_path)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// The cache file for a URL. The URL is flattened into a readable file name, followed by a hash of the full URL
+    /// so that URLs which flatten alike (e.g. `a-b.json` and `a_b.json`) never share a file. Its extension is kept
+    /// (defaulting to `.json`) so the config format can still be detected.
+    fn cache_path(&self, url: &str) -> PathBuf {
+        let trimmed = url.split(['?', '#']).next().unwrap_or(url);
+        let extension = Path::new(trimmed)
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .filter(|ext| ["json", "toml", "yaml", "yml", "ini", "ron", "json5"].contains(ext))
+            .unwrap_or("json");
+
+        let name = url
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect::<String>();
+        let mut hasher = DefaultHasher::new();
+        url.hash(&mut hasher);
+
+        self.dir.join(format!("{name}_{:016x}.{extension}", hasher.finish()))
+    }
+
+    fn is_fresh(&self, cache_path: &Path) -> bool {
+        cache_path
+            .metadata()
+            .and_then(|meta| meta.modified())
+            .is_ok_and(|modified| {
+                SystemTime::now()
+                    .duration_since(modified)
+                    .is_ok_and(|age| age < self.ttl)
+            })
+    }
+}
+
+fn fetch_url(url: &str) -> Result<String> {
+    reqwest::blocking::get(url)
+        .and_then(reqwest::blocking::Response::error_for_status)
+        .and_then(reqwest::blocking::Response::text)
+        .map_err(|e| Error::RemoteConfig(e.to_string()))
+}
+
+#[cfg(test)]
+mod remote_tests {
+    use std::fs::File;
+
+    use tempfile::tempdir;
+
+    use super::*;
+
+    const URL: &str = "https://example.com/configs/config.json?v=1";
+
+    fn offline(_: &str) -> Result<String> {
+        Err(Error::RemoteConfig("network is unreachable".to_string()))
+    }
+
+    #[test]
+    fn test_is_remote() {
+        assert!(is_remote("https://example.com/config.json"));
+        assert!(is_remote("http://localhost:8080/config.json"));
+        assert!(!is_remote("config/config.json"));
+        assert!(!is_remote("C:\\config\\config.json"));
+    }
+
+    #[test]
+    fn test_fetch_writes_cache() {
+        let dir = tempdir().unwrap();
+        let cache = RemoteConfigCache::new(dir.path().join("remote_cache"), Duration::from_secs(60));
+
+        let path = cache
+            .fetch_with(URL, |_| Ok(r#"{"source": "a.csv"}"#.to_string()))
+            .unwrap();
+
+        assert_eq!(path.extension().unwrap(), "json");
+        assert_eq!(std::fs::read_to_string(path).unwrap(), r#"{"source": "a.csv"}"#);
+    }
+
+    #[test]
+    fn test_urls_that_flatten_alike_are_cached_apart() {
+        let dir = tempdir().unwrap();
+        let cache = RemoteConfigCache::new(dir.path().to_path_buf(), Duration::from_secs(60));
+
+        for (a, b) in [
+            ("https://example.com/a-b.json", "https://example.com/a_b.json"),
+            ("https://example.com/config.json?v=1", "https://example.com/config.json?v_1"),
+        ] {
+            let path_a = cache.fetch_with(a, |_| Ok(a.to_string())).unwrap();
+            let path_b = cache.fetch_with(b, |_| Ok(b.to_string())).unwrap();
+
+            assert_ne!(path_a, path_b);
+            assert_eq!(std::fs::read_to_string(path_a).unwrap(), a);
+            assert_eq!(std::fs::read_to_string(path_b).unwrap(), b);
+        }
+    }
+
+    #[test]
+    fn test_cache_hit_skips_fetch() {
+        let dir = tempdir().unwrap();
+        let cache = RemoteConfigCache::new(dir.path().to_path_buf(), Duration::from_secs(60));
+        cache.fetch_with(URL, |_| Ok("cached".to_string())).unwrap();
+
+        let path = cache
+            .fetch_with(URL, |_| panic!("a fresh cache must not be re-fetched"))
+            .unwrap();
+
+        assert_eq!(std::fs::read_to_string(path).unwrap(), "cached");
+    }
+
+    #[test]
+    fn test_cache_expiry_refetches() {
+        let dir = tempdir().unwrap();
+        let cache = RemoteConfigCache::new(dir.path().to_path_buf(), Duration::from_secs(60));
+        let path = cache.fetch_with(URL, |_| Ok("old".to_string())).unwrap();
+
+        File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(SystemTime::now() - Duration::from_secs(120))
+            .unwrap();
+
+        let path = cache.fetch_with(URL, |_| Ok("new".to_string())).unwrap();
+        assert_eq!(std::fs::read_to_string(path).unwrap(), "new");
+    }
+
+    #[test]
+    fn test_offline_falls_back_to_stale_cache() {
+        let dir = tempdir().unwrap();
+        let cache = RemoteConfigCache::new(dir.path().to_path_buf(), Duration::ZERO);
+        cache.fetch_with(URL, |_| Ok("stale".to_string())).unwrap();
+
+        let path = cache.fetch_with(URL, offline).unwrap();
+        assert_eq!(std::fs::read_to_string(path).unwrap(), "stale");
+    }
+
+    #[test]
+    fn test_offline_without_cache_errors() {
+        let dir = tempdir().unwrap();
+        let cache = RemoteConfigCache::new(dir.path().to_path_buf(), Duration::from_secs(60));
+
+        assert!(matches!(cache.fetch_with(URL, offline), Err(Error::RemoteConfig(_))));
+    }
+}
@@ -0,0 +1,131 @@
+use crate::prelude::{Deserialize, Serialize};
+
+/// The direction a single `sort_by` key sorts in.
+///
+/// # Variants
+///
+/// * `Asc` - From smallest to largest, also accepted as `ascending`.
+/// * `Desc` - From largest to smallest, also accepted as `descending`.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SortDirection {
+    #[serde(alias = "ascending")]
+    Asc,
+    #[serde(alias = "descending")]
+    Desc,
+}
+
+/// How the cells of a `sort_by` key are compared.
+///
+/// # Variants
+///
+/// * `Auto` - `Numeric` if most of the column's non-empty cells are numbers, otherwise `String` (the default).
+/// * `Numeric` - Compared as numbers. Cells that aren't numbers sort after those that are.
+/// * `Date` - Compared as dates (or date-times) in the given chrono format, e.g. `{ "date": "%d/%m/%Y" }`.
+///   Cells that don't match the format sort after those that do.
+/// * `String` - Compared as plain text.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SortKind {
+    #[default]
+    Auto,
+    Numeric,
+    Date(String),
+    String,
+}
+
+/// A single `sort_by` key.
+///
+/// Configured either as just the column name, or as an object that also sets its direction and kind.
+///
+/// # Fields
+///
+/// * `column` - The output column to sort by.
+/// * `direction` - The direction of this key. When unset, `sort_desc` decides.
+/// * `kind` - How the column's cells are compared, see [`SortKind`].
+///
+/// # Example
+///
+/// ```json
+/// "sort_by": [
+///   { "column": "Amount", "direction": "desc", "kind": "numeric" },
+///   { "column": "Date", "kind": { "date": "%d/%m/%Y" } },
+///   "Name"
+/// ]
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(from = "SortKeyRepr")]
+pub struct SortKey {
+    pub column: String,
+    pub direction: Option<SortDirection>,
+    pub kind: SortKind,
+}
+
+impl SortKey {
+    /// Creates a key sorting by `column`, in the `sort_desc` direction and with `SortKind::Auto`.
+    ///
+    /// # Arguments
+    ///
+    /// * `column` - The output column to sort by.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - Returns the new `SortKey`.
+    pub fn column(column: impl Into<String>) -> Self {
+        Self {
+            column: column.into(),
+            direction: None,
+            kind: SortKind::Auto,
+        }
+    }
+}
+
+/// The accepted forms of a `sort_by` entry: a bare column name, or the full key.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SortKeyRepr {
+    Column(String),
+    Key {
+        column: String,
+        #[serde(default)]
+        direction: Option<SortDirection>,
+        #[serde(default)]
+        kind: SortKind,
+    },
+}
+
+impl From<SortKeyRepr> for SortKey {
+    fn from(repr: SortKeyRepr) -> Self {
+        match repr {
+            SortKeyRepr::Column(column) => Self::column(column),
+            SortKeyRepr::Key {
+                column,
+                direction,
+                kind,
+            } => Self {
+                column,
+                direction,
+                kind,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod sort_tests {
+    use super::*;
+
+    #[test]
+    fn test_sort_key_forms() {
+        let keys: Vec<SortKey> = serde_json::from_str(
+            r#"["Name", { "column": "Amount", "direction": "descending", "kind": "numeric" }, { "column": "Date", "kind": { "date": "%d/%m/%Y" } }]"#,
+        )
+        .unwrap();
+
+        assert_eq!(keys[0], SortKey::column("Name"));
+        assert_eq!(keys[1].direction, Some(SortDirection::Desc));
+        assert_eq!(keys[1].kind, SortKind::Numeric);
+        assert_eq!(keys[2].direction, None);
+        assert_eq!(keys[2].kind, SortKind::Date("%d/%m/%Y".to_string()));
+    }
+}
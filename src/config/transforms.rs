@@ -0,0 +1,95 @@
+use crate::prelude::{Deserialize, Serialize};
+
+/// Normalizes a boolean-like column (`Y`/`N`, `1`/`0`, `yes`/`no`, ...) into a consistent representation.
+///
+/// Values are compared case-insensitively and ignoring surrounding whitespace.
+///
+/// # Fields
+///
+/// * `true_values` - Values that are considered true, written out as `output_true`.
+/// * `false_values` - Values that are considered false, written out as `output_false`.
+///   Only consulted when `passthrough_unrecognized` is set.
+/// * `output_true` - The representation written for true values (default `"true"`).
+/// * `output_false` - The representation written for false values (default `"false"`).
+/// * `passthrough_unrecognized` - When `false` (default), anything that isn't a true value is written as
+///   `output_false`. When `true`, values that are neither a true nor a false value are left untouched.
+///
+/// # Example
+///
+/// ```json
+/// "boolean_fields": {
+///   "IsActive": { "true_values": ["Y", "yes", "1"], "output_true": "true", "output_false": "false" }
+/// }
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct BooleanField {
+    #[serde(default)]
+    pub true_values: Vec<String>,
+
+    #[serde(default)]
+    pub false_values: Vec<String>,
+
+    #[serde(default = "default_output_true")]
+    pub output_true: String,
+
+    #[serde(default = "default_output_false")]
+    pub output_false: String,
+
+    #[serde(default)]
+    pub passthrough_unrecognized: bool,
+}
+
+impl BooleanField {
+    /// Maps a single cell value to its normalized representation.
+    ///
+    /// # Arguments
+    ///
+    /// * `val` - The cell value to normalize.
+    ///
+    /// # Returns
+    ///
+    /// * `String` - Returns `output_true`, `output_false`, or the original value if it is passed through.
+    pub fn normalize(&self, val: &str) -> String {
+        let is_in = |values: &[String]| values.iter().any(|v| v.trim().eq_ignore_ascii_case(val.trim()));
+
+        if is_in(&self.true_values) {
+            self.output_true.clone()
+        } else if !self.passthrough_unrecognized || is_in(&self.false_values) {
+            self.output_false.clone()
+        } else {
+            val.to_string()
+        }
+    }
+}
+
+/// Adds a column holding the cumulative sum of a numeric column, in output row order.
+///
+/// # Fields
+///
+/// * `source` - The retained column whose values are summed.
+/// * `into` - The name of the new column the running total is written to.
+/// * `strict_numeric` - When `false` (default), cells that aren't numbers contribute zero.
+///   When `true`, such a cell is an error.
+///
+/// # Example
+///
+/// ```json
+/// "running_total": { "source": "Amount", "into": "Balance" }
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct RunningTotal {
+    pub source: String,
+
+    pub into: String,
+
+    #[serde(default)]
+    pub strict_numeric: bool,
+}
+
+fn default_output_true() -> String {
+    "true".to_string()
+}
+
+fn default_output_false() -> String {
+    "false".to_string()
+}
@@ -0,0 +1,32 @@
+use crate::prelude::{Deserialize, Serialize};
+
+/// Which cells have their leading and trailing whitespace trimmed as the source is read.
+///
+/// Header names are always normalized (see `normalize_header`), so only the trimming of values makes a difference.
+///
+/// # Variants
+///
+/// * `None` - Nothing is trimmed (the default).
+/// * `Headers` - Only the header row, which is already normalized - so the same as `None`.
+/// * `Fields` - Only the data rows, so padded values like `" Rollover "` match filter values.
+/// * `All` - Both the header row and the data rows - so the same as `Fields`.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Trim {
+    #[default]
+    None,
+    Headers,
+    Fields,
+    All,
+}
+
+impl From<Trim> for csv::Trim {
+    fn from(trim: Trim) -> Self {
+        match trim {
+            Trim::None => csv::Trim::None,
+            Trim::Headers => csv::Trim::Headers,
+            Trim::Fields => csv::Trim::Fields,
+            Trim::All => csv::Trim::All,
+        }
+    }
+}
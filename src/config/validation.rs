@@ -0,0 +1,105 @@
+use crate::prelude::{Deserialize, Serialize};
+
+/// The type a column's values are expected to have.
+///
+/// # Variants
+///
+/// * `Integer` - A whole number, e.g. `-12`.
+/// * `Number` - Any number, e.g. `3.14`.
+/// * `Boolean` - `true` or `false`, compared case-insensitively.
+/// * `Date` - A date in `YYYY-MM-DD` form.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ValueType {
+    Integer,
+    Number,
+    Boolean,
+    Date,
+}
+
+impl ValueType {
+    /// Checks whether a (non-empty) value is of this type.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The cell value to check, surrounding whitespace is ignored.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - Returns `true` if the value parses as this type.
+    pub fn matches(self, value: &str) -> bool {
+        let value = value.trim();
+        match self {
+            Self::Integer => value.parse::<i64>().is_ok(),
+            Self::Number => value.parse::<f64>().is_ok(),
+            Self::Boolean => value.eq_ignore_ascii_case("true") || value.eq_ignore_ascii_case("false"),
+            Self::Date => chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d").is_ok(),
+        }
+    }
+}
+
+impl std::fmt::Display for ValueType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Integer => write!(f, "integer"),
+            Self::Number => write!(f, "number"),
+            Self::Boolean => write!(f, "boolean"),
+            Self::Date => write!(f, "date"),
+        }
+    }
+}
+
+/// The expectations a single output column is validated against.
+///
+/// Empty values are only checked by `required` - the type and domain checks apply to non-empty values.
+///
+/// # Fields
+///
+/// * `required` - The value must not be empty (or only whitespace).
+/// * `value_type` - The type every value must have, configured as `type`.
+/// * `allowed` - When not empty, the only values allowed (compared exactly).
+///
+/// # Example
+///
+/// ```json
+/// "validations": {
+///   "Id": { "required": true, "type": "integer" },
+///   "Status": { "allowed": ["Open", "Closed"] }
+/// }
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct ColumnValidation {
+    #[serde(default)]
+    pub required: bool,
+
+    #[serde(default, rename = "type")]
+    pub value_type: Option<ValueType>,
+
+    #[serde(default)]
+    pub allowed: Vec<String>,
+}
+
+#[cfg(test)]
+mod validation_config_tests {
+    use super::*;
+
+    #[test]
+    fn test_value_types() {
+        assert!(ValueType::Integer.matches(" -12 "));
+        assert!(!ValueType::Integer.matches("1.5"));
+        assert!(ValueType::Number.matches("1.5"));
+        assert!(ValueType::Boolean.matches("TRUE"));
+        assert!(!ValueType::Boolean.matches("yes"));
+        assert!(ValueType::Date.matches("2025-01-15"));
+        assert!(!ValueType::Date.matches("2025-02-30"));
+    }
+
+    #[test]
+    fn test_deserialize_column_validation() {
+        let validation: ColumnValidation = serde_json::from_str(r#"{ "required": true, "type": "date" }"#).unwrap();
+
+        assert!(validation.required);
+        assert_eq!(validation.value_type, Some(ValueType::Date));
+        assert!(validation.allowed.is_empty());
+    }
+}
@@ -1,47 +1,251 @@
+use std::collections::VecDeque;
 use std::fs::File;
+use std::io::{BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
 
-use csv::Reader;
+use csv::{Reader, StringRecord};
+use flate2::read::MultiGzDecoder;
+use rayon::prelude::*;
 
-use crate::config::Config;
+use crate::config::{Config, DedupKeep, InputType, OnError, is_stream_file};
 pub(crate) use crate::prelude::*;
-use crate::processing::{CsvHandler, CsvProcessor};
+use crate::processing::{
+    BloomFilter, CsvHandler, CsvProcessor, DedupPreviewEntry, ExternalSorter, Stats, composite_key, decode_source,
+    json_to_csv, normalize_headers,
+};
+use crate::progress::SourceProgress;
 use crate::retained::RetainedData;
 
 pub struct CsvPipeline {
-    reader: Reader<File>,
+    reader: Reader<Box<dyn Read>>,
+    /// The remaining files of a glob or list `source`, already read past their (checked) headers.
+    pending: VecDeque<(PathBuf, Reader<Box<dyn Read>>)>,
     handler: CsvHandler,
     processor: CsvProcessor,
+    bounds: ReadBounds,
+    /// Counts of the records read, the rows that passed the filters, the rows kept (past `dedup_bloom`),
+    /// and the rows removed by `unique_fields` deduplication, for `stats`.
+    records_read: usize,
+    passed_filters: usize,
+    kept: usize,
+    dedup_removed: usize,
+    bloom: Option<(BloomFilter, Vec<usize>)>,
+    parallel: bool,
+    read_errors: ReadErrors,
+    /// The `progress` indicator, shown until every source has been read.
+    progress: Option<SourceProgress>,
+}
+
+/// Where reading stops: once `limit` rows have been kept, or at the end of the `row_range` of record indices to process.
+#[derive(Debug, Clone, Copy)]
+struct ReadBounds {
+    limit: Option<usize>,
+    /// The start and (exclusive) end index of the records to process.
+    row_range: Option<(usize, usize)>,
+}
+
+impl ReadBounds {
+    /// Whether no more records need to be read, given the rows kept and the records read so far.
+    fn done(self, kept: usize, records_read: usize) -> bool {
+        self.limit.is_some_and(|limit| kept >= limit) || self.row_range.is_some_and(|(_, end)| records_read >= end)
+    }
+
+    /// Whether the record at this index is in the `row_range` (always, without one).
+    fn in_range(self, index: usize) -> bool {
+        self.row_range.is_none_or(|(start, _)| index >= start)
+    }
+}
+
+/// Applies the `on_error` policy to records that fail to parse, collecting the ones that are skipped.
+struct ReadErrors {
+    policy: OnError,
+    errors: Vec<(usize, String)>,
+}
+
+impl ReadErrors {
+    /// Handles a record that failed to parse.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<()>` - Returns `Ok(())` if the record was skipped, or the error when the policy is `Abort`.
+    ///   Errors reading the source itself (I/O errors) are never skipped.
+    fn handle(&mut self, e: csv::Error) -> Result<()> {
+        if matches!(e.kind(), csv::ErrorKind::Io(_)) {
+            return Err(e.into());
+        }
+
+        let line = e.position().map_or(0, |pos| pos.line() as usize);
+        match self.policy {
+            OnError::Abort => return Err(e.into()),
+            OnError::Skip => debug!("Skipping malformed record on line {line}: {e}"),
+            OnError::Warn => warn!("Skipping malformed record on line {line}: {e}"),
+        }
+        self.errors.push((line, e.to_string()));
+        Ok(())
+    }
 }
 
 impl CsvPipeline {
+    /// Creates a new `CsvPipeline` reading from the configured `source`.
+    ///
+    /// A `source` of `-` reads from stdin, anything else is opened as a file. A FIFO (named pipe) source
+    /// is opened the same way, and read front to back as its writer produces data.
+    /// A gzip compressed source (a `.gz` extension, or the `gzip` setting) is decompressed as it is read.
+    /// When a glob or list `source` resolved to several `sources`, they are all opened and their headers checked
+    /// against the first one's up front, then read one after another as if they were a single file.
+    /// With `progress`, a progress bar of the bytes read out of the size of every source is shown until they have all
+    /// been read, or a spinner with the number of records read when that size can't be known (stdin, named pipes).
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - A reference to a `Config` instance containing the configuration settings.
+    /// * `retained_data` - A mutable reference to `RetainedData` to store the processed data.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Self>` - Returns a new `CsvPipeline` instance, or an `Error` on failure.
+    ///
+    /// # Errors
+    ///
+    /// This function can return errors if a source cannot be opened or its headers cannot be read,
+    /// or with several `sources`, if their headers differ from those of the first.
     pub fn new(config: &Config, retained_data: &mut RetainedData) -> Result<Self> {
-        let mut reader = csv::ReaderBuilder::new()
-            .has_headers(config.has_headers)
-            .from_path(&config.source)
-            .map_err(|e| Error::CsvRead(format!("Failed to read CSV file from source provided: {e}")))?;
+        let progress = config.progress.then(|| SourceProgress::new(sources_size(config)));
+        let input = open_source(config, &config.source, progress.as_ref())?;
+        let mut pipeline = Self::from_reader(config, retained_data, input)?;
+
+        for path in config.sources.iter().skip(1) {
+            let mut reader = csv_reader(config, open_source(config, path, progress.as_ref())?)?;
+            let first_row = reader.headers().map_err(|e| Error::CsvHeaders(e.to_string()))?;
+            let headers = if config.has_header_row() {
+                normalize_headers(first_row)
+            } else {
+                synthetic_headers(first_row.len())
+            };
+
+            if headers.iter().ne(&retained_data.all_headers) {
+                return Err(Error::CsvHeaders(format!(
+                    "the headers of {path:?} ({:?}) don't match those of {:?} ({:?})",
+                    headers.iter().collect::<Vec<_>>(),
+                    config.source,
+                    retained_data.all_headers
+                )));
+            }
+            pipeline.pending.push_back((path.clone(), reader));
+        }
+
+        pipeline.progress = progress;
+        Ok(pipeline)
+    }
+
+    /// Creates a new `CsvPipeline` reading from any `Read` implementation, rather than the configured `source`.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - A reference to a `Config` instance containing the configuration settings.
+    /// * `retained_data` - A mutable reference to `RetainedData` to store the processed data.
+    /// * `input` - The reader to pull CSV data from.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Self>` - Returns a new `CsvPipeline` instance, or an `Error` on failure.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let input = std::io::Cursor::new("Id,Name\n1,Alice\n");
+    /// let pipeline = CsvPipeline::from_reader(&config, &mut retained_data, Box::new(input))?;
+    /// ```
+    pub fn from_reader(config: &Config, retained_data: &mut RetainedData, input: Box<dyn Read>) -> Result<Self> {
+        let mut reader = csv_reader(config, input)?;
+
+        let first_row = reader.headers().map_err(|e| Error::CsvHeaders(e.to_string()))?;
+        if config.input_type == InputType::Csv
+            && let Some(warning) = header_setting_warning(config.has_headers, first_row)
+        {
+            warn!("{warning}");
+        }
+
+        // Without headers the first row is still read as data, so the columns are given synthetic names instead
+        let headers = if config.has_header_row() {
+            first_row.clone()
+        } else {
+            synthetic_headers(first_row.len())
+        };
 
         #[rustfmt::skip]
         let handler = CsvHandler::new(
             config,
             retained_data,
-            reader.headers().map_err(|e| {
-                Error::CsvHeaders(e.to_string())
-            })?,
-        );
+            &headers,
+        )?;
+
+        if let Some((start, end)) = config.row_range
+            && start > end
+        {
+            return Err(Error::InvalidConfig(format!("the row_range start {start} is past its end {end}")));
+        }
 
         let processor = CsvProcessor::new(config);
 
+        if !config.dedup_aggregate.is_empty() {
+            if config.unique_fields.is_empty() {
+                warn!("dedup_aggregate is set without any unique_fields, nothing will be aggregated");
+            } else {
+                // Fail before reading any rows, rather than once they have all been read
+                processor.dedup_aggregate_idxs(retained_data)?;
+            }
+        }
+
+        let bloom = match &config.dedup_bloom {
+            Some(_) if !config.deduplicates() => {
+                warn!("dedup_bloom is set without any unique_fields, nothing will be deduplicated while reading");
+                None
+            }
+            Some(settings) => {
+                if config.dedup_keep == DedupKeep::Last || config.dedup_count || !config.dedup_aggregate.is_empty() {
+                    warn!(
+                        "dedup_bloom drops duplicates as they are read, so dedup_keep = last, dedup_count and dedup_aggregate have no effect"
+                    );
+                }
+                Some((BloomFilter::new(settings)?, processor.dedup_key_idxs(retained_data)?))
+            }
+            None => None,
+        };
+
         Ok(Self {
             reader,
+            pending: VecDeque::new(),
             handler,
             processor,
+            // `head` can stop the read early just like `limit`, whichever is smaller wins
+            bounds: ReadBounds {
+                limit: match (config.limit, config.head) {
+                    (Some(limit), Some(head)) => Some(limit.min(head)),
+                    (limit, head) => limit.or(head),
+                },
+                row_range: config.row_range,
+            },
+            records_read: 0,
+            passed_filters: 0,
+            kept: 0,
+            dedup_removed: 0,
+            bloom,
+            parallel: config.parallel,
+            progress: None,
+            read_errors: ReadErrors {
+                policy: config.on_error,
+                errors: Vec::new(),
+            },
         })
     }
 
     /// Processes the CSV data and updates the retained data.
     ///
     /// This function iterates over the records in the CSV reader, applies filters using the `CsvHandler`,
-    /// and retains the specified columns in the `retained_data`.
+    /// and retains the specified columns in the `retained_data`. Any `post_filters` are then applied
+    /// to the projected row before it is kept. Reading stops as soon as `limit` (or `head`) rows have been kept,
+    /// or the end of the `row_range` is reached.
     ///
     /// # Arguments
     ///
@@ -59,24 +263,1165 @@ impl CsvPipeline {
     ///
     /// # Example
     ///
-    /// ```rust
+    /// ```ignore
     /// let mut rdr = csv::Reader::from_path("data.csv").expect("Failed to open CSV file");
     /// processor.process(&mut retained_data, &handler, &mut rdr).expect("Failed to process CSV data");
     /// ```
     pub fn process(&mut self, retained_data: &mut RetainedData) -> Result<()> {
-        for record_result in self.reader.records() {
-            let record = record_result?;
+        self.process_with(|retained| {
+            retained_data.data.push(retained);
+            Ok(())
+        })
+    }
+
+    /// Processes the CSV data, handing each retained row to `sink` instead of collecting them.
+    ///
+    /// This is the streaming form of `process`, used when rows shouldn't all be held in memory at once.
+    /// When a `limit` is configured, no further records are read once `limit` rows have been handed to `sink`.
+    /// With a `row_range`, the records before its start are skipped, and no records are read past its end.
+    /// With `dedup_bloom`, rows whose `unique_fields` key was (probably) seen before are never handed to `sink`.
+    /// With `parallel`, records are read in batches that are filtered and projected on all cores - see `process_parallel`.
+    /// Records that fail to parse abort the read, unless `on_error` says to skip them.
+    ///
+    /// # Arguments
+    ///
+    /// * `sink` - A closure called with every projected row that passes the filters.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<()>` - Returns `Ok(())` on success, or the first `Error` from reading or from `sink`.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// pipeline.process_with(|row| sorter.push(row)).expect("Failed to process CSV data");
+    /// ```
+    pub fn process_with(&mut self, mut sink: impl FnMut(Vec<String>) -> Result<()>) -> Result<()> {
+        loop {
+            if self.parallel {
+                self.process_parallel(&mut sink)?;
+            } else {
+                self.process_sequential(&mut sink)?;
+            }
+
+            if !self.next_source() {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Counts the records that pass the filters, without collecting or handing on any rows - for `--count`.
+    ///
+    /// Records are only projected (see `CsvHandler::keep_columns`) when there are `post_filters` to check, so `fields`
+    /// may be left empty. As nothing is kept, `dedup_bloom` doesn't apply, but a `limit` still caps the count,
+    /// and only the records in the `row_range` are counted.
+    /// Records that fail to parse abort the count, unless `on_error` says to skip them.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<usize>` - Returns the number of matching records, or the first `Error` from reading.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let matching = pipeline.count().expect("Failed to count CSV data");
+    /// ```
+    pub fn count(&mut self) -> Result<usize> {
+        loop {
+            let mut records = self.reader.records();
+            loop {
+                // Checked before pulling the next record, so nothing past the limit is read
+                if self.bounds.done(self.kept, self.records_read) {
+                    break;
+                }
+                let Some(record_result) = records.next() else {
+                    break;
+                };
+                let in_range = self.bounds.in_range(self.records_read);
+                self.records_read += 1;
+                if let Some(progress) = &self.progress {
+                    progress.records_read(self.records_read);
+                }
+                if !in_range {
+                    continue;
+                }
+
+                let record = match record_result {
+                    Ok(record) => record,
+                    Err(e) => {
+                        self.read_errors.handle(e)?;
+                        continue;
+                    }
+                };
+
+                if self.handler.row_passes_filters(&record)
+                    && (!self.handler.has_post_filters()
+                        || self
+                            .handler
+                            .row_passes_post_filters(&self.handler.keep_columns(&record)))
+                {
+                    self.passed_filters += 1;
+                    self.kept += 1;
+                }
+            }
+
+            if !self.next_source() {
+                return Ok(self.kept);
+            }
+        }
+    }
+
+    /// Moves on to the next of several `sources` once the current one has been read, unless the `limit` or the end of
+    /// the `row_range` was reached. When reading is done, the `progress` indicator is cleared.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - Returns `true` if there is another source to read, `false` when reading is done.
+    fn next_source(&mut self) -> bool {
+        let next = if self.bounds.done(self.kept, self.records_read) {
+            None
+        } else {
+            self.pending.pop_front()
+        };
+
+        match next {
+            Some((path, reader)) => {
+                debug!("Reading the next source: {path:?}");
+                self.reader = reader;
+                true
+            }
+            None => {
+                self.progress = None;
+                false
+            }
+        }
+    }
+
+    /// The sequential form of `process_with`, reading and handing over one record at a time.
+    ///
+    /// # Arguments
+    ///
+    /// * `sink` - A closure called with every projected row that passes the filters.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<()>` - Returns `Ok(())` on success, or the first `Error` from reading or from `sink`.
+    fn process_sequential(&mut self, mut sink: impl FnMut(Vec<String>) -> Result<()>) -> Result<()> {
+        let mut records = self.reader.records();
+        loop {
+            // Checked before pulling the next record, so nothing past the limit is read
+            if self.bounds.done(self.kept, self.records_read) {
+                debug!("Reached the limit of {} rows or the end of the row_range, stopping early", self.kept);
+                break;
+            }
+            let Some(record_result) = records.next() else {
+                break;
+            };
+            let in_range = self.bounds.in_range(self.records_read);
+            self.records_read += 1;
+            if let Some(progress) = &self.progress {
+                progress.records_read(self.records_read);
+            }
+            if !in_range {
+                continue;
+            }
+
+            let record = match record_result {
+                Ok(record) => record,
+                Err(e) => {
+                    self.read_errors.handle(e)?;
+                    continue;
+                }
+            };
 
             if self.handler.row_passes_filters(&record) {
                 let retained = self.handler.keep_columns(&record);
-                retained_data.data.push(retained);
+                if self.handler.row_passes_post_filters(&retained) {
+                    self.passed_filters += 1;
+                    if not_bloom_duplicate(&mut self.bloom, &retained) {
+                        sink(retained)?;
+                        self.kept += 1;
+                    }
+                }
             }
         }
 
         Ok(())
     }
 
-    pub fn deduplicate(&mut self, retained_data: &mut RetainedData) {
-        self.processor.deduplicate(retained_data);
+    /// The `parallel` form of `process_with`: records are read in batches of `PARALLEL_BATCH_ROWS`,
+    /// and each batch is filtered and projected across all cores.
+    ///
+    /// The rows of a batch are still handed to `sink` in input order, and `dedup_bloom` and `limit` are applied
+    /// as they are handed over, so the result is the same as the sequential read. As a whole batch is read at once,
+    /// up to `PARALLEL_BATCH_ROWS` records past the `limit` may be read (but not kept). A batch never reads past the
+    /// end of the `row_range`, and the records before its start are dropped from the batch.
+    /// When a record fails to parse, the rows of the batch before it are still handed to `sink` before the error is returned.
+    ///
+    /// # Arguments
+    ///
+    /// * `sink` - A closure called with every projected row that passes the filters.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<()>` - Returns `Ok(())` on success, or the first `Error` from reading or from `sink`.
+    fn process_parallel(&mut self, mut sink: impl FnMut(Vec<String>) -> Result<()>) -> Result<()> {
+        let handler = &self.handler;
+        let read_errors = &mut self.read_errors;
+        let mut records = self.reader.records();
+        let mut batch = Vec::with_capacity(PARALLEL_BATCH_ROWS);
+
+        loop {
+            batch.clear();
+            let (start, end) = self.bounds.row_range.unwrap_or((0, usize::MAX));
+            let mut read = 0;
+            let mut read_error = None;
+            for record_result in records
+                .by_ref()
+                .take(PARALLEL_BATCH_ROWS.min(end.saturating_sub(self.records_read)))
+            {
+                read += 1;
+                if self.records_read + read <= start {
+                    continue;
+                }
+                match record_result {
+                    Ok(record) => batch.push(record),
+                    Err(e) => {
+                        if let Err(e) = read_errors.handle(e) {
+                            read_error = Some(e);
+                            break;
+                        }
+                    }
+                }
+            }
+            if read == 0 {
+                return Ok(());
+            }
+            self.records_read += read;
+            if let Some(progress) = &self.progress {
+                progress.records_read(self.records_read);
+            }
+
+            let retained = batch
+                .par_iter()
+                .filter(|record| handler.row_passes_filters(record))
+                .map(|record| handler.keep_columns(record))
+                .filter(|retained| handler.row_passes_post_filters(retained))
+                .collect::<Vec<_>>();
+
+            for retained in retained {
+                if self.bounds.limit.is_some_and(|limit| self.kept >= limit) {
+                    debug!("Reached the limit of {} rows, stopping early", self.kept);
+                    return Ok(());
+                }
+                self.passed_filters += 1;
+                if not_bloom_duplicate(&mut self.bloom, &retained) {
+                    sink(retained)?;
+                    self.kept += 1;
+                }
+            }
+
+            if let Some(e) = read_error {
+                return Err(e);
+            }
+        }
+    }
+
+    /// Returns the records that failed to parse and were skipped under the `on_error` policy, in input order.
+    ///
+    /// # Returns
+    ///
+    /// * `&[(usize, String)]` - The source line number (counting from 1, `0` when unknown) and error message of each record.
+    pub fn errors(&self) -> &[(usize, String)] {
+        &self.read_errors.errors
+    }
+
+    pub fn deduplicate(&mut self, retained_data: &mut RetainedData) -> Result<()> {
+        self.dedup_removed += self.processor.deduplicate(retained_data)?;
+        Ok(())
+    }
+
+    /// Records how many rows an `external` sort wrote, the rows it dropped being the duplicates it removed.
+    pub fn external_written(&mut self, written: usize) {
+        self.dedup_removed += self.kept.saturating_sub(written);
+    }
+
+    /// Returns the row counts of the run so far, see `Stats`.
+    ///
+    /// # Arguments
+    ///
+    /// * `rows_kept` - The number of rows in the output, which only the caller knows after `head`, `tail`, `sample` etc.
+    ///
+    /// # Returns
+    ///
+    /// * `Stats` - The counts of the rows read, skipped as malformed, filtered out, removed as duplicates and kept.
+    pub fn stats(&self, rows_kept: usize) -> Stats {
+        let rows_malformed = self.read_errors.errors.len();
+        Stats {
+            rows_read: self.records_read,
+            rows_malformed,
+            rows_filtered_out: self.records_read.saturating_sub(rows_malformed + self.passed_filters),
+            rows_deduplicated: self.passed_filters - self.kept + self.dedup_removed,
+            rows_kept,
+        }
+    }
+
+    pub fn aggregate(&self, retained_data: &mut RetainedData) -> Result<()> {
+        self.processor.aggregate(retained_data)
+    }
+
+    pub fn running_total(&self, retained_data: &mut RetainedData) -> Result<()> {
+        self.processor.running_total(retained_data)
+    }
+
+    pub fn delta(&self, retained_data: &mut RetainedData, previous: &RetainedData) -> Result<()> {
+        self.processor.delta(retained_data, previous)
+    }
+
+    pub fn check_row_count(&self, actual: usize) -> Result<()> {
+        self.processor.check_row_count(actual)
+    }
+
+    pub fn external_sorter(&self, retained_data: &RetainedData, budget_bytes: usize) -> Result<ExternalSorter> {
+        self.processor.external_sorter(retained_data, budget_bytes)
+    }
+
+    pub fn dedup_preview(&self, retained_data: &RetainedData, top_n: usize) -> Result<Vec<DedupPreviewEntry>> {
+        self.processor.dedup_preview(retained_data, top_n)
+    }
+}
+
+/// Checks a row against the `dedup_bloom` filter, if there is one, recording its key as seen.
+///
+/// # Returns
+///
+/// * `bool` - Returns `false` if the row's `unique_fields` key was (probably) seen before, otherwise `true`.
+fn not_bloom_duplicate(bloom: &mut Option<(BloomFilter, Vec<usize>)>, retained: &[String]) -> bool {
+    match bloom {
+        Some((bloom, key_idxs)) => bloom.insert(&composite_key(retained, key_idxs)),
+        None => true,
+    }
+}
+
+/// Opens one source for reading: stdin for `-`, a named pipe as a stream, otherwise a file,
+/// decompressed as gzip when it has a `.gz` extension or the `gzip` setting is on.
+///
+/// # Arguments
+///
+/// * `config` - The `Config` with the `gzip` setting.
+/// * `path` - The path of the source to open.
+/// * `progress` - The `progress` indicator, advanced by the (still compressed) bytes read from the source.
+///
+/// # Returns
+///
+/// * `Result<Box<dyn Read>>` - The (decompressing) reader of the source, transcoded to UTF-8 (see `decode_source`),
+///   and as CSV when the `input_type` is `json`.
+///
+/// # Errors
+///
+/// This function will return an error if the source cannot be opened, the `encoding` is unknown, or a `json` source isn't a JSON array of objects.
+fn open_source(config: &Config, path: &Path, progress: Option<&SourceProgress>) -> Result<Box<dyn Read>> {
+    let input: Box<dyn Read> = if path.as_os_str() == "-" {
+        Box::new(std::io::stdin().lock())
+    } else if is_stream_file(path) {
+        debug!("Opening {path:?} as a stream, this waits until something writes to it");
+        Box::new(
+            File::open(path).map_err(|e| Error::CsvRead(format!("Failed to open the source stream provided: {e}")))?,
+        )
+    } else {
+        Box::new(
+            File::open(path)
+                .map_err(|e| Error::CsvRead(format!("Failed to read CSV file from source provided: {e}")))?,
+        )
+    };
+
+    let input = match progress {
+        Some(progress) => progress.wrap_read(input),
+        None => input,
+    };
+
+    let input: Box<dyn Read> = if config.reads_gzip(path) {
+        debug!("Decompressing {path:?} as gzip");
+        Box::new(MultiGzDecoder::new(input))
+    } else {
+        input
+    };
+    let input = decode_source(input, config.encoding.as_deref())?;
+
+    match config.input_type {
+        InputType::Csv => Ok(input),
+        InputType::Json => json_to_csv(input),
+    }
+}
+
+/// Adds up the size in bytes of every source, for the `progress` bar.
+///
+/// # Returns
+///
+/// * `Option<u64>` - The combined size, or `None` if any source is stdin, a named pipe, or can't be looked at.
+fn sources_size(config: &Config) -> Option<u64> {
+    let paths = if config.sources.is_empty() {
+        std::slice::from_ref(&config.source)
+    } else {
+        config.sources.as_slice()
+    };
+
+    paths.iter().try_fold(0, |total, path| {
+        if path.as_os_str() == STDIN_SOURCE || is_stream_file(path) {
+            return None;
+        }
+        Some(total + std::fs::metadata(path).ok()?.len())
+    })
+}
+
+/// Builds the CSV reader of a source from the `delimiter`, quoting, `flexible`, `trim` and `skip_rows` settings.
+///
+/// # Arguments
+///
+/// * `config` - The `Config` with the reading settings.
+/// * `input` - The opened source.
+///
+/// # Returns
+///
+/// * `Result<Reader<Box<dyn Read>>>` - The CSV reader, positioned after the skipped lines.
+///
+/// # Errors
+///
+/// This function will return an error if `quote` or `escape` is not a single byte character,
+/// or if the skipped lines cannot be read.
+fn csv_reader(config: &Config, input: Box<dyn Read>) -> Result<Reader<Box<dyn Read>>> {
+    // A JSON source has already been converted to plain CSV with a header row by `open_source`
+    if config.input_type == InputType::Json {
+        return Ok(csv::ReaderBuilder::new().trim(config.trim.into()).from_reader(input));
+    }
+
+    let mut builder = csv::ReaderBuilder::new();
+    builder
+        .has_headers(config.has_headers)
+        .trim(config.trim.into())
+        .delimiter(config.delimiter.as_byte())
+        .double_quote(config.double_quote)
+        .flexible(config.flexible);
+    if let Some(quote) = config.quote {
+        builder.quote(quoting_byte("quote", quote)?);
+    }
+    if let Some(escape) = config.escape {
+        builder.escape(Some(quoting_byte("escape", escape)?));
+    }
+    Ok(builder.from_reader(skip_lines(input, config.skip_rows)?))
+}
+
+/// Consumes the first `n` lines of the input, so the CSV reader starts after them.
+///
+/// Lines are skipped as raw text rather than as CSV records, so banner lines don't need the same
+/// number of fields as the real data. Skipping more lines than there are leaves an empty input.
+///
+/// # Arguments
+///
+/// * `input` - The input to skip lines of.
+/// * `n` - The number of lines to skip.
+///
+/// # Returns
+///
+/// * `Result<Box<dyn Read>>` - Returns the input positioned after the skipped lines, or an `Error` if reading fails.
+fn skip_lines(input: Box<dyn Read>, n: usize) -> Result<Box<dyn Read>> {
+    if n == 0 {
+        return Ok(input);
+    }
+
+    let mut reader = BufReader::new(input);
+    let mut line = Vec::new();
+    for skipped in 0..n {
+        line.clear();
+        if reader.read_until(b'\n', &mut line)? == 0 {
+            warn!("Only {skipped} of the {n} skip_rows lines were present in the source");
+            break;
+        }
+    }
+    Ok(Box::new(reader))
+}
+
+/// Names the columns of a source without headers `col0`, `col1`, ... (see `SYNTHETIC_HEADER_PREFIX`).
+///
+/// # Arguments
+///
+/// * `width` - The number of columns, taken from the first row of the source.
+///
+/// # Returns
+///
+/// * `StringRecord` - The synthetic header row.
+fn synthetic_headers(width: usize) -> StringRecord {
+    (0..width)
+        .map(|idx| format!("{SYNTHETIC_HEADER_PREFIX}{idx}"))
+        .collect()
+}
+
+/// Checks whether the first row of the source looks at odds with the `has_headers` setting.
+///
+/// With `has_headers` a row of nothing but numbers is more likely data than a header row, and without it
+/// a row of unique, non-numeric values is more likely a header row than data.
+///
+/// # Arguments
+///
+/// * `has_headers` - The configured `has_headers` setting.
+/// * `first_row` - The first row of the source.
+///
+/// # Returns
+///
+/// * `Option<String>` - Returns a warning message when the setting looks wrong, otherwise `None`.
+fn header_setting_warning(has_headers: bool, first_row: &StringRecord) -> Option<String> {
+    let cells = first_row.iter().map(str::trim).collect::<Vec<_>>();
+    if cells.is_empty() || cells.iter().any(|cell| cell.is_empty()) {
+        return None;
+    }
+    let is_numeric = |cell: &&str| cell.parse::<f64>().is_ok();
+
+    if has_headers && cells.iter().all(is_numeric) {
+        Some(format!(
+            "has_headers is true, but the header row looks like data (all numeric): {cells:?} - should has_headers be false?"
+        ))
+    } else if !has_headers
+        && !cells.iter().any(is_numeric)
+        && cells.iter().collect::<std::collections::HashSet<_>>().len() == cells.len()
+    {
+        Some(format!(
+            "has_headers is false, but the first row looks like headers (unique, non-numeric): {cells:?} - should has_headers be true?"
+        ))
+    } else {
+        None
+    }
+}
+
+/// Converts a configured `quote`/`escape` character to the single byte `csv::ReaderBuilder` expects.
+fn quoting_byte(setting: &str, c: char) -> Result<u8> {
+    u8::try_from(c).ok().filter(u8::is_ascii).ok_or_else(|| {
+        Error::ConfigParse(config::ConfigError::Message(format!(
+            "{setting} must be a single-byte (ASCII) character, got {c:?}"
+        )))
+    })
+}
+
+#[cfg(test)]
+mod csv_pipeline_tests {
+    use std::io::Write;
+
+    use tempfile::tempdir;
+
+    use super::*;
+    use crate::config::Trim;
+
+    fn config_for(source: &std::path::Path, extra: &str) -> Config {
+        let json = format!(
+            r#"{{
+                "source": {source:?},
+                "has_headers": true,
+                "fields": ["Id", "Name"],
+                "unique_fields": [],
+                "include_cols_with": {{}}
+                {extra}
+            }}"#
+        );
+        serde_json::from_str(&json).unwrap()
+    }
+
+    fn run(config: &Config) -> Result<RetainedData> {
+        let mut retained_data = RetainedData::new(config.fields.len());
+        let mut pipeline = CsvPipeline::new(config, &mut retained_data)?;
+        pipeline.process(&mut retained_data)?;
+        Ok(retained_data)
+    }
+
+    #[test]
+    fn test_sources_are_concatenated() {
+        let dir = tempdir().unwrap();
+        let first = dir.path().join("export_1.csv");
+        let second = dir.path().join("export_2.csv");
+        std::fs::write(&first, "Id,Name,Other\n1,Alice,x\n").unwrap();
+        std::fs::write(&second, "Id,Name,Other\n2,Bob,y\n3,Carol,z\n").unwrap();
+        let config = Config {
+            sources: vec![first.clone(), second],
+            ..config_for(&first, "")
+        };
+
+        let retained_data = run(&config).unwrap();
+
+        assert_eq!(retained_data.data, vec![vec!["1", "Alice"], vec!["2", "Bob"], vec!["3", "Carol"]]);
+    }
+
+    #[test]
+    fn test_sources_limit_spans_files() {
+        let dir = tempdir().unwrap();
+        let first = dir.path().join("export_1.csv");
+        let second = dir.path().join("export_2.csv");
+        std::fs::write(&first, "Id,Name\n1,Alice\n").unwrap();
+        std::fs::write(&second, "Id,Name\n2,Bob\n3,Carol\n").unwrap();
+        let config = Config {
+            sources: vec![first.clone(), second],
+            ..config_for(&first, r#", "limit": 2"#)
+        };
+
+        let retained_data = run(&config).unwrap();
+
+        assert_eq!(retained_data.data, vec![vec!["1", "Alice"], vec!["2", "Bob"]]);
+    }
+
+    #[test]
+    fn test_sources_size() {
+        let dir = tempdir().unwrap();
+        let first = dir.path().join("export_1.csv");
+        let second = dir.path().join("export_2.csv");
+        std::fs::write(&first, "Id,Name\n1,Alice\n").unwrap();
+        std::fs::write(&second, "Id,Name\n2,Bob\n3,Carol\n").unwrap();
+
+        assert_eq!(sources_size(&config_for(&first, "")), Some(16));
+        let config = Config {
+            sources: vec![first.clone(), second],
+            ..config_for(&first, "")
+        };
+        assert_eq!(sources_size(&config), Some(16 + 22));
+        assert_eq!(sources_size(&config_for(Path::new(STDIN_SOURCE), "")), None);
+
+        // With progress on, the rows read are the same
+        let retained_data = run(&config_for(&first, r#", "progress": true"#)).unwrap();
+        assert_eq!(retained_data.data, vec![vec!["1", "Alice"]]);
+    }
+
+    #[test]
+    fn test_sources_header_mismatch() {
+        let dir = tempdir().unwrap();
+        let first = dir.path().join("export_1.csv");
+        let second = dir.path().join("export_2.csv");
+        std::fs::write(&first, "Id,Name\n1,Alice\n").unwrap();
+        std::fs::write(&second, "Id,Surname\n2,Bob\n").unwrap();
+        let config = Config {
+            sources: vec![first.clone(), second],
+            ..config_for(&first, "")
+        };
+
+        let err = run(&config).unwrap_err();
+
+        assert!(matches!(&err, Error::CsvHeaders(msg) if msg.contains("export_2.csv")), "{err:?}");
+    }
+
+    #[test]
+    fn test_semicolon_delimiter() {
+        let dir = tempdir().unwrap();
+        let source = dir.path().join("input.csv");
+        std::fs::write(&source, "Id;Name;Other\n1;Alice;x\n2;Bob;y\n").unwrap();
+
+        let retained_data = run(&config_for(&source, r#", "delimiter": ";""#)).unwrap();
+
+        assert_eq!(retained_data.retained_headers, vec!["Id", "Name"]);
+        assert_eq!(retained_data.data, vec![vec!["1", "Alice"], vec!["2", "Bob"]]);
+    }
+
+    #[test]
+    fn test_tab_delimiter() {
+        let dir = tempdir().unwrap();
+        let source = dir.path().join("input.tsv");
+        std::fs::write(&source, "Id\tName\n1\tAlice\n").unwrap();
+
+        let retained_data = run(&config_for(&source, r#", "delimiter": "\\t""#)).unwrap();
+
+        assert_eq!(retained_data.data, vec![vec!["1", "Alice"]]);
+    }
+
+    #[test]
+    fn test_single_quote_round_trip() {
+        let dir = tempdir().unwrap();
+        let source = dir.path().join("input.csv");
+        std::fs::write(&source, "Id,Name\n1,'O''Brien, Pat'\n2,Bob\n").unwrap();
+
+        let retained_data = run(&config_for(&source, r#", "quote": "'""#)).unwrap();
+        assert_eq!(retained_data.data, vec![vec!["1", "O'Brien, Pat"], vec!["2", "Bob"]]);
+
+        // Written with the default `"` quoting, the value reads back unchanged
+        let output = dir.path().join("output.csv");
+        retained_data.to_csv(&output).unwrap();
+        assert_eq!(run(&config_for(&output, "")).unwrap().data, retained_data.data);
+    }
+
+    #[test]
+    fn test_escape_without_double_quote() {
+        let dir = tempdir().unwrap();
+        let source = dir.path().join("input.csv");
+        std::fs::write(&source, "Id,Name\n1,\"Say \\\"hi\\\"\"\n").unwrap();
+
+        let retained_data = run(&config_for(&source, r#", "escape": "\\", "double_quote": false"#)).unwrap();
+
+        assert_eq!(retained_data.data, vec![vec!["1", "Say \"hi\""]]);
+    }
+
+    #[test]
+    fn test_non_ascii_quote_is_rejected() {
+        let dir = tempdir().unwrap();
+        let source = dir.path().join("input.csv");
+        std::fs::write(&source, "Id,Name\n1,Alice\n").unwrap();
+
+        assert!(matches!(run(&config_for(&source, r#", "quote": "«""#)), Err(Error::ConfigParse(_))));
+    }
+
+    #[test]
+    fn test_ragged_row_aborts_by_default() {
+        let dir = tempdir().unwrap();
+        let source = dir.path().join("input.csv");
+        std::fs::write(&source, "Id,Name\n1,Alice\n2,Bob,extra\n3,Carol\n").unwrap();
+
+        assert!(matches!(run(&config_for(&source, "")), Err(Error::CsvParse(_))));
+    }
+
+    #[test]
+    fn test_ragged_rows_are_skipped() {
+        let dir = tempdir().unwrap();
+        let source = dir.path().join("input.csv");
+        std::fs::write(&source, "Id,Name\n1,Alice\n2,Bob,extra\n3\n4,Dave\n").unwrap();
+
+        for extra in [r#", "on_error": "skip""#, r#", "on_error": "warn", "parallel": true"#] {
+            let config = config_for(&source, extra);
+            let mut retained_data = RetainedData::new(config.fields.len());
+            let mut pipeline = CsvPipeline::new(&config, &mut retained_data).unwrap();
+            pipeline.process(&mut retained_data).unwrap();
+
+            assert_eq!(retained_data.data, vec![vec!["1", "Alice"], vec!["4", "Dave"]]);
+            let lines = pipeline.errors().iter().map(|(line, _)| *line).collect::<Vec<_>>();
+            assert_eq!(lines, vec![3, 4]);
+        }
+    }
+
+    #[test]
+    fn test_flexible_reads_short_rows_as_empty() {
+        let dir = tempdir().unwrap();
+        let source = dir.path().join("input.csv");
+        std::fs::write(&source, "Id,Name\n1,Alice\n2\n3,Carol,extra\n").unwrap();
+
+        let retained_data = run(&config_for(&source, r#", "flexible": true"#)).unwrap();
+
+        assert_eq!(retained_data.data, vec![vec!["1", "Alice"], vec!["2", ""], vec!["3", "Carol"]]);
+    }
+
+    #[test]
+    fn test_gzip_source() {
+        let dir = tempdir().unwrap();
+        let source = dir.path().join("input.csv.gz");
+        let mut input = String::from("Id,Name\n");
+        for id in 0..20_000 {
+            input.push_str(&format!("{id},Name {id}\n"));
+        }
+        let mut encoder = flate2::write::GzEncoder::new(File::create(&source).unwrap(), flate2::Compression::default());
+        encoder.write_all(input.as_bytes()).unwrap();
+        encoder.finish().unwrap();
+
+        let retained_data = run(&config_for(&source, "")).unwrap();
+
+        assert_eq!(retained_data.data.len(), 20_000);
+        assert_eq!(retained_data.data[19_999], vec!["19999", "Name 19999"]);
+    }
+
+    #[test]
+    fn test_utf16le_source_with_non_ascii_headers() {
+        let dir = tempdir().unwrap();
+        let source = dir.path().join("input.csv");
+        let mut bytes = vec![0xFF, 0xFE];
+        bytes.extend(
+            "Nº,Größe,Ärger\n1,Groß,x\n2,Klein,y\n"
+                .encode_utf16()
+                .flat_map(u16::to_le_bytes),
+        );
+        std::fs::write(&source, bytes).unwrap();
+
+        let config = Config {
+            fields: vec!["Nº".to_string(), "Größe".to_string()],
+            ..config_for(&source, "")
+        };
+        let detected = run(&config).unwrap();
+        let explicit = run(&Config {
+            encoding: Some("utf-16le".to_string()),
+            ..config
+        })
+        .unwrap();
+
+        for retained_data in [detected, explicit] {
+            assert_eq!(retained_data.all_headers, vec!["Nº", "Größe", "Ärger"]);
+            assert_eq!(retained_data.data, vec![vec!["1", "Groß"], vec!["2", "Klein"]]);
+        }
+    }
+
+    #[test]
+    fn test_utf8_bom_is_stripped_from_first_header() {
+        let dir = tempdir().unwrap();
+        let first = dir.path().join("export_1.csv");
+        let second = dir.path().join("export_2.csv");
+        std::fs::write(&first, "\u{feff}Id,Name\n1,Alice\n2,Bob\n").unwrap();
+        std::fs::write(&second, "\u{feff}Id,Name\n3,Carol\n").unwrap();
+
+        let config = Config {
+            include_cols_with: [("Id".to_string(), vec!["1".to_string(), "3".to_string()])].into(),
+            sources: vec![first.clone(), second],
+            ..config_for(&first, "")
+        };
+        let retained_data = run(&config).unwrap();
+
+        assert_eq!(retained_data.all_headers, vec!["Id", "Name"]);
+        assert_eq!(retained_data.retained_headers, vec!["Id", "Name"]);
+        assert_eq!(retained_data.data, vec![vec!["1", "Alice"], vec!["3", "Carol"]]);
+    }
+
+    #[test]
+    fn test_trim_fields_lets_padded_values_match() {
+        let dir = tempdir().unwrap();
+        let source = dir.path().join("input.csv");
+        std::fs::write(&source, "Id,Name,Type\n1, Alice , Rollover \n2,Bob,New\n").unwrap();
+        let config = |trim| Config {
+            trim,
+            include_cols_with: [("Type".to_string(), vec!["Rollover".to_string()])].into(),
+            ..config_for(&source, "")
+        };
+
+        assert!(run(&config(Trim::None)).unwrap().data.is_empty());
+        assert_eq!(run(&config(Trim::Fields)).unwrap().data, vec![vec!["1", "Alice"]]);
+    }
+
+    #[test]
+    fn test_padded_header_names_match_with_any_trim() {
+        let dir = tempdir().unwrap();
+        let source = dir.path().join("input.csv");
+        std::fs::write(&source, " Id , Name \n1, Alice \n").unwrap();
+        let config = |trim| Config {
+            trim,
+            ..config_for(&source, "")
+        };
+
+        // Headers are always normalized, so `trim` only decides whether the values are trimmed
+        assert_eq!(run(&config(Trim::None)).unwrap().data, vec![vec!["1", " Alice "]]);
+        assert_eq!(run(&config(Trim::Headers)).unwrap().data, vec![vec!["1", " Alice "]]);
+        assert_eq!(run(&config(Trim::All)).unwrap().data, vec![vec!["1", "Alice"]]);
+    }
+
+    #[test]
+    fn test_from_reader() {
+        let config = config_for(std::path::Path::new(STDIN_SOURCE), "");
+        assert!(config.reads_from_stdin());
+
+        let mut retained_data = RetainedData::new(config.fields.len());
+        let input = std::io::Cursor::new("Name,Id\nAlice,1\nBob,2\n");
+        let mut pipeline = CsvPipeline::from_reader(&config, &mut retained_data, Box::new(input)).unwrap();
+        pipeline.process(&mut retained_data).unwrap();
+
+        // Columns follow the order of `fields`, not the source
+        assert_eq!(retained_data.retained_headers, vec!["Id", "Name"]);
+        assert_eq!(retained_data.data, vec![vec!["1", "Alice"], vec!["2", "Bob"]]);
+    }
+
+    #[test]
+    fn test_limit_stops_reading_early() {
+        let config = config_for(std::path::Path::new(STDIN_SOURCE), r#", "limit": 2"#);
+
+        let mut retained_data = RetainedData::new(config.fields.len());
+        // The third data row is malformed - it must never be read
+        let input = std::io::Cursor::new(
+            "Id,Name
+1,Alice
+1,Alice
+\"broken,row
+3,Carl
+",
+        );
+        let mut pipeline = CsvPipeline::from_reader(&config, &mut retained_data, Box::new(input)).unwrap();
+        pipeline.process(&mut retained_data).unwrap();
+
+        // The limit counts rows before deduplication, so the duplicate is still one of the two
+        assert_eq!(retained_data.data, vec![vec!["1", "Alice"], vec!["1", "Alice"]]);
+        // Only the header and the two kept rows were read
+        assert_eq!(pipeline.reader.position().record(), 3);
+    }
+
+    #[test]
+    fn test_limit_counts_only_kept_rows() {
+        let config = config_for(
+            std::path::Path::new(STDIN_SOURCE),
+            r#", "limit": 2, "filters": [{ "column": "Name", "mode": "prefix", "values": ["A"] }]"#,
+        );
+
+        let mut retained_data = RetainedData::new(config.fields.len());
+        let input = std::io::Cursor::new(
+            "Id,Name
+1,Bob
+2,Alice
+3,Carl
+4,Anna
+5,Alex
+",
+        );
+        let mut pipeline = CsvPipeline::from_reader(&config, &mut retained_data, Box::new(input)).unwrap();
+        pipeline.process(&mut retained_data).unwrap();
+
+        assert_eq!(retained_data.data, vec![vec!["2", "Alice"], vec!["4", "Anna"]]);
+    }
+
+    #[test]
+    fn test_head_stops_reading_early() {
+        let config = config_for(std::path::Path::new(STDIN_SOURCE), r#", "limit": 5, "head": 1"#);
+
+        let mut retained_data = RetainedData::new(config.fields.len());
+        // The second data row is malformed - the smaller of `head` and `limit` means it's never read
+        let input = std::io::Cursor::new(
+            "Id,Name
+1,Alice
+\"broken,row
+3,Carl
+",
+        );
+        let mut pipeline = CsvPipeline::from_reader(&config, &mut retained_data, Box::new(input)).unwrap();
+        pipeline.process(&mut retained_data).unwrap();
+
+        assert_eq!(retained_data.data, vec![vec!["1", "Alice"]]);
+    }
+
+    #[test]
+    fn test_row_range_stops_reading_early() {
+        let read = |extra: &str| {
+            let config = config_for(std::path::Path::new(STDIN_SOURCE), extra);
+            let mut retained_data = RetainedData::new(config.fields.len());
+            // The record at index 4 is malformed - it must never be read
+            let input = std::io::Cursor::new("Id,Name\n0,Alice\n1,Bob\n2,Carl\n3,Dana\n\"broken,row\n5,Eve\n");
+            let mut pipeline = CsvPipeline::from_reader(&config, &mut retained_data, Box::new(input))?;
+            pipeline.process(&mut retained_data)?;
+            Ok::<_, Error>(retained_data.data)
+        };
+
+        assert_eq!(
+            read(r#", "row_range": [1, 4]"#).unwrap(),
+            vec![vec!["1", "Bob"], vec!["2", "Carl"], vec!["3", "Dana"]]
+        );
+        // The limit counts the kept rows within the range
+        assert_eq!(read(r#", "row_range": [1, 4], "limit": 1"#).unwrap(), vec![vec!["1", "Bob"]]);
+        assert!(read(r#", "row_range": [2, 2]"#).unwrap().is_empty());
+        assert!(matches!(read(r#", "row_range": [3, 2]"#), Err(Error::InvalidConfig(_))));
+    }
+
+    #[test]
+    fn test_parallel_matches_sequential() {
+        let mut input = String::from("Id,Name\n");
+        for id in 0..(PARALLEL_BATCH_ROWS * 2 + 17) {
+            let name = ["Alice", "Bob", "Anna"][id % 3];
+            input.push_str(&format!("{id},{name}\n"));
+        }
+        let read = |extra: &str| {
+            let config = config_for(std::path::Path::new(STDIN_SOURCE), extra);
+            let mut retained_data = RetainedData::new(config.fields.len());
+            let input = std::io::Cursor::new(input.clone());
+            let mut pipeline = CsvPipeline::from_reader(&config, &mut retained_data, Box::new(input)).unwrap();
+            pipeline.process(&mut retained_data).unwrap();
+            retained_data.data
+        };
+
+        let filters = r#", "filters": [{ "column": "Name", "mode": "prefix", "values": ["A"] }]"#;
+        let sequential = read(filters);
+        let parallel = read(&format!(r#"{filters}, "parallel": true"#));
+        assert_eq!(sequential.len(), PARALLEL_BATCH_ROWS * 4 / 3 + 12);
+        assert_eq!(parallel, sequential);
+
+        // The limit falls in the middle of the second batch, and still keeps exactly the first rows
+        let limit = PARALLEL_BATCH_ROWS + 5;
+        let limited = read(&format!(r#"{filters}, "parallel": true, "limit": {limit}"#));
+        assert_eq!(limited, sequential[..limit]);
+
+        // The range spans a batch boundary, with a batch cut short at its end
+        let (start, end) = (PARALLEL_BATCH_ROWS - 10, PARALLEL_BATCH_ROWS * 2 - 3);
+        let in_range = sequential
+            .iter()
+            .filter(|row| (start..end).contains(&row[0].parse().unwrap()))
+            .cloned()
+            .collect::<Vec<_>>();
+        assert_eq!(read(&format!(r#"{filters}, "row_range": [{start}, {end}]"#)), in_range);
+        assert_eq!(read(&format!(r#"{filters}, "parallel": true, "row_range": [{start}, {end}]"#)), in_range);
+    }
+
+    #[test]
+    fn test_dedup_bloom_drops_duplicates_while_reading() {
+        let json = r#"{"source": "-", "has_headers": true, "fields": ["Id", "Name"], "unique_fields": ["Id"], "include_cols_with": {},
+            "dedup_bloom": { "capacity": 100, "fp_rate": 0.001 }}"#;
+        let config: Config = serde_json::from_str(json).unwrap();
+
+        let mut retained_data = RetainedData::new(config.fields.len());
+        let input = std::io::Cursor::new("Id,Name\n1,Alice\n2,Bob\n1,Alice again\n3,Carl\n2,Bob again\n");
+        let mut pipeline = CsvPipeline::from_reader(&config, &mut retained_data, Box::new(input)).unwrap();
+        pipeline.process(&mut retained_data).unwrap();
+
+        // The first row of each key is kept, before the in-memory dedup has even run
+        assert_eq!(retained_data.data, vec![vec!["1", "Alice"], vec!["2", "Bob"], vec!["3", "Carl"]]);
+    }
+
+    #[test]
+    fn test_stats_counts() {
+        for parallel in [false, true] {
+            let json = format!(
+                r#"{{"source": "-", "has_headers": true, "fields": ["Id", "Name"], "unique_fields": ["Id"],
+                "include_cols_with": {{}}, "exclude_cols_with": {{ "Name": ["Bob"] }}, "on_error": "skip",
+                "dedup_bloom": {{ "capacity": 100, "fp_rate": 0.001 }}, "parallel": {parallel}}}"#
+            );
+            let config: Config = serde_json::from_str(&json).unwrap();
+
+            let mut retained_data = RetainedData::new(config.fields.len());
+            let input = std::io::Cursor::new("Id,Name\n1,Alice\n2,Bob\n3,Carl,extra\n1,Alice again\n4,Dana\n");
+            let mut pipeline = CsvPipeline::from_reader(&config, &mut retained_data, Box::new(input)).unwrap();
+            pipeline.process(&mut retained_data).unwrap();
+            pipeline.deduplicate(&mut retained_data).unwrap();
+
+            assert_eq!(
+                pipeline.stats(retained_data.data.len()),
+                Stats {
+                    rows_read: 5,
+                    rows_malformed: 1,
+                    rows_filtered_out: 1,
+                    rows_deduplicated: 1,
+                    rows_kept: 2,
+                },
+                "parallel: {parallel}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_count_matching_rows() {
+        let count = |extra: &str| {
+            let json = format!(
+                r#"{{"source": "-", "has_headers": true, "unique_fields": [], "include_cols_with": {{}},
+                "exclude_cols_with": {{ "Name": ["Bob"] }}, "on_error": "skip" {extra}}}"#
+            );
+            let config: Config = serde_json::from_str(&json).unwrap();
+
+            let mut retained_data = RetainedData::new(config.fields.len());
+            let input = std::io::Cursor::new("Id,Name\n1,Alice\n2,Bob\n3,Carl,extra\n1,Alice\n4,Dana\n");
+            let mut pipeline = CsvPipeline::from_reader(&config, &mut retained_data, Box::new(input)).unwrap();
+            let count = pipeline.count().unwrap();
+            assert!(retained_data.data.is_empty());
+            count
+        };
+
+        // No fields are needed, duplicates all count and the malformed record is skipped
+        assert_eq!(count(""), 3);
+        assert_eq!(count(r#", "limit": 2"#), 2);
+        assert_eq!(count(r#", "row_range": [1, 4]"#), 1);
+        assert_eq!(count(r#", "fields": ["Name"], "post_filters": [{ "column": "Name", "values": ["Alice"] }]"#), 2);
+    }
+
+    #[test]
+    fn test_count_stops_reading_early() {
+        let config = config_for(std::path::Path::new(STDIN_SOURCE), r#", "limit": 2"#);
+
+        let mut retained_data = RetainedData::new(config.fields.len());
+        // The third data row is malformed - it must never be read
+        let input = std::io::Cursor::new("Id,Name\n1,Alice\n2,Bob\n\"broken,row\n");
+        let mut pipeline = CsvPipeline::from_reader(&config, &mut retained_data, Box::new(input)).unwrap();
+
+        assert_eq!(pipeline.count().unwrap(), 2);
+        // Only the header and the two counted rows were read
+        assert_eq!(pipeline.reader.position().record(), 3);
+    }
+
+    #[test]
+    fn test_skip_rows_before_headers() {
+        let config = config_for(std::path::Path::new(STDIN_SOURCE), r#", "skip_rows": 2"#);
+
+        let mut retained_data = RetainedData::new(config.fields.len());
+        let input =
+            std::io::Cursor::new("Exported by: someone\nReport date: 2025-01-01,extra,fields\nId,Name\n1,Alice\n");
+        let mut pipeline = CsvPipeline::from_reader(&config, &mut retained_data, Box::new(input)).unwrap();
+        pipeline.process(&mut retained_data).unwrap();
+
+        assert_eq!(retained_data.retained_headers, vec!["Id", "Name"]);
+        assert_eq!(retained_data.data, vec![vec!["1", "Alice"]]);
+    }
+
+    #[test]
+    fn test_skip_rows_without_headers() {
+        let json = r#"{"source": "-", "has_headers": false, "skip_rows": 1, "fields": ["col0"], "unique_fields": [], "include_cols_with": {}}"#;
+        let config: Config = serde_json::from_str(json).unwrap();
+
+        let mut retained_data = RetainedData::new(config.fields.len());
+        let input = std::io::Cursor::new("Banner line\n1,Alice\n2,Bob\n");
+        let mut pipeline = CsvPipeline::from_reader(&config, &mut retained_data, Box::new(input)).unwrap();
+        pipeline.process(&mut retained_data).unwrap();
+
+        // The banner is neither a header nor a data row, and the first real row is still data
+        assert_eq!(retained_data.data, vec![vec!["1"], vec!["2"]]);
+    }
+
+    #[test]
+    fn test_headerless_synthetic_column_names() {
+        let dir = tempdir().unwrap();
+        let source = dir.path().join("input.csv");
+        std::fs::write(&source, "1,Alice,x\n2,Bob,y\n3,Carol,x\n").unwrap();
+
+        let config = Config {
+            has_headers: false,
+            fields: vec!["col0".to_string(), "col2".to_string()],
+            filters: serde_json::from_str(r#"[{ "column": "col2", "mode": "exact", "values": ["x"] }]"#).unwrap(),
+            ..config_for(&source, "")
+        };
+        let retained_data = run(&config).unwrap();
+
+        assert_eq!(retained_data.all_headers, vec!["col0", "col1", "col2"]);
+        assert_eq!(retained_data.retained_headers, vec!["col0", "col2"]);
+        assert_eq!(retained_data.data, vec![vec!["1", "x"], vec!["3", "x"]]);
+    }
+
+    #[test]
+    fn test_json_source() {
+        let dir = tempdir().unwrap();
+        let source = dir.path().join("input.json");
+        std::fs::write(
+            &source,
+            r#"[
+                {"Id": 1, "Name": "Alice", "Status": "Open"},
+                {"Id": 2, "Status": "Closed"},
+                {"Name": "Bob", "Id": 3, "Status": "Open"}
+            ]"#,
+        )
+        .unwrap();
+
+        let config = Config {
+            input_type: InputType::Json,
+            // Ignored for a JSON source, its keys are always the headers
+            has_headers: false,
+            fields: vec!["Id".to_string(), "Name".to_string()],
+            filters: serde_json::from_str(r#"[{ "column": "Status", "mode": "exact", "values": ["Open"] }]"#).unwrap(),
+            ..config_for(&source, "")
+        };
+        let retained_data = run(&config).unwrap();
+
+        assert_eq!(retained_data.all_headers, vec!["Id", "Name", "Status"]);
+        assert_eq!(retained_data.data, vec![vec!["1", "Alice"], vec!["3", "Bob"]]);
+    }
+
+    #[test]
+    fn test_header_warning_when_headers_look_like_data() {
+        let first_row = StringRecord::from(vec!["1", "2.5", "3"]);
+
+        assert!(header_setting_warning(true, &first_row).is_some());
+        assert!(header_setting_warning(false, &first_row).is_none());
+    }
+
+    #[test]
+    fn test_header_warning_when_first_row_looks_like_headers() {
+        let first_row = StringRecord::from(vec!["Id", "Name", "Status"]);
+
+        assert!(header_setting_warning(false, &first_row).is_some());
+        assert!(header_setting_warning(true, &first_row).is_none());
+    }
+
+    #[test]
+    fn test_no_header_warning_for_ambiguous_rows() {
+        // Mixed, or repeated values, could be either
+        assert!(header_setting_warning(true, &StringRecord::from(vec!["1", "Alice"])).is_none());
+        assert!(header_setting_warning(false, &StringRecord::from(vec!["1", "Alice"])).is_none());
+        assert!(header_setting_warning(false, &StringRecord::from(vec!["Yes", "Yes"])).is_none());
+    }
+
+    #[test]
+    fn test_multi_byte_delimiter_is_rejected() {
+        let json = r#"{"source": "data.csv", "delimiter": ";;", "fields": [], "unique_fields": [], "include_cols_with": {"a": []}}"#;
+        assert!(matches!(Config::try_from(json), Err(Error::ConfigParse(_))));
     }
 }
@@ -21,7 +21,7 @@ pub enum Error {
     ///
     /// # Example
     ///
-    /// ```rust
+    /// ```ignore
     /// use std::fs::File;
     /// use std::io::Error as IoError;
     /// use crate::Error;
@@ -39,7 +39,7 @@ pub enum Error {
     ///
     /// # Example
     ///
-    /// ```rust
+    /// ```ignore
     /// use config::Config;
     /// use crate::Error;
     ///
@@ -56,7 +56,7 @@ pub enum Error {
     ///
     /// # Example
     ///
-    /// ```rust
+    /// ```ignore
     /// use csv::Reader;
     /// use crate::Error;
     ///
@@ -67,13 +67,29 @@ pub enum Error {
     #[error("Failed to parse CSV file: {0}")]
     CsvParse(#[from] csv::Error),
 
+    /// Represents an error that occurred while writing JSON output.
+    ///
+    /// This variant wraps a `serde_json::Error` and provides a detailed error message.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// use crate::Error;
+    ///
+    /// fn write_json(rows: &[Vec<String>]) -> Result<String, Error> {
+    ///     serde_json::to_string(rows).map_err(Error::from)
+    /// }
+    /// ```
+    #[error("Failed to write JSON: {0}")]
+    Json(#[from] serde_json::Error),
+
     /// Represents an error related to parsing CSV headers.
     ///
     /// This variant provides a detailed error message indicating the issue with the CSV headers.
     ///
     /// # Example
     ///
-    /// ```rust
+    /// ```ignore
     /// use crate::Error;
     ///
     /// fn check_headers(headers: &str) -> Result<(), Error> {
@@ -92,7 +108,7 @@ pub enum Error {
     ///
     /// # Example
     ///
-    /// ```rust
+    /// ```ignore
     /// use crate::Error;
     /// use std::fs::File;
     ///
@@ -103,26 +119,62 @@ pub enum Error {
     #[error("Failed to read CSV file from source provided: {0}")]
     CsvRead(String),
 
+    #[error("Failed to parse numeric value: {0}")]
+    NumericParse(String),
+
+    #[error("Output row count does not match the expectation: expected {expected}, got {actual}")]
+    RowCountMismatch { expected: String, actual: usize },
+
     #[error("Failed to capture or parsee regex: {0}")]
     RegexCapture(String),
 
     #[error("Failed to parse config 'source'")]
     ConfigSource,
 
+    #[error("Invalid config: {0}")]
+    InvalidConfig(String),
+
     #[error(
-        "Ambiguous file match - Two files have the exact same name, modified timestamp and size - Congratulations, you've found a bug in Windows!"
+        "Ambiguous file match - {count} files have the exact same name, modified timestamp and size: {0:?}",
+        count = .0.len()
     )]
-    AmbiguousFileMatch,
+    AmbiguousFileMatch(Vec<PathBuf>),
+
+    #[error("The output path is the source file itself, writing it would overwrite the data being read: {0}")]
+    OutputIsSource(PathBuf),
 
     #[error("No matching files found")]
     NoMatchingFiles,
 
+    #[error("No matching files found within the first {0} directory entries (source_scan_limit)")]
+    ScanLimitExceeded(usize),
+
     #[error("Failed to find a parent path for the provided path: {0}. Please ensure the path is valid.")]
     NoParentPath(PathBuf),
 
     #[error("Failed to parse path: {0}")]
     ParsingPath(String),
 
+    #[error("Invalid dedup_bloom settings: {0}")]
+    DedupBloom(String),
+
+    #[error("Invalid date format: {0}")]
+    DateFormat(String),
+
+    #[error("Unknown encoding: {0:?}, expected a label such as utf-16le, latin1 or windows-1252")]
+    Encoding(String),
+
+    #[error("Failed to fetch remote config: {0}")]
+    RemoteConfig(String),
+
+    #[error("{failed} of {total} configs failed")]
+    BatchFailed { failed: usize, total: usize },
+
+    #[error(
+        "No config file found at {0}, and --no-config doesn't create one - pass the source and fields on the command line"
+    )]
+    NoConfigFile(PathBuf),
+
     #[error("Failed to update the application: {0}")]
     SelfUpdateFailed(#[from] self_update::errors::Error),
 }
@@ -0,0 +1,309 @@
+//! Parses CSV files based on a configuration, filtering, deduplicating and reshaping their rows.
+//!
+//! The `csv_parser_rs` binary is a thin wrapper over this library: `run` is what it calls for a `Cli`.
+//! To use the processing from another program without any CLI or environment coupling, build a `Config`
+//! and hand it to `process_config`, which returns the resulting `RetainedData` instead of writing it out.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use csv_parser_rs::{ConfigBuilder, process_config};
+//!
+//! # fn main() -> csv_parser_rs::Result<()> {
+//! let config = ConfigBuilder::new()
+//!     .source("data.csv")
+//!     .field("Id")
+//!     .field("Name")
+//!     .unique_field("Id")
+//!     .build()?;
+//! let retained_data = process_config(&config)?;
+//! println!("{:?}", retained_data.retained_headers);
+//! # Ok(())
+//! # }
+//! ```
+#![allow(clippy::needless_doctest_main)]
+
+pub mod batch;
+pub mod cli;
+pub mod config;
+pub mod csv_pipeline;
+pub mod error;
+pub mod macros;
+pub mod prelude;
+pub mod processing;
+pub mod progress;
+pub mod retained;
+pub mod state;
+
+pub use crate::cli::Cli;
+pub use crate::config::{Config, ConfigBuilder};
+pub use crate::csv_pipeline::CsvPipeline;
+pub use crate::error::Error;
+pub use crate::prelude::Result;
+pub(crate) use crate::prelude::*;
+pub use crate::retained::RetainedData;
+pub use crate::state::State;
+
+/// Runs a `Config` through the whole pipeline, and returns the resulting rows rather than writing them out.
+///
+/// This is the library counterpart of `run`: the source is read and filtered, then deduplicated, aggregated, reduced to a
+/// delta, sampled, sorted and given a running total, each only if configured, and the row count is checked.
+/// No `Cli` or environment is involved, so `source` must be a concrete path (no `{regex}` or glob),
+/// and `output_type` / `output_path` are ignored as nothing is written.
+///
+/// # Arguments
+///
+/// * `config` - The configuration to process, e.g. built with `ConfigBuilder`.
+///
+/// # Returns
+///
+/// * `Result<RetainedData>` - Returns the processed headers and rows, or an `Error` on failure.
+///
+/// # Errors
+///
+/// This function can return errors if the source cannot be read, a configured column is missing,
+/// or the row count doesn't match `expect_rows` / `expect_rows_range`.
+///
+/// # Example
+///
+/// ```no_run
+/// # use csv_parser_rs::{ConfigBuilder, process_config};
+/// # fn main() -> csv_parser_rs::Result<()> {
+/// # let config = ConfigBuilder::new().source("data.csv").field("Id").build()?;
+/// let retained_data = process_config(&config)?;
+/// for row in &retained_data.data {
+///     println!("{}", row.join(","));
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub fn process_config(config: &Config) -> Result<RetainedData> {
+    let mut state = State::from_config(config.clone())?;
+
+    state.process()?;
+    report_parse_errors(&state.csv_pipeline);
+    if state.config.deduplicates() {
+        state.deduplicate()?;
+    }
+    state.aggregate()?;
+    state.delta()?;
+    state.sample();
+    state.sort()?;
+    state.running_total()?;
+    state.check_row_count()?;
+
+    Ok(state.retained_data)
+}
+
+/// Runs a single config through the whole pipeline, from reading the source to writing the output.
+///
+/// This covers steps 3-7 of the binary's `main`, and is what every config of a `--config-dir` batch is run with.
+///
+/// # Arguments
+///
+/// * `cli` - The CLI arguments to build the `State` from.
+///
+/// # Returns
+///
+/// * `Result<()>` - Returns `Ok(())` on success, or an `Error` on failure.
+///
+/// # Errors
+///
+/// This function can return errors if the `State` cannot be created, or processing or outputting the data fails.
+///
+/// # Example
+///
+/// ```no_run
+/// # use csv_parser_rs::{Cli, run};
+/// # fn main() -> csv_parser_rs::Result<()> {
+/// run(Cli::new())?;
+/// # Ok(())
+/// # }
+/// ```
+pub fn run(cli: Cli) -> Result<()> {
+    let dry_run = cli.dry_run;
+    let write_run_stats = cli.stats;
+    let count_only = cli.count;
+    let mut state = match State::new(cli) {
+        Ok(state) => state,
+        Err(e) => {
+            error!("Error setting up the run: {e}");
+            return Err(e);
+        }
+    };
+    debug!("MAIN:: Config: {:?}", &state.config);
+
+    if dry_run {
+        info!("Dry run, nothing is read past the headers or written:");
+        for line in state.dry_run_report() {
+            info!("  {line}");
+        }
+        return Ok(());
+    }
+
+    if count_only {
+        let result = state.count();
+        report_parse_errors(&state.csv_pipeline);
+        // The count is the only thing written to stdout, so it can be piped or captured as-is
+        println!("{}", result?);
+        return Ok(());
+    }
+
+    if state.config.sources.len() > 1 {
+        info!("Processing CSV data for: {:?}", state.config.sources);
+    } else {
+        info!("Processing CSV data for: {:?}", state.config.source);
+    }
+
+    if state.config.external {
+        let result = state.process_external();
+        report_parse_errors(&state.csv_pipeline);
+        return match result {
+            Ok(()) => {
+                info!("Output successful");
+                report_stats(&state, write_run_stats)
+            }
+            Err(e) => {
+                error!("Error processing externally: {e}");
+                Err(e)
+            }
+        };
+    }
+
+    if state.streaming {
+        debug!("Nothing needs every row at once, streaming rows straight to the output");
+        let result = state.process_streaming();
+        report_parse_errors(&state.csv_pipeline);
+        return match result {
+            Ok(()) => {
+                info!("Output successful");
+                report_stats(&state, write_run_stats)
+            }
+            Err(e) => {
+                error!("Error processing: {e}");
+                Err(e)
+            }
+        };
+    }
+
+    if let Err(e) = state.process() {
+        error!("Error processing: {e}");
+        return Err(e);
+    }
+    report_parse_errors(&state.csv_pipeline);
+
+    if state.config.deduplicates() {
+        if let Some(top_n) = state.config.dedup_preview
+            && let Err(e) = state.dedup_preview(top_n)
+        {
+            error!("Error previewing deduplication: {e}");
+            return Err(e);
+        }
+        if let Err(e) = state.deduplicate() {
+            error!("Error deduplicating: {e}");
+            return Err(e);
+        }
+    } else {
+        warn!("No unique fields provided and dedup_all is off, skipping deduplication");
+    }
+
+    state.aggregate()?;
+    state.delta()?;
+    state.sample();
+    state.sort()?;
+    state.running_total()?;
+
+    if let Err(e) = state.check_row_count() {
+        error!("{e}");
+        return Err(e);
+    }
+
+    match state
+        .output()
+        .and_then(|()| state.write_stats())
+        .and_then(|()| state.write_validation_report())
+    {
+        Ok(()) => {
+            info!("Output successful");
+            report_stats(&state, write_run_stats)
+        }
+        Err(e) => {
+            error!("Error outputting: {e}");
+            Err(e)
+        }
+    }
+}
+
+/// Logs the row counts of a finished run at info level, and writes them next to the output for `--stats`.
+fn report_stats(state: &State, write_run_stats: bool) -> Result<()> {
+    info!("Summary: {}", state.stats());
+    if write_run_stats {
+        state.write_run_stats()?;
+    }
+    Ok(())
+}
+
+/// Reports the records that were skipped because they failed to parse (see `on_error`):
+/// a count as a warning, and every line number and error message at trace verbosity.
+fn report_parse_errors(csv_pipeline: &CsvPipeline) {
+    let errors = csv_pipeline.errors();
+    if errors.is_empty() {
+        return;
+    }
+
+    warn!("Skipped {} record(s) of the source that failed to parse", errors.len());
+    for (line, message) in errors {
+        trace!("Line {line}: {message}");
+    }
+}
+
+#[cfg(test)]
+mod lib_tests {
+    use tempfile::tempdir;
+
+    use super::*;
+
+    #[test]
+    fn test_process_config() {
+        let dir = tempdir().unwrap();
+        let source = dir.path().join("input.csv");
+        std::fs::write(&source, "Id,Name,Amount\n2,Bob,5\n1,Alice,3\n2,Bob,5\n3,Carl,x\n").unwrap();
+
+        let config = Config {
+            source,
+            has_headers: true,
+            fields: vec!["Id".to_string(), "Name".to_string()],
+            unique_fields: vec!["Id".to_string()],
+            exclude_cols_with: [("Name".to_string(), vec!["Carl".to_string()])].into(),
+            ..Default::default()
+        };
+        let retained_data = process_config(&config).unwrap();
+
+        assert_eq!(retained_data.retained_headers, vec!["Id", "Name"]);
+        assert_eq!(retained_data.data, vec![vec!["2", "Bob"], vec!["1", "Alice"]]);
+        assert!(!dir.path().join("output.csv").exists());
+    }
+
+    #[test]
+    fn test_process_config_unretained_unique_field_is_an_error() {
+        let dir = tempdir().unwrap();
+        let source = dir.path().join("input.csv");
+        std::fs::write(
+            &source,
+            "Id,Name,Amount
+1,Alice,3
+",
+        )
+        .unwrap();
+
+        let config = Config {
+            source,
+            has_headers: true,
+            fields: vec!["Id".to_string(), "Name".to_string()],
+            unique_fields: vec!["Amount".to_string()],
+            ..Default::default()
+        };
+
+        assert!(matches!(process_config(&config), Err(Error::CsvHeaders(_))));
+    }
+}
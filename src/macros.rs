@@ -10,8 +10,9 @@
 /// # Examples
 ///
 /// ```no_run
+/// # use csv_parser_rs::crate_authors;
 /// let m = crate_authors!();
-/// assert_eq!(m, "author1 lastname <author1@example.com>:author2 lastname <author2@example.com>"
+/// assert_eq!(m, "author1 lastname <author1@example.com>:author2 lastname <author2@example.com>");
 /// ```
 #[macro_export]
 macro_rules! crate_authors {
@@ -46,7 +47,8 @@ macro_rules! crate_authors {
 /// # Examples
 ///
 /// ```no_run
-/// let m =  crate_name!();
+/// # use csv_parser_rs::crate_name;
+/// let m = crate_name!();
 /// assert_eq!(m, "csv_parser_rs");
 /// ```
 #[macro_export]
@@ -1,29 +1,31 @@
 #![allow(clippy::needless_doctest_main)]
 
-use log::{error, info, warn};
-use state::State;
-
-pub(crate) mod cli;
-pub(crate) mod config;
-pub(crate) mod csv_pipeline;
-pub(crate) mod error;
-pub(crate) mod macros;
-pub(crate) mod prelude;
-pub(crate) mod processing;
-pub(crate) mod retained;
-pub(crate) mod state;
-
-use crate::cli::{Cli, VerbosityLevel};
-pub(crate) use crate::prelude::*;
+use csv_parser_rs::cli::{Cli, VerbosityLevel};
+use csv_parser_rs::prelude::{Error, Result, update};
+use csv_parser_rs::{batch, config, progress, run};
+use log::{error, info};
 
 /// The main entry point of the application.
 ///
 /// This function performs the following steps:
 /// 1. Initializes the `Cli` instance to parse command-line arguments.
-/// 2. Creates a new `State` instance based on the `Cli` input.
-/// 3. Processes the CSV data using the `State` instance.
-/// 4. Deduplicates the retained data if unique fields are specified in the configuration.
-/// 5. Outputs the retained data based on the configured output type.
+///    With `--update`, the tool is updated to its latest release instead, and nothing else runs.
+/// 2. Creates a new `State` instance based on the `Cli` input - unless `--init-from-source` was given,
+///    in which case a config is generated from the source's headers instead and nothing else runs.
+///    With `--config-dir`, steps 2-7 are run for every config in the directory instead (see `run`), `--jobs` at a time.
+/// 3. Processes the CSV data using the `State` instance. When nothing needs every row at once (no deduplication,
+///    sorting, etc.), the rows are written to the output as they are read instead, skipping steps 4-7.
+/// 4. Deduplicates the retained data if unique fields are specified in the configuration,
+///    optionally previewing the most duplicated keys first.
+/// 5. Reduces the data to a delta against `delta_against`, samples `sample` rows, sorts it by `sort_by`, then appends the
+///    `running_total` column - each only if configured.
+/// 6. Checks the row count against `expect_rows` / `expect_rows_range`, if configured.
+/// 7. Outputs the retained data based on the configured output type, along with the `with_stats` file
+///    and the `validate_report` if configured. A summary of the row counts is then logged (and written with `--stats`).
+///
+/// When `external` is set, steps 3-7 are instead performed on disk by `State::process_external`.
+/// With `--dry-run` the run stops after step 2 with a report of what it would do, and with `--count` step 3 only counts
+/// the rows that pass the filters, printing the number instead of steps 4-7.
 ///
 /// # Returns
 ///
@@ -38,7 +40,7 @@ pub(crate) use crate::prelude::*;
 ///
 /// # Example
 ///
-/// ```rust
+/// ```ignore
 /// fn main() {
 ///     if let Err(e) = main() {
 ///         eprintln!("Application error: {e}");
@@ -47,50 +49,63 @@ pub(crate) use crate::prelude::*;
 /// ```
 pub fn main() -> Result<()> {
     let cli = Cli::new();
-    let _ = stderrlog::new()
+    let verbosity = cli.verbosity_level.unwrap_or(VerbosityLevel::Info);
+    let mut logger = stderrlog::new();
+    logger
         .color(stderrlog::ColorChoice::Always)
-        .verbosity(cli.verbosity_level.unwrap_or(VerbosityLevel::Info))
+        .verbosity(verbosity)
         .show_level(true)
-        .show_module_names(true)
-        .init();
+        .show_module_names(true);
+    let _ = progress::init_logger(logger, verbosity);
 
-    match update() {
-        Ok(vers) => {
-            // Only produce output when we actually did something
-            if vers != self_update::cargo_crate_version!() {
-                println!("vers val: {:#?}", vers);
-                println!("cargo pkg vers: {:#?}", self_update::cargo_crate_version!());
-                info!("Update successful. Restarting with new version");
+    // Updating doesn't need a config, so it's done before one is looked for
+    if cli.update {
+        return match update() {
+            Ok(vers) => {
+                if vers == self_update::cargo_crate_version!() {
+                    info!("Already up to date: {vers}");
+                } else {
+                    info!("Updated to version: {vers}");
+                }
+                Ok(())
             }
-        }
-        Err(e) => {
-            debug!("Error updating: {e}");
-            warn!("Update not completed. Continuing with current version");
-        }
+            Err(e) => {
+                error!("Error updating: {e}");
+                Err(e)
+            }
+        };
     }
 
-    let mut state = State::new(cli)?;
-    debug!("MAIN:: Config: {:?}", &state.config);
-    info!("Processing CSV data for: {:?}", state.config.source);
-
-    if let Err(proc_err) = state.process() {
-        error!("Error processing: {proc_err}");
+    if cli.init_from_source.is_some() {
+        return match config::write_init_config(&cli) {
+            Ok(written) => {
+                info!("Config generated at: {}", written.display());
+                Ok(())
+            }
+            Err(e) => {
+                error!("Error generating config: {e}");
+                Err(e)
+            }
+        };
     }
 
-    if !state.config.unique_fields.is_empty() || state.config.unique_fields.len().gt(&1) {
-        state.deduplicate();
-    } else {
-        warn!("No unique fields provided, skipping deduplication");
-    }
+    if let Some(config_dir) = &cli.config_dir {
+        let outcomes = batch::run_batch(&cli, batch::config_files(config_dir)?, cli.jobs, run)?;
+        info!("Batch finished:");
+        for line in batch::render_batch_status(&outcomes).lines() {
+            info!("{line}");
+        }
 
-    match state.output() {
-        Ok(()) => {
-            info!("Output successful");
+        let failed = outcomes.iter().filter(|outcome| outcome.status.is_err()).count();
+        return if failed == 0 {
             Ok(())
-        }
-        Err(e) => {
-            error!("Error outputting: {e}");
-            Err(e)
-        }
+        } else {
+            Err(Error::BatchFailed {
+                failed,
+                total: outcomes.len(),
+            })
+        };
     }
+
+    run(cli)
 }
@@ -18,6 +18,22 @@ pub struct W<T>(pub T);
 pub const CLI_ENV_PREFIX: &str = "CSV_CLI";
 pub const DEFAULT_CONFIG_DIR: &str = "config";
 pub const DEFAULT_CONFIG_FILE: &str = "config.json";
+/// The extensions of the config files `--config-dir` picks up.
+pub const CONFIG_FILE_EXTENSIONS: &[&str] = &["json", "yaml", "yml", "toml"];
+/// The `source` value that means "read the CSV from stdin".
+pub const STDIN_SOURCE: &str = "-";
+pub const REMOTE_CONFIG_CACHE_DIR: &str = "remote_cache";
+pub const DEFAULT_REMOTE_CONFIG_TTL_SECS: u64 = 60 * 60;
+/// How many rows NDJSON output writes between flushes.
+pub const NDJSON_FLUSH_ROWS: usize = 1000;
+/// The separator between the values joined by the `concat` aggregation of `dedup_aggregate`.
+pub const DEDUP_CONCAT_SEPARATOR: &str = ";";
+/// Without `has_headers`, columns are named this prefix followed by their zero-based index (`col0`, `col1`, ...).
+pub const SYNTHETIC_HEADER_PREFIX: &str = "col";
+/// How many records the `parallel` read filters at a time.
+pub const PARALLEL_BATCH_ROWS: usize = 4096;
+/// Columns with at most this many distinct values get a suggested filter from `--with-filters`.
+pub const INIT_FILTER_MAX_DISTINCT: usize = 10;
 pub const DEFAULT_FILLER: &str = r#"
 {
   "source": "some\\winodws\\path\\to\\file.csv",
@@ -47,34 +63,79 @@ pub const DEFAULT_FILLER: &str = r#"
 }
 "#;
 
+/// Where `update` looks for releases: the GitHub repository and the name of the binary in its release assets.
+///
+/// Each defaults to this crate's own, and can be overridden for a fork or private mirror with the `CSV_CLI_UPDATE_REPO_OWNER`,
+/// `CSV_CLI_UPDATE_REPO_NAME` and `CSV_CLI_UPDATE_BIN_NAME` environment variables. These are read from the environment
+/// rather than the config, as `--update` doesn't need one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UpdateSource {
+    pub repo_owner: String,
+    pub repo_name: String,
+    pub bin_name: String,
+}
+
+impl UpdateSource {
+    /// Reads the update source from the environment, see `UpdateSource`.
+    pub fn from_env() -> Self {
+        Self::from_lookup(|key| std::env::var(key).ok())
+    }
+
+    /// Builds the update source from the given lookup of environment variables, so tests don't have to set process-wide
+    /// variables. Unset or empty variables keep the default.
+    fn from_lookup(lookup: impl Fn(&str) -> Option<String>) -> Self {
+        let setting = |key: &str, default: String| {
+            lookup(&format!("{CLI_ENV_PREFIX}_{}", key.to_uppercase()))
+                .filter(|value| !value.is_empty())
+                .unwrap_or(default)
+        };
+
+        Self {
+            repo_owner: setting("update_repo_owner", first_author().to_lowercase()),
+            repo_name: setting("update_repo_name", crate_name!().to_string()),
+            bin_name: setting("update_bin_name", crate_name!().to_string()),
+        }
+    }
+}
+
+/// Updates the application to the latest release of its `UpdateSource`.
+///
+/// # Returns
+///
+/// * `Result<String>` - Returns the version now installed: the new one, or the current one when there was no newer release.
+///
+/// # Errors
+///
+/// Returns `Error::SelfUpdateFailed` if the release can't be fetched or installed (e.g. the repository doesn't exist,
+/// a rate limit was hit or the download is corrupt), or the update thread panicked.
 pub fn update() -> Result<String> {
-    let author = first_author().to_lowercase();
+    let source = UpdateSource::from_env();
     info!("Checking for updates...");
+    debug!("Update source: {:?}", source);
 
     let mut status_builder = self_update::backends::github::Update::configure();
     status_builder
-        .repo_owner(&author)
-        .repo_name(crate_name!())
-        .bin_name(crate_name!())
+        .repo_owner(&source.repo_owner)
+        .repo_name(&source.repo_name)
+        .bin_name(&source.bin_name)
         .current_version(self_update::cargo_crate_version!())
-        .show_output(true)
+        // self_update prints its progress with print!, which would end up mixed into data written to stdout
+        .show_output(false)
         .show_download_progress(true);
 
     trace!("stauts_build: {:#?}", status_builder);
 
     let stauts_cls = move || -> Result<Status> { Ok(status_builder.build()?.update()?) };
-    let status = std::thread::spawn(move || stauts_cls()).join().unwrap();
-    println!(); // self_update crate maintainer decided to use print! instead of println! or something....
+    let status = std::thread::spawn(stauts_cls)
+        .join()
+        .map_err(|_| self_update::errors::Error::Update("the update thread panicked".to_string()))??;
 
+    // No newer release isn't an error, the current version is simply kept
     match status {
-        Ok(v) => {
-            info!("Update successful. Restarting with new version");
-            Ok(v.version().to_string())
-        }
-        Err(_) => {
-            error!("Error updating.");
-            warn!("Update not completed. Continuing with current version");
-            Ok(self_update::cargo_crate_version!().to_string())
+        Status::UpToDate(version) => Ok(version),
+        Status::Updated(version) => {
+            info!("Update successful");
+            Ok(version)
         }
     }
 }
@@ -90,4 +151,35 @@ pub const NO_CONFIG_FILE_MSG: &str = r#"
     Config file either doesn't exist,
     is empty, or there was an error parsing it.
     Please check the config.json file.
-    "#;
\ No newline at end of file
+    "#;
+
+#[cfg(test)]
+mod prelude_tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    #[test]
+    fn test_update_source_overrides() {
+        let env = HashMap::from([
+            ("CSV_CLI_UPDATE_REPO_OWNER", "my-fork"),
+            ("CSV_CLI_UPDATE_REPO_NAME", "csv_mirror"),
+            ("CSV_CLI_UPDATE_BIN_NAME", ""),
+        ]);
+        let source = UpdateSource::from_lookup(|key| env.get(key).map(ToString::to_string));
+
+        assert_eq!(source.repo_owner, "my-fork");
+        assert_eq!(source.repo_name, "csv_mirror");
+        // An empty variable keeps the default
+        assert_eq!(source.bin_name, crate_name!());
+    }
+
+    #[test]
+    fn test_update_source_defaults() {
+        let source = UpdateSource::from_lookup(|_| None);
+
+        assert_eq!(source.repo_owner, first_author().to_lowercase());
+        assert_eq!(source.repo_name, crate_name!());
+        assert_eq!(source.bin_name, crate_name!());
+    }
+}
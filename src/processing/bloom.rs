@@ -0,0 +1,156 @@
+use std::f64::consts::LN_2;
+use std::hash::{DefaultHasher, Hash, Hasher};
+
+use crate::config::DedupBloom;
+use crate::prelude::*;
+
+/// A fixed-size bloom filter, for approximate set membership in far less memory than a `HashSet`.
+///
+/// It never reports a key it has seen as new, but may report a small fraction (`fp_rate`) of new keys as seen.
+///
+/// # Fields
+///
+/// * `bits` - The bit array, packed into `u64` words.
+/// * `num_bits` - The number of bits in use.
+/// * `num_hashes` - The number of bits set per key.
+#[derive(Debug, Clone)]
+pub struct BloomFilter {
+    bits: Vec<u64>,
+    num_bits: u64,
+    num_hashes: u32,
+}
+
+impl BloomFilter {
+    /// Creates a bloom filter sized for `capacity` keys at the given false-positive rate.
+    ///
+    /// # Arguments
+    ///
+    /// * `settings` - The expected number of keys and the acceptable false-positive rate.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Self>` - Returns the empty filter, or an `Error` if the settings are invalid.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::DedupBloom` if `capacity` is zero or `fp_rate` is not between 0 and 1 (exclusive).
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let mut bloom = BloomFilter::new(&DedupBloom { capacity: 1_000_000, fp_rate: 0.001 })?;
+    /// ```
+    pub fn new(settings: &DedupBloom) -> Result<Self> {
+        let DedupBloom { capacity, fp_rate } = *settings;
+        if capacity == 0 {
+            return Err(Error::DedupBloom("capacity must be greater than 0".to_string()));
+        }
+        if !(fp_rate > 0.0 && fp_rate < 1.0) {
+            return Err(Error::DedupBloom(format!("fp_rate must be between 0 and 1, got {fp_rate}")));
+        }
+
+        // The standard sizing: m = -n ln(p) / ln(2)^2 bits, and k = (m / n) ln(2) hashes
+        let num_bits = ((-(capacity as f64) * fp_rate.ln() / (LN_2 * LN_2)).ceil() as u64).max(64);
+        let num_hashes = ((num_bits as f64 / capacity as f64) * LN_2).round().max(1.0) as u32;
+        debug!("Bloom filter: {num_bits} bits, {num_hashes} hashes for {capacity} keys at {fp_rate}");
+
+        Ok(Self {
+            bits: vec![0; num_bits.div_ceil(64) as usize],
+            num_bits,
+            num_hashes,
+        })
+    }
+
+    /// Adds a key, reporting whether it was (probably) not seen before.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The key to add.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - Returns `true` if the key is new, `false` if it was (probably) added before.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// if bloom.insert(&key) {
+    ///     keep(row);
+    /// }
+    /// ```
+    pub fn insert<T: Hash + ?Sized>(&mut self, key: &T) -> bool {
+        let (h1, h2) = Self::hashes(key);
+        let mut is_new = false;
+
+        // Double hashing: the i-th bit is h1 + i * h2, which behaves like k independent hashes
+        for i in 0..u64::from(self.num_hashes) {
+            let bit = h1.wrapping_add(i.wrapping_mul(h2)) % self.num_bits;
+            let (word, mask) = ((bit / 64) as usize, 1 << (bit % 64));
+            if self.bits[word] & mask == 0 {
+                self.bits[word] |= mask;
+                is_new = true;
+            }
+        }
+        is_new
+    }
+
+    fn hashes<T: Hash + ?Sized>(key: &T) -> (u64, u64) {
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        let h1 = hasher.finish();
+        // Feeding the first hash back in gives a second, independent-enough one
+        h1.hash(&mut hasher);
+        (h1, hasher.finish() | 1)
+    }
+}
+
+#[cfg(test)]
+mod bloom_tests {
+    use super::*;
+
+    fn bloom(capacity: usize, fp_rate: f64) -> BloomFilter {
+        BloomFilter::new(&DedupBloom { capacity, fp_rate }).unwrap()
+    }
+
+    #[test]
+    fn test_bloom_never_misses_a_duplicate() {
+        let mut bloom = bloom(1_000, 0.01);
+        for key in 0..1_000 {
+            bloom.insert(&key);
+        }
+
+        assert!((0..1_000).all(|key| !bloom.insert(&key)));
+    }
+
+    #[test]
+    fn test_bloom_false_positive_rate() {
+        let capacity = 10_000;
+        let fp_rate = 0.01;
+        let mut bloom = bloom(capacity, fp_rate);
+
+        // Every key is unique, so each one reported as seen is a false positive
+        let false_positives = (0..capacity).filter(|key| !bloom.insert(&format!("key-{key}"))).count();
+        let observed = false_positives as f64 / capacity as f64;
+
+        // Filling up to capacity stays within a small margin of the configured rate
+        assert!(observed <= fp_rate * 2.0, "observed false-positive rate {observed}");
+    }
+
+    #[test]
+    fn test_bloom_invalid_settings() {
+        assert!(matches!(
+            BloomFilter::new(&DedupBloom {
+                capacity: 0,
+                fp_rate: 0.01
+            }),
+            Err(Error::DedupBloom(_))
+        ));
+        assert!(matches!(
+            BloomFilter::new(&DedupBloom {
+                capacity: 10,
+                fp_rate: 1.5
+            }),
+            Err(Error::DedupBloom(_))
+        ));
+    }
+}
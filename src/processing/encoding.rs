@@ -0,0 +1,186 @@
+use std::io::{Cursor, Read};
+
+use encoding_rs::{CoderResult, Decoder, Encoding, UTF_16BE, UTF_16LE};
+
+use crate::prelude::*;
+
+/// The size of the buffers raw and decoded bytes are collected in.
+const DECODE_BUF: usize = 8 * 1024;
+
+/// A reader that transcodes its input from another encoding (e.g. UTF-16LE or Latin-1) to UTF-8 as it is read.
+///
+/// Bytes that aren't valid in the encoding are replaced with `U+FFFD`, rather than failing the read.
+///
+/// # Example
+///
+/// ```ignore
+/// let utf8 = DecodeReader::new(File::open("export.csv")?, encoding_rs::UTF_16LE);
+/// ```
+pub struct DecodeReader<R: Read> {
+    inner: R,
+    decoder: Decoder,
+    input: Vec<u8>,
+    input_start: usize,
+    input_end: usize,
+    output: Vec<u8>,
+    output_start: usize,
+    output_end: usize,
+    eof: bool,
+}
+
+impl<R: Read> DecodeReader<R> {
+    /// Creates a reader decoding `inner` from `encoding`. A byte order mark at the start of `inner` is removed.
+    pub fn new(inner: R, encoding: &'static Encoding) -> Self {
+        Self {
+            inner,
+            decoder: encoding.new_decoder_with_bom_removal(),
+            input: vec![0; DECODE_BUF],
+            input_start: 0,
+            input_end: 0,
+            output: vec![0; DECODE_BUF],
+            output_start: 0,
+            output_end: 0,
+            eof: false,
+        }
+    }
+
+    /// Decodes the next chunk of `inner` into `output`, returning `false` once everything has been decoded.
+    fn fill_output(&mut self) -> std::io::Result<bool> {
+        loop {
+            if self.input_start == self.input_end && !self.eof {
+                self.input_start = 0;
+                self.input_end = self.inner.read(&mut self.input)?;
+                self.eof = self.input_end == 0;
+            }
+
+            let (result, read, written, _) =
+                self.decoder
+                    .decode_to_utf8(&self.input[self.input_start..self.input_end], &mut self.output, self.eof);
+            self.input_start += read;
+            self.output_start = 0;
+            self.output_end = written;
+
+            if written > 0 {
+                return Ok(true);
+            }
+            if self.eof && result == CoderResult::InputEmpty {
+                return Ok(false);
+            }
+        }
+    }
+}
+
+impl<R: Read> Read for DecodeReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        if self.output_start == self.output_end && !self.fill_output()? {
+            return Ok(0);
+        }
+
+        let n = buf.len().min(self.output_end - self.output_start);
+        buf[..n].copy_from_slice(&self.output[self.output_start..self.output_start + n]);
+        self.output_start += n;
+        Ok(n)
+    }
+}
+
+/// Transcodes a source to UTF-8, for the CSV reader.
+///
+/// With an `encoding` the source is decoded from it. Without one, a source starting with a UTF-16 byte order mark
+/// is decoded as UTF-16 (little or big endian, going by the mark), and anything else is read as UTF-8, untouched.
+///
+/// # Arguments
+///
+/// * `input` - The source, after any decompression.
+/// * `encoding` - The configured `encoding`, a WHATWG label such as `utf-16le`, `latin1` or `windows-1252`.
+///
+/// # Returns
+///
+/// * `Result<Box<dyn Read>>` - The source as UTF-8.
+///
+/// # Errors
+///
+/// This function will return an error if `encoding` isn't a known label, or the start of the source can't be read.
+pub(crate) fn decode_source(mut input: Box<dyn Read>, encoding: Option<&str>) -> Result<Box<dyn Read>> {
+    if let Some(label) = encoding {
+        let encoding =
+            Encoding::for_label(label.trim().as_bytes()).ok_or_else(|| Error::Encoding(label.to_string()))?;
+        debug!("Decoding the source from {}", encoding.name());
+        return Ok(Box::new(DecodeReader::new(input, encoding)));
+    }
+
+    // Only the first two bytes are needed to spot a UTF-16 BOM, they're put back in front of the rest afterwards
+    let mut bom = Vec::with_capacity(2);
+    input.by_ref().take(2).read_to_end(&mut bom)?;
+    let encoding = match bom.as_slice() {
+        [0xFF, 0xFE] => Some(UTF_16LE),
+        [0xFE, 0xFF] => Some(UTF_16BE),
+        _ => None,
+    };
+    let input: Box<dyn Read> = Box::new(Cursor::new(bom).chain(input));
+
+    match encoding {
+        Some(encoding) => {
+            debug!("Found a {} byte order mark, decoding the source from it", encoding.name());
+            Ok(Box::new(DecodeReader::new(input, encoding)))
+        }
+        None => Ok(input),
+    }
+}
+
+#[cfg(test)]
+mod encoding_tests {
+    use super::*;
+
+    fn utf16le(text: &str) -> Vec<u8> {
+        let mut bytes = vec![0xFF, 0xFE];
+        bytes.extend(text.encode_utf16().flat_map(u16::to_le_bytes));
+        bytes
+    }
+
+    fn decode(bytes: Vec<u8>, encoding: Option<&str>) -> String {
+        let mut text = String::new();
+        decode_source(Box::new(Cursor::new(bytes)), encoding)
+            .unwrap()
+            .read_to_string(&mut text)
+            .unwrap();
+        text
+    }
+
+    #[test]
+    fn test_utf16le_with_and_without_encoding() {
+        let text = "Größe,Nº,Ünit\n1,2,3\n";
+
+        assert_eq!(decode(utf16le(text), Some("utf-16le")), text);
+        assert_eq!(decode(utf16le(text), None), text);
+    }
+
+    #[test]
+    fn test_utf16be_bom_is_detected() {
+        let mut bytes = vec![0xFE, 0xFF];
+        bytes.extend("Id,Name\n".encode_utf16().flat_map(u16::to_be_bytes));
+
+        assert_eq!(decode(bytes, None), "Id,Name\n");
+    }
+
+    #[test]
+    fn test_latin1_and_plain_utf8() {
+        assert_eq!(decode(b"Caf\xe9,Ma\xf1ana\n".to_vec(), Some("latin1")), "Café,Mañana\n");
+        assert_eq!(decode("Café\n".as_bytes().to_vec(), None), "Café\n");
+        assert_eq!(decode(b"I".to_vec(), None), "I");
+    }
+
+    #[test]
+    fn test_decodes_across_buffer_boundaries() {
+        let text = "Größe\n".repeat(DECODE_BUF);
+
+        assert_eq!(decode(utf16le(&text), Some("utf-16le")), text);
+    }
+
+    #[test]
+    fn test_unknown_encoding() {
+        assert!(matches!(decode_source(Box::new(Cursor::new(Vec::new())), Some("utf-17")), Err(Error::Encoding(_))));
+    }
+}
@@ -0,0 +1,363 @@
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Write};
+use std::path::PathBuf;
+
+use tempfile::TempDir;
+
+use crate::config::DedupKeep;
+use crate::crate_name;
+use crate::prelude::*;
+use crate::processing::processor::composite_key;
+
+/// Rough per-cell overhead (the `String` itself) used when estimating buffered memory.
+const CELL_OVERHEAD: usize = std::mem::size_of::<String>();
+
+/// Sorts (and optionally deduplicates) rows that may not fit in memory.
+///
+/// Rows are buffered until `budget_bytes` is reached, at which point the buffer is sorted by
+/// the key columns and spilled to a temporary chunk file. `finish` then k-way merges every chunk,
+/// keeping a single row per key when deduplicating.
+///
+/// Rows with equal keys keep their input order, so deduplication keeps the first (or with
+/// `DedupKeep::Last`, the last) occurrence - exactly like the in-memory path.
+///
+/// # Example
+///
+/// ```ignore
+/// let mut sorter = ExternalSorter::new(vec![0], Some(DedupKeep::First), 64 * 1024 * 1024)?;
+/// sorter.push(vec!["b".to_string()])?;
+/// sorter.push(vec!["a".to_string()])?;
+/// let written = sorter.finish(&mut wtr, |_| Ok(()))?;
+/// ```
+pub struct ExternalSorter {
+    key_idxs: Vec<usize>,
+    dedup: Option<DedupKeep>,
+    budget_bytes: usize,
+    buffer: Vec<Vec<String>>,
+    buffered_bytes: usize,
+    chunk_dir: TempDir,
+    chunks: Vec<PathBuf>,
+}
+
+impl ExternalSorter {
+    /// Creates a new `ExternalSorter`, along with the temporary directory its chunks are written to.
+    ///
+    /// # Arguments
+    ///
+    /// * `key_idxs` - The indices (within a row) of the columns to sort and deduplicate on.
+    /// * `dedup` - Which row to keep per key, or `None` to keep every row.
+    /// * `budget_bytes` - The approximate number of bytes to buffer before spilling a chunk to disk.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Self>` - Returns a new `ExternalSorter`, or an `Error` if the temporary directory cannot be created.
+    pub fn new(key_idxs: Vec<usize>, dedup: Option<DedupKeep>, budget_bytes: usize) -> Result<Self> {
+        Ok(Self {
+            key_idxs,
+            dedup,
+            budget_bytes: budget_bytes.max(1),
+            buffer: Vec::new(),
+            buffered_bytes: 0,
+            chunk_dir: tempfile::Builder::new().prefix(crate_name!()).tempdir()?,
+            chunks: Vec::new(),
+        })
+    }
+
+    /// Adds a row, spilling the buffer to a chunk file once the memory budget is exceeded.
+    ///
+    /// # Arguments
+    ///
+    /// * `row` - The projected row to add.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<()>` - Returns `Ok(())` on success, or an `Error` if a chunk could not be written.
+    pub fn push(&mut self, row: Vec<String>) -> Result<()> {
+        self.buffered_bytes += row.iter().map(|cell| cell.len() + CELL_OVERHEAD).sum::<usize>();
+        self.buffer.push(row);
+
+        if self.buffered_bytes >= self.budget_bytes {
+            self.spill()?;
+        }
+        Ok(())
+    }
+
+    /// Merges all chunks and writes the sorted (and deduplicated) rows to the provided writer.
+    ///
+    /// The header row is not written; that is left to the caller.
+    /// Every surviving row is passed through `map_row` (in sorted order) just before it is written.
+    ///
+    /// # Arguments
+    ///
+    /// * `wtr` - A mutable reference to the CSV writer to write the final rows to.
+    /// * `map_row` - Called with each row before it is written, e.g. to append a running total.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<usize>` - Returns the number of rows written, or an `Error` on failure.
+    pub fn finish<W>(
+        mut self,
+        wtr: &mut csv::Writer<W>,
+        mut map_row: impl FnMut(&mut Vec<String>) -> Result<()>,
+    ) -> Result<usize>
+    where
+        W: std::io::Write,
+    {
+        self.spill()?;
+        debug!("Merging {} sorted chunks from {:?}", self.chunks.len(), self.chunk_dir.path());
+
+        let mut readers = self
+            .chunks
+            .iter()
+            .map(|chunk| {
+                Ok(csv::ReaderBuilder::new()
+                    .has_headers(false)
+                    .flexible(true)
+                    .from_reader(BufReader::new(File::open(chunk)?)))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut heap = BinaryHeap::with_capacity(readers.len());
+        for (chunk, reader) in readers.iter_mut().enumerate() {
+            if let Some(item) = self.next_item(reader, chunk)? {
+                heap.push(Reverse(item));
+            }
+        }
+
+        let mut written = 0;
+        let mut write = |mut row: Vec<String>| -> Result<()> {
+            map_row(&mut row)?;
+            wtr.write_record(&row)?;
+            written += 1;
+            Ok(())
+        };
+
+        let mut last_key: Option<Vec<String>> = None;
+        // With `Last`, the latest row of the current key is held back until the key changes
+        let mut pending: Option<MergeItem> = None;
+
+        while let Some(Reverse(item)) = heap.pop() {
+            if let Some(next) = self.next_item(&mut readers[item.chunk], item.chunk)? {
+                heap.push(Reverse(next));
+            }
+
+            match self.dedup {
+                None => write(item.row)?,
+                Some(DedupKeep::First) => {
+                    if last_key.as_ref() == Some(&item.key) {
+                        continue;
+                    }
+                    write(item.row)?;
+                    last_key = Some(item.key);
+                }
+                Some(DedupKeep::Last) => {
+                    if let Some(previous) = pending.take()
+                        && previous.key != item.key
+                    {
+                        write(previous.row)?;
+                    }
+                    pending = Some(item);
+                }
+            }
+        }
+
+        if let Some(previous) = pending {
+            write(previous.row)?;
+        }
+
+        Ok(written)
+    }
+
+    /// Sorts the current buffer by key and writes it to a new chunk file.
+    fn spill(&mut self) -> Result<()> {
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
+
+        // Stable, so equal keys keep their input order within the chunk
+        let key_idxs = &self.key_idxs;
+        self.buffer.sort_by_cached_key(|row| composite_key(row, key_idxs));
+
+        let path = self.chunk_dir.path().join(format!("chunk_{}.csv", self.chunks.len()));
+        let mut wtr = csv::WriterBuilder::new()
+            .has_headers(false)
+            .from_writer(BufWriter::new(File::create(&path)?));
+        for row in self.buffer.drain(..) {
+            wtr.write_record(&row)?;
+        }
+        wtr.into_inner().map_err(|e| Error::Io(e.into_error()))?.flush()?;
+
+        trace!("Spilled chunk {:?} ({} bytes buffered)", path, self.buffered_bytes);
+        self.chunks.push(path);
+        self.buffered_bytes = 0;
+        Ok(())
+    }
+
+    fn next_item(&self, reader: &mut csv::Reader<BufReader<File>>, chunk: usize) -> Result<Option<MergeItem>> {
+        let mut record = csv::StringRecord::new();
+        if !reader.read_record(&mut record)? {
+            return Ok(None);
+        }
+
+        let row = record.iter().map(ToString::to_string).collect::<Vec<_>>();
+        Ok(Some(MergeItem {
+            key: composite_key(&row, &self.key_idxs),
+            row,
+            chunk,
+        }))
+    }
+}
+
+/// The head row of a chunk during the k-way merge.
+///
+/// Ordered by key, then by chunk index - chunks are written in input order, so on equal keys
+/// the row that was read first wins.
+#[derive(Debug, PartialEq, Eq)]
+struct MergeItem {
+    key: Vec<String>,
+    row: Vec<String>,
+    chunk: usize,
+}
+
+impl Ord for MergeItem {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key.cmp(&other.key).then_with(|| self.chunk.cmp(&other.chunk))
+    }
+}
+
+impl PartialOrd for MergeItem {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+#[cfg(test)]
+mod external_sorter_tests {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    /// A deterministic "medium" fixture with plenty of duplicate keys spread across the input.
+    fn fixture(rows: usize) -> Vec<Vec<String>> {
+        (0..rows)
+            .map(|i| {
+                let key = (i * 7919) % 97;
+                vec![format!("k{key:03}"), format!("{}", i % 5), format!("row{i}")]
+            })
+            .collect()
+    }
+
+    /// The in-memory equivalent: stable sort by key, then keep the first (or last) row per key.
+    fn in_memory(rows: &[Vec<String>], key_idxs: &[usize], dedup: Option<DedupKeep>) -> Vec<Vec<String>> {
+        let mut rows = rows.to_vec();
+        rows.sort_by_cached_key(|row| composite_key(row, key_idxs));
+
+        let keep_last = dedup == Some(DedupKeep::Last);
+        if dedup.is_some() {
+            if keep_last {
+                rows.reverse();
+            }
+            let mut seen = HashSet::new();
+            rows.retain(|row| seen.insert(composite_key(row, key_idxs)));
+            if keep_last {
+                rows.reverse();
+            }
+        }
+        rows
+    }
+
+    fn external(
+        rows: &[Vec<String>],
+        key_idxs: &[usize],
+        dedup: Option<DedupKeep>,
+        budget_bytes: usize,
+    ) -> Vec<Vec<String>> {
+        let mut sorter = ExternalSorter::new(key_idxs.to_vec(), dedup, budget_bytes).unwrap();
+        for row in rows {
+            sorter.push(row.clone()).unwrap();
+        }
+        assert!(sorter.chunks.len() > 1 || budget_bytes == usize::MAX);
+
+        let mut wtr = csv::WriterBuilder::new().has_headers(false).from_writer(vec![]);
+        let written = sorter.finish(&mut wtr, |_| Ok(())).unwrap();
+
+        let out = wtr.into_inner().unwrap();
+        let result = csv::ReaderBuilder::new()
+            .has_headers(false)
+            .from_reader(out.as_slice())
+            .records()
+            .map(|r| r.unwrap().iter().map(ToString::to_string).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        assert_eq!(written, result.len());
+        result
+    }
+
+    #[test]
+    fn test_external_matches_in_memory_dedup() {
+        let rows = fixture(2_000);
+        let key_idxs = [0];
+
+        let expected = in_memory(&rows, &key_idxs, Some(DedupKeep::First));
+        let actual = external(&rows, &key_idxs, Some(DedupKeep::First), 2 * 1024);
+
+        assert_eq!(expected.len(), 97);
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_external_matches_in_memory_composite_key() {
+        let rows = fixture(2_000);
+        let key_idxs = [0, 1];
+
+        assert_eq!(
+            external(&rows, &key_idxs, Some(DedupKeep::First), 4 * 1024),
+            in_memory(&rows, &key_idxs, Some(DedupKeep::First))
+        );
+    }
+
+    #[test]
+    fn test_external_matches_in_memory_keep_last() {
+        let rows = fixture(2_000);
+        let key_idxs = [0];
+
+        let expected = in_memory(&rows, &key_idxs, Some(DedupKeep::Last));
+        let actual = external(&rows, &key_idxs, Some(DedupKeep::Last), 2 * 1024);
+
+        assert_eq!(expected.len(), 97);
+        assert_ne!(expected, in_memory(&rows, &key_idxs, Some(DedupKeep::First)));
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_external_sort_without_dedup_keeps_every_row() {
+        let rows = fixture(500);
+        let key_idxs = [0];
+
+        let actual = external(&rows, &key_idxs, None, 1024);
+
+        assert_eq!(actual.len(), 500);
+        assert_eq!(actual, in_memory(&rows, &key_idxs, None));
+    }
+
+    #[test]
+    fn test_external_single_chunk() {
+        let rows = fixture(100);
+        assert_eq!(
+            external(&rows, &[0], Some(DedupKeep::First), usize::MAX),
+            in_memory(&rows, &[0], Some(DedupKeep::First))
+        );
+        assert_eq!(
+            external(&rows, &[0], Some(DedupKeep::Last), usize::MAX),
+            in_memory(&rows, &[0], Some(DedupKeep::Last))
+        );
+    }
+
+    #[test]
+    fn test_external_empty_input() {
+        let sorter = ExternalSorter::new(vec![0], Some(DedupKeep::Last), 1024).unwrap();
+        let mut wtr = csv::Writer::from_writer(vec![]);
+        assert_eq!(sorter.finish(&mut wtr, |_| Ok(())).unwrap(), 0);
+    }
+}
@@ -0,0 +1,307 @@
+use std::collections::HashSet;
+
+use chrono::NaiveDate;
+use chrono::format::{Item, StrftimeItems};
+use regex::Regex;
+
+use crate::config::{DateRange, FilterRule, MatchMode};
+use crate::prelude::*;
+
+/// A filter that has been resolved against the CSV headers and is ready to be evaluated per row.
+///
+/// The values of a `FilterRule` are prepared once (regexes compiled, numeric bounds parsed)
+/// so that `matches` does no per-row setup work.
+/// `ExactIgnoreCase` holds the values already lowercased, and is built with `exact_ignore_case`.
+/// `DateRange` holds the parsed bounds and the format cells are parsed with, and is built with `date_range`.
+///
+/// # Example
+///
+/// ```ignore
+/// let filter = ColumnFilter::new(MatchMode::Prefix, &["AB".to_string()])?;
+/// assert!(filter.matches("ABC123"));
+/// ```
+#[derive(Debug, Clone)]
+pub enum ColumnFilter {
+    Exact(Vec<String>),
+    ExactIgnoreCase(HashSet<String>),
+    Contains(Vec<String>),
+    Regex(Vec<Regex>),
+    NumericRange(Option<f64>, Option<f64>),
+    Prefix(Vec<String>),
+    DateRange {
+        format: String,
+        from: Option<NaiveDate>,
+        to: Option<NaiveDate>,
+        keep_unparseable: bool,
+    },
+}
+
+impl ColumnFilter {
+    /// Prepares a `ColumnFilter` for the given mode and values.
+    ///
+    /// # Arguments
+    ///
+    /// * `mode` - The `MatchMode` to compare with.
+    /// * `values` - The configured values. For `NumericRange` these are `[min, max]`, where an empty
+    ///   or missing bound means unbounded on that side.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Self>` - Returns the prepared filter, or an `Error` on failure.
+    ///
+    /// # Errors
+    ///
+    /// * `Error::RegexCapture` - If a `Regex` value fails to compile.
+    /// * `Error::ConfigParse` - If a `NumericRange` bound is not a number, or more than two bounds are given.
+    pub fn new(mode: MatchMode, values: &[String]) -> Result<Self> {
+        let filter = match mode {
+            MatchMode::Exact => Self::Exact(values.to_vec()),
+            MatchMode::Contains => Self::Contains(values.to_vec()),
+            MatchMode::Prefix => Self::Prefix(values.to_vec()),
+            MatchMode::Regex => Self::Regex(
+                values
+                    .iter()
+                    .map(|v| Regex::new(v).map_err(|e| Error::RegexCapture(e.to_string())))
+                    .collect::<Result<Vec<_>>>()?,
+            ),
+            MatchMode::NumericRange => {
+                if values.len() > 2 {
+                    return Err(config_value_error(format!(
+                        "numeric_range expects at most two values [min, max], got {}",
+                        values.len()
+                    )));
+                }
+                let min = parse_bound(values.first())?;
+                let max = parse_bound(values.get(1))?;
+                Self::NumericRange(min, max)
+            }
+        };
+        Ok(filter)
+    }
+
+    /// Prepares a case-insensitive `Exact` filter, lowercasing the values once up front.
+    ///
+    /// # Arguments
+    ///
+    /// * `values` - The configured values.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - Returns the prepared filter.
+    pub fn exact_ignore_case(values: &[String]) -> Self {
+        Self::ExactIgnoreCase(values.iter().map(|v| v.to_lowercase()).collect())
+    }
+
+    /// Prepares a `DateRange` filter for a `date_filters` entry, parsing its bounds once up front.
+    ///
+    /// # Arguments
+    ///
+    /// * `range` - The configured bounds, written as `YYYY-MM-DD`.
+    /// * `format` - The chrono format the cells are parsed with, the config's `date_format`.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Self>` - Returns the prepared filter, or an `Error` on failure.
+    ///
+    /// # Errors
+    ///
+    /// * `Error::DateFormat` - If `format` isn't a valid chrono format.
+    /// * `Error::ConfigParse` - If a bound isn't a `YYYY-MM-DD` date.
+    pub fn date_range(range: &DateRange, format: &str) -> Result<Self> {
+        if StrftimeItems::new(format).any(|item| matches!(item, Item::Error)) {
+            return Err(Error::DateFormat(format.to_string()));
+        }
+
+        Ok(Self::DateRange {
+            format: format.to_string(),
+            from: parse_date_bound(range.from.as_ref())?,
+            to: parse_date_bound(range.to.as_ref())?,
+            keep_unparseable: range.keep_unparseable,
+        })
+    }
+
+    /// Checks if a single cell value satisfies this filter.
+    ///
+    /// # Arguments
+    ///
+    /// * `val` - The cell value to check.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - Returns `true` if the value matches, `false` otherwise.
+    pub fn matches(&self, val: &str) -> bool {
+        match self {
+            Self::Exact(values) => values.iter().any(|v| v == val),
+            Self::ExactIgnoreCase(values) => values.contains(&val.to_lowercase()),
+            Self::Contains(values) => values.iter().any(|v| val.contains(v.as_str())),
+            Self::Prefix(values) => values.iter().any(|v| val.starts_with(v.as_str())),
+            Self::Regex(regexes) => regexes.iter().any(|re| re.is_match(val)),
+            Self::NumericRange(min, max) => val
+                .trim()
+                .parse::<f64>()
+                .is_ok_and(|num| min.is_none_or(|min| num >= min) && max.is_none_or(|max| num <= max)),
+            Self::DateRange {
+                format,
+                from,
+                to,
+                keep_unparseable,
+            } => match NaiveDate::parse_from_str(val.trim(), format) {
+                Ok(date) => from.is_none_or(|from| date >= from) && to.is_none_or(|to| date <= to),
+                Err(_) => *keep_unparseable,
+            },
+        }
+    }
+}
+
+impl TryFrom<&FilterRule> for ColumnFilter {
+    type Error = Error;
+
+    fn try_from(rule: &FilterRule) -> Result<Self> {
+        Self::new(rule.mode, &rule.values)
+    }
+}
+
+fn parse_bound(bound: Option<&String>) -> Result<Option<f64>> {
+    match bound.map(|b| b.trim()) {
+        None | Some("") => Ok(None),
+        Some(b) => b
+            .parse::<f64>()
+            .map(Some)
+            .map_err(|e| config_value_error(format!("invalid numeric_range bound '{b}': {e}"))),
+    }
+}
+
+fn parse_date_bound(bound: Option<&String>) -> Result<Option<NaiveDate>> {
+    match bound.map(|b| b.trim()) {
+        None | Some("") => Ok(None),
+        Some(b) => NaiveDate::parse_from_str(b, "%Y-%m-%d")
+            .map(Some)
+            .map_err(|e| config_value_error(format!("invalid date_filters bound '{b}', expected YYYY-MM-DD: {e}"))),
+    }
+}
+
+fn config_value_error(msg: String) -> Error {
+    Error::ConfigParse(config::ConfigError::Message(msg))
+}
+
+#[cfg(test)]
+mod column_filter_tests {
+    use super::*;
+
+    fn values(vals: &[&str]) -> Vec<String> {
+        vals.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn test_exact() {
+        let filter = ColumnFilter::new(MatchMode::Exact, &values(&["Active", "Pending"])).unwrap();
+        assert!(filter.matches("Active"));
+        assert!(!filter.matches("active"));
+        assert!(!filter.matches("Active "));
+    }
+
+    #[test]
+    fn test_exact_ignore_case() {
+        let filter = ColumnFilter::exact_ignore_case(&values(&["Active", "PENDING"]));
+        assert!(filter.matches("active"));
+        assert!(filter.matches("ACTIVE"));
+        assert!(filter.matches("Pending"));
+        assert!(!filter.matches("Active "));
+        assert!(!filter.matches("Closed"));
+    }
+
+    #[test]
+    fn test_contains() {
+        let filter = ColumnFilter::new(MatchMode::Contains, &values(&["refund"])).unwrap();
+        assert!(filter.matches("partial refund issued"));
+        assert!(!filter.matches("payment"));
+    }
+
+    #[test]
+    fn test_regex() {
+        let filter = ColumnFilter::new(MatchMode::Regex, &values(&[r"^\d{3}-\d{4}$"])).unwrap();
+        assert!(filter.matches("123-4567"));
+        assert!(!filter.matches("1234-567"));
+    }
+
+    #[test]
+    fn test_regex_invalid() {
+        let filter = ColumnFilter::new(MatchMode::Regex, &values(&["(unclosed"]));
+        assert!(matches!(filter, Err(Error::RegexCapture(_))));
+    }
+
+    #[test]
+    fn test_numeric_range() {
+        let filter = ColumnFilter::new(MatchMode::NumericRange, &values(&["10", "100.5"])).unwrap();
+        assert!(filter.matches("10"));
+        assert!(filter.matches("100.5"));
+        assert!(filter.matches(" 42 "));
+        assert!(!filter.matches("9.99"));
+        assert!(!filter.matches("101"));
+        assert!(!filter.matches("not a number"));
+    }
+
+    #[test]
+    fn test_numeric_range_unbounded() {
+        let min_only = ColumnFilter::new(MatchMode::NumericRange, &values(&["0", ""])).unwrap();
+        assert!(min_only.matches("1e9"));
+        assert!(!min_only.matches("-1"));
+
+        let max_only = ColumnFilter::new(MatchMode::NumericRange, &values(&["", "0"])).unwrap();
+        assert!(max_only.matches("-1e9"));
+        assert!(!max_only.matches("1"));
+    }
+
+    #[test]
+    fn test_date_range() {
+        let range = DateRange {
+            from: Some("2024-01-01".to_string()),
+            to: Some("2024-03-31".to_string()),
+            keep_unparseable: false,
+        };
+        let filter = ColumnFilter::date_range(&range, "%d/%m/%Y").unwrap();
+
+        // Both ends are inclusive
+        assert!(filter.matches("01/01/2024"));
+        assert!(filter.matches(" 31/03/2024 "));
+        assert!(filter.matches("15/02/2024"));
+        assert!(!filter.matches("31/12/2023"));
+        assert!(!filter.matches("01/04/2024"));
+        assert!(!filter.matches("2024-02-15"));
+        assert!(!filter.matches(""));
+
+        let keep = DateRange {
+            keep_unparseable: true,
+            to: None,
+            ..range
+        };
+        let filter = ColumnFilter::date_range(&keep, "%Y-%m-%d").unwrap();
+        assert!(filter.matches("2099-01-01"));
+        assert!(!filter.matches("2023-12-31"));
+        assert!(filter.matches("n/a"));
+    }
+
+    #[test]
+    fn test_date_range_invalid() {
+        let bad_bound = DateRange {
+            from: Some("01/01/2024".to_string()),
+            ..Default::default()
+        };
+        assert!(matches!(ColumnFilter::date_range(&bad_bound, "%d/%m/%Y"), Err(Error::ConfigParse(_))));
+        assert!(matches!(ColumnFilter::date_range(&DateRange::default(), "%Y-%Q"), Err(Error::DateFormat(_))));
+    }
+
+    #[test]
+    fn test_numeric_range_invalid_bound() {
+        let filter = ColumnFilter::new(MatchMode::NumericRange, &values(&["ten"]));
+        assert!(matches!(filter, Err(Error::ConfigParse(_))));
+    }
+
+    #[test]
+    fn test_prefix() {
+        let filter = ColumnFilter::new(MatchMode::Prefix, &values(&["AB", "CD"])).unwrap();
+        assert!(filter.matches("AB123"));
+        assert!(filter.matches("CD"));
+        assert!(!filter.matches("XAB"));
+    }
+}
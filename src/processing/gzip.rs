@@ -0,0 +1,27 @@
+use std::path::Path;
+
+/// Checks whether a path names a gzip file, going by its `.gz` extension.
+///
+/// # Arguments
+///
+/// * `path` - The path to check.
+///
+/// # Returns
+///
+/// * `bool` - Returns `true` if the path ends in `.gz` (in any case).
+pub fn is_gzip_path(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("gz"))
+}
+
+#[cfg(test)]
+mod gzip_tests {
+    use super::*;
+
+    #[test]
+    fn test_is_gzip_path() {
+        assert!(is_gzip_path(Path::new("output.csv.gz")));
+        assert!(is_gzip_path(Path::new("OUTPUT.CSV.GZ")));
+        assert!(!is_gzip_path(Path::new("output.csv")));
+        assert!(!is_gzip_path(Path::new("gz")));
+    }
+}
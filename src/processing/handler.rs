@@ -1,9 +1,11 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::HashMap;
 
 use csv::StringRecord;
 use rayon::prelude::*;
 
-use crate::config::Config;
+use crate::config::{BooleanField, ComputedColumn, Config, ExprTerm, FilterLogic, normalize_header};
+use crate::prelude::*;
+use crate::processing::filter::ColumnFilter;
 use crate::retained::RetainedData;
 
 /// Represents the handler for managing CSV processing.
@@ -14,16 +16,32 @@ use crate::retained::RetainedData;
 /// # Fields
 ///
 /// * `field_idxs` - A vector of indices representing the columns to be retained.
-/// * `filter_idxs` - A hashmap where the key is the column index and the value is a vector of valid values for filtering.
+/// * `boolean_fields` - Parallel to `field_idxs`, the optional `BooleanField` normalization for each retained column.
+/// * `computed` - The terms of each `computed` column, appended after the retained columns.
+/// * `filter_idxs` - A vector of column indices paired with the `ColumnFilter` to apply to that column.
+/// * `exclude_idxs` - A vector of column indices paired with the blacklisted values for that column.
+/// * `non_empty_idxs` - The indices of the `require_non_empty` columns, which must hold a value for a row to be kept.
+/// * `empty_idxs` - The indices of the `require_empty` columns, which must be empty for a row to be kept.
+/// * `post_filter_idxs` - Like `filter_idxs`, but the indices point into the projected (retained) row.
+/// * `filter_logic` - Whether all (`And`) or any (`Or`) of the filters must match for a row to be kept.
+/// * `filter_min_match` - When set, at least this many of the `filter_idxs` must match instead, overriding `filter_logic`.
 ///
 /// # Example
 ///
-/// ```rust
-/// let handler = CsvHandler::new(&config, &mut retained_data, &headers);
+/// ```ignore
+/// let handler = CsvHandler::new(&config, &mut retained_data, &headers)?;
 /// ```
 pub struct CsvHandler {
     field_idxs: Vec<usize>,
-    filter_idxs: HashMap<usize, Vec<String>>,
+    boolean_fields: Vec<Option<BooleanField>>,
+    computed: Vec<Vec<ComputedPart>>,
+    filter_idxs: Vec<(usize, ColumnFilter)>,
+    exclude_idxs: Vec<(usize, ColumnFilter)>,
+    non_empty_idxs: Vec<usize>,
+    empty_idxs: Vec<usize>,
+    post_filter_idxs: Vec<(usize, ColumnFilter)>,
+    filter_logic: FilterLogic,
+    filter_min_match: Option<usize>,
 }
 
 impl CsvHandler {
@@ -31,6 +49,26 @@ impl CsvHandler {
     ///
     /// This function initializes the handler by setting up the field indices and filter indices
     /// based on the provided configuration and CSV headers.
+    /// A `fields` entry of `#N` or `$N` selects the column at zero-based index `N`, which can be mixed with names.
+    /// Either way, the columns are retained in the order they are listed in `fields`, a column listed twice is kept once.
+    /// `fields` entries and `include_cols_with` keys that are not columns of the source are ignored with a warning,
+    /// or are an error when `strict_fields` is set.
+    /// Legacy `include_cols_with` entries are treated as `MatchMode::Exact` filters (compared ignoring case when
+    /// `case_insensitive_filters` is set), and are evaluated alongside any `filters` entries. Filters on columns not present in the headers are ignored.
+    /// `numeric_filters` entries become `ColumnFilter::NumericRange` filters, and `date_filters` entries
+    /// `ColumnFilter::DateRange` filters parsing the cells with `date_format`.
+    /// `exclude_cols_with` entries are kept separately, as they reject a row rather than keep it.
+    /// `require_non_empty` and `require_empty` columns are resolved like `fields` entries, but don't have to be retained.
+    /// Retained columns listed in `rename` get their new name in `retained_headers`, renames of other columns are ignored.
+    /// `computed` columns are appended to `retained_headers` after the retained columns. The columns their expressions
+    /// reference are resolved like `fields` entries (a missing one is evaluated as an empty string).
+    /// `post_filters` are resolved against the final `retained_headers` rather than the source headers.
+    /// Every `unique_fields` entry must be one of the final `retained_headers` (i.e. its new name if renamed).
+    /// The headers are normalized first (see `normalize_headers`), as are the column names in the config when they are
+    /// matched, so that a header with a trailing space or a stray `\r` can still be selected by its name. The normalized
+    /// headers are the ones written to the output.
+    /// With `case_insensitive_headers`, the column names in the config match the headers ignoring case, while
+    /// `retained_headers` keep the casing of the source.
     ///
     /// # Arguments
     ///
@@ -40,44 +78,199 @@ impl CsvHandler {
     ///
     /// # Returns
     ///
-    /// * `Self` - Returns a new `CsvHandler` instance.
+    /// * `Result<Self>` - Returns a new `CsvHandler` instance, or an `Error` if a filter could not be prepared.
+    ///
+    /// # Errors
+    ///
+    /// This function can return errors if a `filters` entry has an invalid regex or numeric bound, a `date_filters` entry
+    /// has an invalid bound (or `date_format` is invalid), a `computed` expression
+    /// can't be parsed, or with `strict_fields`, if any `fields` entry, `include_cols_with` key, `require_non_empty` /
+    /// `require_empty` column or column referenced by a `computed` expression is not a column of the source (all of them
+    /// are listed at once). It also returns an error if any `unique_fields` entry is not a retained column.
     ///
     /// # Example
     ///
-    /// ```rust
-    /// let handler = CsvHandler::new(&config, &mut retained_data, &headers);
+    /// ```ignore
+    /// let handler = CsvHandler::new(&config, &mut retained_data, &headers)?;
     /// ```
-    #[allow(clippy::unnecessary_to_owned)] // for (idx, col_name) loop -- contains(&col_name.to_string()) loop
-    pub(crate) fn new(config: &Config, retained_data: &mut RetainedData, headers: &StringRecord) -> Self {
+    pub(crate) fn new(config: &Config, retained_data: &mut RetainedData, headers: &StringRecord) -> Result<Self> {
+        let headers = &normalize_headers(headers);
         retained_data.all_headers = headers.iter().map(ToString::to_string).collect();
 
-        let fields_set: HashSet<&String> = config.fields.iter().collect();
+        let mut field_idxs = Vec::with_capacity(config.fields.len());
+        let mut missing = Vec::new();
+        for field in &config.fields {
+            match resolve_field(field, headers, config) {
+                Some(idx) if field_idxs.contains(&idx) => {}
+                Some(idx) => field_idxs.push(idx),
+                None => missing.push(field.as_str()),
+            }
+        }
 
-        let mut field_idxs = Vec::with_capacity(fields_set.len());
-        let mut filter_idxs = HashMap::with_capacity(config.include_cols_with.len());
+        let mut missing_filters = config
+            .include_cols_with
+            .keys()
+            .filter(|col_name| !headers.iter().any(|header| config.header_matches(header, col_name)))
+            .map(String::as_str)
+            .collect::<Vec<_>>();
+        missing_filters.sort_unstable();
+        missing.extend(missing_filters);
+
+        let non_empty_idxs = resolve_columns(&config.require_non_empty, headers, config, &mut missing);
+        let empty_idxs = resolve_columns(&config.require_empty, headers, config, &mut missing);
+
+        let computed_terms = config
+            .computed
+            .iter()
+            .map(ComputedColumn::terms)
+            .collect::<Result<Vec<_>>>()?;
+        for term in computed_terms.iter().flatten() {
+            if let ExprTerm::Column(name) = term
+                && resolve_field(name, headers, config).is_none()
+                && !missing.contains(&name.as_str())
+            {
+                missing.push(name);
+            }
+        }
+
+        if !missing.is_empty() {
+            let missing = missing
+                .iter()
+                .map(|name| format!("{name:?}"))
+                .collect::<Vec<_>>()
+                .join(", ");
+            if config.strict_fields {
+                return Err(Error::CsvHeaders(format!(
+                    "these fields are not columns of the source ({} columns): {missing}",
+                    headers.len()
+                )));
+            }
+            warn!("These fields are not columns of the source and are ignored: {missing}");
+        }
+
+        let mut filter_idxs = Vec::with_capacity(
+            config.include_cols_with.len()
+                + config.numeric_filters.len()
+                + config.date_filters.len()
+                + config.filters.len(),
+        );
+        let mut exclude_idxs = Vec::with_capacity(config.exclude_cols_with.len());
 
         for (idx, col_name) in headers.iter().enumerate() {
-            if fields_set.contains(&col_name.to_string()) {
-                field_idxs.push(idx);
+            if let Some(valid_values) = column_entry(&config.include_cols_with, col_name, config) {
+                let filter = if config.case_insensitive_filters {
+                    ColumnFilter::exact_ignore_case(valid_values)
+                } else {
+                    ColumnFilter::Exact(valid_values.clone())
+                };
+                filter_idxs.push((idx, filter));
             }
 
-            if let Some(valid_values) = config.include_cols_with.get(col_name) {
-                filter_idxs.insert(idx, valid_values.clone());
+            if let Some(&(min, max)) = column_entry(&config.numeric_filters, col_name, config) {
+                filter_idxs.push((idx, ColumnFilter::NumericRange(min, max)));
+            }
+
+            if let Some(range) = column_entry(&config.date_filters, col_name, config) {
+                filter_idxs.push((idx, ColumnFilter::date_range(range, &config.date_format)?));
+            }
+
+            if let Some(excluded_values) = column_entry(&config.exclude_cols_with, col_name, config) {
+                exclude_idxs.push((idx, ColumnFilter::Exact(excluded_values.clone())));
+            }
+        }
+
+        for rule in &config.filters {
+            if let Some(idx) = headers
+                .iter()
+                .position(|col_name| config.header_matches(col_name, &rule.column))
+            {
+                filter_idxs.push((idx, ColumnFilter::try_from(rule)?));
             }
         }
 
-        retained_data.retained_headers = field_idxs.iter().map(|&idx| headers[idx].to_string()).collect();
+        let boolean_fields = field_idxs
+            .iter()
+            .map(|&idx| column_entry(&config.boolean_fields, &headers[idx], config).cloned())
+            .collect();
 
-        Self {
+        retained_data.retained_headers = field_idxs
+            .iter()
+            .map(|&idx| {
+                column_entry(&config.rename, &headers[idx], config)
+                    .map_or(&headers[idx], String::as_str)
+                    .to_string()
+            })
+            .collect();
+        retained_data
+            .retained_headers
+            .extend(config.computed.iter().map(|column| column.name.clone()));
+
+        let computed = computed_terms
+            .into_iter()
+            .map(|terms| {
+                terms
+                    .into_iter()
+                    .map(|term| match term {
+                        ExprTerm::Column(name) => resolve_field(&name, headers, config)
+                            .map_or(ComputedPart::Text(String::new()), ComputedPart::Cell),
+                        ExprTerm::Literal(text) => ComputedPart::Text(text),
+                    })
+                    .collect()
+            })
+            .collect();
+
+        let missing_unique = config
+            .unique_fields
+            .iter()
+            .filter(|field| {
+                !retained_data
+                    .retained_headers
+                    .iter()
+                    .any(|header| config.header_matches(header, field))
+            })
+            .map(|field| format!("{field:?}"))
+            .collect::<Vec<_>>();
+        if !missing_unique.is_empty() {
+            return Err(Error::CsvHeaders(format!(
+                "these unique_fields are not retained fields: {}",
+                missing_unique.join(", ")
+            )));
+        }
+
+        // Resolved last, so that post filters see the output columns exactly as they will be written
+        let mut post_filter_idxs = Vec::with_capacity(config.post_filters.len());
+        for rule in &config.post_filters {
+            if let Some(idx) = retained_data
+                .retained_headers
+                .iter()
+                .position(|col_name| config.header_matches(col_name, &rule.column))
+            {
+                post_filter_idxs.push((idx, ColumnFilter::try_from(rule)?));
+            }
+        }
+
+        Ok(Self {
             field_idxs,
+            boolean_fields,
+            computed,
             filter_idxs,
-        }
+            exclude_idxs,
+            non_empty_idxs,
+            empty_idxs,
+            post_filter_idxs,
+            filter_logic: config.filter_logic,
+            filter_min_match: config.filter_min_match,
+        })
     }
 
     /// Checks if a CSV record passes the configured filters.
     ///
     /// This function iterates over the filter indices and checks if the values in the record
-    /// match the valid values specified in the configuration.
+    /// match the valid values specified in the configuration, combining the results with the
+    /// configured `filter_logic`, or by requiring at least `filter_min_match` of them to match when that is set.
+    /// A row always passes when no filters are configured.
+    /// Once the include filters pass, the row is rejected if any `exclude_cols_with` column holds a blacklisted value,
+    /// a `require_non_empty` column is empty or a `require_empty` column isn't (a whitespace-only cell counts as empty).
     ///
     /// # Arguments
     ///
@@ -89,24 +282,51 @@ impl CsvHandler {
     ///
     /// # Example
     ///
-    /// ```rust
+    /// ```ignore
     /// let passes = handler.row_passes_filters(&record);
     /// ```
     pub(crate) fn row_passes_filters(&self, record: &StringRecord) -> bool {
-        self.filter_idxs.par_iter().all(|(col_idx, valid_values)| {
-            // let val =
-            record
-                .get(*col_idx)
-                .is_some_and(|val| valid_values.contains(&val.to_string()))
-            // unwrap_or("");
-            // valid_values.contains(&val.to_string())
-        })
+        filters_pass(&self.filter_idxs, self.filter_logic, self.filter_min_match, |idx| record.get(idx))
+            && !self
+                .exclude_idxs
+                .iter()
+                .any(|(idx, filter)| record.get(*idx).is_some_and(|val| filter.matches(val)))
+            && self.non_empty_idxs.iter().all(|&idx| !is_blank(record.get(idx)))
+            && self.empty_idxs.iter().all(|&idx| is_blank(record.get(idx)))
+    }
+
+    /// Checks if a projected row (the output of `keep_columns`) passes the configured `post_filters`.
+    ///
+    /// This is the second filter stage, evaluated against the retained columns rather than the source record,
+    /// and combined using the same `filter_logic` as `row_passes_filters` (`filter_min_match` does not apply here).
+    ///
+    /// # Arguments
+    ///
+    /// * `row` - A slice containing the projected row.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - Returns `true` if the row passes the post filters, `false` otherwise.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let retained = handler.keep_columns(&record);
+    /// let passes = handler.row_passes_post_filters(&retained);
+    /// ```
+    pub(crate) fn row_passes_post_filters(&self, row: &[String]) -> bool {
+        filters_pass(&self.post_filter_idxs, self.filter_logic, None, |idx| row.get(idx).map(String::as_str))
+    }
+
+    /// Returns `true` when any `post_filters` are configured, which are only checked on projected rows (see `keep_columns`).
+    pub(crate) fn has_post_filters(&self) -> bool {
+        !self.post_filter_idxs.is_empty()
     }
 
     /// Retains the specified columns from a CSV record.
     ///
     /// This function creates a subset of the record containing only the columns specified
-    /// in the field indices.
+    /// in the field indices, normalizing any columns configured in `boolean_fields`, followed by the `computed` columns.
     ///
     /// # Arguments
     ///
@@ -118,15 +338,638 @@ impl CsvHandler {
     ///
     /// # Example
     ///
-    /// ```rust
+    /// ```ignore
     /// let columns = handler.keep_columns(&record);
     /// ```
     pub(crate) fn keep_columns(&self, record: &StringRecord) -> Vec<String> {
-        let mut row_subset = Vec::with_capacity(self.field_idxs.len());
-        for idx in &self.field_idxs {
-            let val = record.get(*idx).unwrap_or("").to_string();
+        let mut row_subset = Vec::with_capacity(self.field_idxs.len() + self.computed.len());
+        for (idx, boolean_field) in self.field_idxs.iter().zip(&self.boolean_fields) {
+            let val = record.get(*idx).unwrap_or("");
+            let val = match boolean_field {
+                Some(boolean_field) => boolean_field.normalize(val),
+                None => val.to_string(),
+            };
             row_subset.push(val);
         }
+        for parts in &self.computed {
+            row_subset.push(
+                parts
+                    .iter()
+                    .map(|part| match part {
+                        ComputedPart::Cell(idx) => record.get(*idx).unwrap_or(""),
+                        ComputedPart::Text(text) => text,
+                    })
+                    .collect(),
+            );
+        }
         row_subset
     }
 }
+
+/// A resolved term of a `computed` column's expression.
+///
+/// # Variants
+///
+/// * `Cell` - The value of the source column at this index.
+/// * `Text` - A string literal, or an empty string for a column that isn't in the source.
+enum ComputedPart {
+    Cell(usize),
+    Text(String),
+}
+
+/// Evaluates the given filters against a row, combining the results using `logic`,
+/// or by counting the matches against `min_match` when that is set.
+///
+/// `cell` fetches the value at a column index, which lets the same logic run over both
+/// source `StringRecord`s and projected rows. A row always passes when there are no filters.
+fn filters_pass<'r>(
+    filters: &[(usize, ColumnFilter)],
+    logic: FilterLogic,
+    min_match: Option<usize>,
+    cell: impl Fn(usize) -> Option<&'r str> + Sync,
+) -> bool {
+    if filters.is_empty() {
+        return true;
+    }
+
+    let passes = |(col_idx, filter): &(usize, ColumnFilter)| cell(*col_idx).is_some_and(|val| filter.matches(val));
+
+    if let Some(min_match) = min_match {
+        return filters.par_iter().filter(|filter| passes(filter)).count() >= min_match;
+    }
+
+    match logic {
+        FilterLogic::And => filters.par_iter().all(passes),
+        FilterLogic::Or => filters.par_iter().any(passes),
+    }
+}
+
+/// Whether a cell is missing, empty or only holds whitespace, for `require_non_empty` and `require_empty`.
+fn is_blank(cell: Option<&str>) -> bool {
+    cell.is_none_or(|val| val.trim().is_empty())
+}
+
+/// Resolves columns named like `fields` entries to their indices, adding those that aren't in the headers to `missing`.
+fn resolve_columns<'c>(
+    columns: &'c [String],
+    headers: &StringRecord,
+    config: &Config,
+    missing: &mut Vec<&'c str>,
+) -> Vec<usize> {
+    let mut idxs = Vec::with_capacity(columns.len());
+    for column in columns {
+        match resolve_field(column, headers, config) {
+            Some(idx) => idxs.push(idx),
+            None => missing.push(column),
+        }
+    }
+    idxs
+}
+
+/// Resolves a `fields` entry to the index of its column in the headers.
+///
+/// A header with the entry's name wins (ignoring case with `case_insensitive_headers`), otherwise an entry of `#N` or
+/// `$N` selects the column at index `N`.
+///
+/// # Arguments
+///
+/// * `field` - The `fields` entry.
+/// * `headers` - The headers of the source.
+/// * `config` - The configuration, for `case_insensitive_headers`.
+///
+/// # Returns
+///
+/// * `Option<usize>` - Returns the column index, or `None` when there is no such column (or the index is out of range).
+fn resolve_field(field: &str, headers: &StringRecord, config: &Config) -> Option<usize> {
+    headers
+        .iter()
+        .position(|col_name| config.header_matches(col_name, field))
+        .or_else(|| field_index(field).filter(|&idx| idx < headers.len()))
+}
+
+/// Looks up the entry of a config map keyed by column name (e.g. `include_cols_with`) for a source header.
+///
+/// A key with the exact name is preferred, otherwise one matching the header once normalized (and ignoring case with
+/// `case_insensitive_headers`) is found.
+fn column_entry<'m, V>(map: &'m HashMap<String, V>, header: &str, config: &Config) -> Option<&'m V> {
+    map.get(header).or_else(|| {
+        map.iter()
+            .find(|(name, _)| config.header_matches(header, name))
+            .map(|(_, value)| value)
+    })
+}
+
+/// Removes a UTF-8 byte order mark (`U+FEFF`) from the start of the first header, as left there by e.g. Excel's
+/// "CSV UTF-8" export, which would otherwise stop the first column from matching its name in `fields` and filters.
+///
+/// # Arguments
+///
+/// * `headers` - The headers of the source.
+///
+/// # Returns
+///
+/// * `StringRecord` - The headers without the byte order mark (a copy of `headers` if there was none).
+pub(crate) fn without_bom(headers: &StringRecord) -> StringRecord {
+    match headers.get(0).and_then(|first| first.strip_prefix('\u{feff}')) {
+        Some(first) => std::iter::once(first).chain(headers.iter().skip(1)).collect(),
+        None => headers.clone(),
+    }
+}
+
+/// Normalizes the headers of a source before they are matched against the config: the byte order mark is removed
+/// (see `without_bom`), then control characters and surrounding whitespace are removed from every header
+/// (see `normalize_header`), as exports with CRLF artifacts or padded names would otherwise not match `fields`.
+///
+/// # Arguments
+///
+/// * `headers` - The headers of the source.
+///
+/// # Returns
+///
+/// * `StringRecord` - The normalized headers.
+pub(crate) fn normalize_headers(headers: &StringRecord) -> StringRecord {
+    without_bom(headers).iter().map(normalize_header).collect()
+}
+
+/// Parses a `fields` entry selecting a column by its zero-based index, written as `#N` or `$N`.
+fn field_index(field: &str) -> Option<usize> {
+    field
+        .strip_prefix('#')
+        .or_else(|| field.strip_prefix('$'))
+        .and_then(|idx| idx.parse().ok())
+}
+
+#[cfg(test)]
+mod handler_tests {
+    use super::*;
+
+    const HEADERS: [&str; 4] = ["Id", "Status", "Description", "Amount"];
+
+    fn config_with(extra: &str) -> Config {
+        let json = format!(
+            r#"{{
+                "source": "data.csv",
+                "fields": ["Id", "Amount"],
+                "unique_fields": [],
+                "include_cols_with": {{}}
+                {extra}
+            }}"#
+        );
+        serde_json::from_str(&json).unwrap()
+    }
+
+    fn handler_for(config: &Config) -> CsvHandler {
+        let mut retained_data = RetainedData::default();
+        CsvHandler::new(config, &mut retained_data, &StringRecord::from(HEADERS.to_vec())).unwrap()
+    }
+
+    fn row(vals: [&str; 4]) -> StringRecord {
+        StringRecord::from(vals.to_vec())
+    }
+
+    #[test]
+    fn test_without_bom() {
+        let headers = StringRecord::from(vec!["\u{feff}Id", "Name"]);
+
+        assert_eq!(without_bom(&headers), StringRecord::from(vec!["Id", "Name"]));
+        assert_eq!(without_bom(&StringRecord::from(vec!["Id"])), StringRecord::from(vec!["Id"]));
+        assert_eq!(without_bom(&StringRecord::new()), StringRecord::new());
+    }
+
+    #[test]
+    fn test_normalize_headers() {
+        let headers = StringRecord::from(vec!["\u{feff} Id", "ClaimReason ", "Amount\r", "\tNotes\u{7}"]);
+
+        assert_eq!(
+            normalize_headers(&headers),
+            StringRecord::from(vec!["Id", "ClaimReason", "Amount", "Notes"])
+        );
+    }
+
+    #[test]
+    fn test_trailing_space_header_matches() {
+        let config = Config {
+            fields: vec!["Id".to_string(), "ClaimReason".to_string(), "Amount ".to_string()],
+            include_cols_with: HashMap::from([("ClaimReason".to_string(), vec!["Late".to_string()])]),
+            ..config_with("")
+        };
+        let mut retained_data = RetainedData::default();
+        let headers = StringRecord::from(vec!["Id", "ClaimReason ", "Amount\r"]);
+        let handler = CsvHandler::new(&config, &mut retained_data, &headers).unwrap();
+
+        // The output uses the normalized headers
+        assert_eq!(retained_data.retained_headers, vec!["Id", "ClaimReason", "Amount"]);
+        assert_eq!(retained_data.all_headers, vec!["Id", "ClaimReason", "Amount"]);
+        assert!(handler.row_passes_filters(&StringRecord::from(vec!["1", "Late", "5"])));
+        assert!(!handler.row_passes_filters(&StringRecord::from(vec!["2", "Early", "5"])));
+    }
+
+    #[test]
+    fn test_fields_by_index_and_name() {
+        let config = Config {
+            fields: vec!["$3".to_string(), "Id".to_string(), "#1".to_string(), "#0".to_string()],
+            ..config_with("")
+        };
+        let mut retained_data = RetainedData::default();
+        let handler = CsvHandler::new(&config, &mut retained_data, &StringRecord::from(HEADERS.to_vec())).unwrap();
+
+        // The order of `fields` is kept, and `#0` is the already listed `Id`
+        assert_eq!(retained_data.retained_headers, vec!["Amount", "Id", "Status"]);
+        assert_eq!(handler.keep_columns(&row(["1", "Active", "desc", "5"])), vec!["5", "1", "Active"]);
+    }
+
+    #[test]
+    fn test_missing_fields_are_ignored() {
+        let config = Config {
+            fields: vec!["Id".to_string(), "Missing".to_string(), "#9".to_string()],
+            ..config_with("")
+        };
+        let mut retained_data = RetainedData::default();
+        CsvHandler::new(&config, &mut retained_data, &StringRecord::from(HEADERS.to_vec())).unwrap();
+
+        assert_eq!(retained_data.retained_headers, vec!["Id"]);
+    }
+
+    #[test]
+    fn test_case_insensitive_headers() {
+        let mismatched = Config {
+            fields: vec!["id".to_string(), "AMOUNT".to_string()],
+            include_cols_with: HashMap::from([("status".to_string(), vec!["Active".to_string()])]),
+            unique_fields: vec!["ID".to_string()],
+            ..config_with("")
+        };
+        let config = Config {
+            case_insensitive_headers: true,
+            ..mismatched.clone()
+        };
+        let mut retained_data = RetainedData::default();
+        let handler = CsvHandler::new(&config, &mut retained_data, &StringRecord::from(HEADERS.to_vec())).unwrap();
+
+        // The source casing is kept for the output
+        assert_eq!(retained_data.retained_headers, vec!["Id", "Amount"]);
+        assert!(handler.row_passes_filters(&row(["1", "Active", "desc", "5"])));
+        assert!(!handler.row_passes_filters(&row(["2", "Inactive", "desc", "5"])));
+
+        // Without the flag, the mismatched names don't select anything
+        let config = Config {
+            unique_fields: vec![],
+            ..mismatched
+        };
+        let mut retained_data = RetainedData::default();
+        CsvHandler::new(&config, &mut retained_data, &StringRecord::from(HEADERS.to_vec())).unwrap();
+
+        assert!(retained_data.retained_headers.is_empty());
+    }
+
+    #[test]
+    fn test_strict_fields_lists_every_missing_field() {
+        let config = Config {
+            fields: vec!["Id".to_string(), "Missing".to_string(), "#9".to_string()],
+            include_cols_with: [("Status", "Active"), ("Gone", "x")]
+                .into_iter()
+                .map(|(col_name, value)| (col_name.to_string(), vec![value.to_string()]))
+                .collect(),
+            ..config_with(r#", "strict_fields": true"#)
+        };
+        let mut retained_data = RetainedData::default();
+
+        match CsvHandler::new(&config, &mut retained_data, &StringRecord::from(HEADERS.to_vec())) {
+            Err(Error::CsvHeaders(msg)) => assert!(msg.ends_with(r##""Missing", "#9", "Gone""##), "{msg}"),
+            other => panic!("expected a CsvHeaders error, got {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn test_unique_fields_must_be_retained() {
+        let headers = StringRecord::from(HEADERS.to_vec());
+        let mut retained_data = RetainedData::default();
+
+        let config = config_with(r#", "rename": { "Amount": "Total" }"#);
+        let config = Config {
+            unique_fields: vec!["Id".to_string(), "Total".to_string()],
+            ..config
+        };
+        assert!(CsvHandler::new(&config, &mut retained_data, &headers).is_ok());
+
+        let config = Config {
+            unique_fields: vec!["Amount".to_string(), "Id".to_string(), "Status".to_string()],
+            ..config
+        };
+        match CsvHandler::new(&config, &mut retained_data, &headers) {
+            Err(Error::CsvHeaders(msg)) => assert!(msg.ends_with(r#""Amount", "Status""#), "{msg}"),
+            other => panic!("expected a CsvHeaders error, got {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn test_fields_by_index_with_blank_or_duplicate_headers() {
+        let config = Config {
+            fields: vec!["#1".to_string()],
+            ..config_with("")
+        };
+        let mut retained_data = RetainedData::default();
+        let handler =
+            CsvHandler::new(&config, &mut retained_data, &StringRecord::from(vec!["Name", "Name", ""])).unwrap();
+
+        assert_eq!(handler.keep_columns(&StringRecord::from(vec!["first", "second", "third"])), vec!["second"]);
+    }
+
+    #[test]
+    fn test_no_filters_passes_everything() {
+        let handler = handler_for(&config_with(""));
+        assert!(handler.row_passes_filters(&row(["1", "Active", "anything", "5"])));
+    }
+
+    #[test]
+    fn test_include_cols_with_is_exact() {
+        let mut config = config_with("");
+        config
+            .include_cols_with
+            .insert("Status".to_string(), vec!["Active".to_string()]);
+        let handler = handler_for(&config);
+
+        assert!(handler.row_passes_filters(&row(["1", "Active", "", "5"])));
+        assert!(!handler.row_passes_filters(&row(["1", "Active2", "", "5"])));
+    }
+
+    #[test]
+    fn test_include_cols_with_case_insensitive() {
+        let mut config = config_with(r#", "case_insensitive_filters": true"#);
+        config
+            .include_cols_with
+            .insert("Status".to_string(), vec!["Active".to_string()]);
+        config
+            .exclude_cols_with
+            .insert("Description".to_string(), vec!["Spam".to_string()]);
+        let handler = handler_for(&config);
+
+        assert!(handler.row_passes_filters(&row(["1", "active", "", "5"])));
+        assert!(handler.row_passes_filters(&row(["1", "ACTIVE", "", "5"])));
+        assert!(!handler.row_passes_filters(&row(["1", "inactive", "", "5"])));
+        // Only `include_cols_with` is affected, exclusions stay case-sensitive
+        assert!(handler.row_passes_filters(&row(["1", "Active", "spam", "5"])));
+        assert!(!handler.row_passes_filters(&row(["1", "Active", "Spam", "5"])));
+    }
+
+    #[test]
+    fn test_date_filters() {
+        let handler = handler_for(&config_with(
+            r#", "date_format": "%d.%m.%Y", "date_filters": { "Description": { "from": "2024-02-01" } }"#,
+        ));
+
+        assert!(handler.row_passes_filters(&row(["1", "", "01.02.2024", ""])));
+        assert!(!handler.row_passes_filters(&row(["1", "", "31.01.2024", ""])));
+        assert!(!handler.row_passes_filters(&row(["1", "", "2024-02-01", ""])));
+
+        let config = config_with(r#", "date_filters": { "Description": { "to": "2024-13-01" } }"#);
+        let mut retained_data = RetainedData::default();
+        assert!(CsvHandler::new(&config, &mut retained_data, &StringRecord::from(HEADERS.to_vec())).is_err());
+    }
+
+    #[test]
+    fn test_numeric_filters() {
+        let handler = handler_for(&config_with(r#", "numeric_filters": { "Amount": [10, 100.5], "Id": [null, 5] }"#));
+
+        assert!(handler.row_passes_filters(&row(["1", "", "", "10"])));
+        assert!(handler.row_passes_filters(&row(["-3", "", "", "100.5"])));
+        assert!(!handler.row_passes_filters(&row(["1", "", "", "9.9"])));
+        assert!(!handler.row_passes_filters(&row(["6", "", "", "50"])));
+        // Cells that don't parse as numbers are rejected
+        assert!(!handler.row_passes_filters(&row(["1", "", "", "n/a"])));
+        assert!(!handler.row_passes_filters(&row(["1", "", "", ""])));
+    }
+
+    #[test]
+    fn test_filters_and_logic() {
+        let handler = handler_for(&config_with(
+            r#", "filters": [
+                { "column": "Description", "mode": "contains", "values": ["refund"] },
+                { "column": "Amount", "mode": "numeric_range", "values": ["10", "100"] }
+            ]"#,
+        ));
+
+        assert!(handler.row_passes_filters(&row(["1", "Active", "full refund", "50"])));
+        assert!(!handler.row_passes_filters(&row(["1", "Active", "full refund", "500"])));
+        assert!(!handler.row_passes_filters(&row(["1", "Active", "payment", "50"])));
+    }
+
+    #[test]
+    fn test_filters_or_logic() {
+        let handler = handler_for(&config_with(
+            r#", "filter_logic": "or", "filters": [
+                { "column": "Id", "mode": "prefix", "values": ["AB"] },
+                { "column": "Status", "mode": "regex", "values": ["^(?i)closed$"] }
+            ]"#,
+        ));
+
+        assert!(handler.row_passes_filters(&row(["AB1", "Active", "", ""])));
+        assert!(handler.row_passes_filters(&row(["XY1", "CLOSED", "", ""])));
+        assert!(!handler.row_passes_filters(&row(["XY1", "Active", "", ""])));
+    }
+
+    #[test]
+    fn test_filter_min_match() {
+        let filters = r#""filters": [
+                { "column": "Id", "mode": "prefix", "values": ["AB"] },
+                { "column": "Status", "mode": "exact", "values": ["Active"] },
+                { "column": "Description", "mode": "contains", "values": ["refund"] }
+            ]"#;
+        let rows = [
+            row(["XY1", "Closed", "payment", ""]),
+            row(["AB1", "Closed", "payment", ""]),
+            row(["AB1", "Active", "payment", ""]),
+            row(["AB1", "Active", "refund", ""]),
+        ];
+
+        for (min_match, expected) in [
+            (1, [false, true, true, true]),
+            (2, [false, false, true, true]),
+            (3, [false, false, false, true]),
+        ] {
+            // `filter_logic` is overridden by `filter_min_match`
+            let handler = handler_for(&config_with(&format!(
+                r#", "filter_logic": "or", "filter_min_match": {min_match}, {filters}"#
+            )));
+            let passes = rows.iter().map(|r| handler.row_passes_filters(r)).collect::<Vec<_>>();
+            assert_eq!(passes, expected, "filter_min_match = {min_match}");
+        }
+    }
+
+    #[test]
+    fn test_filter_min_match_above_filter_count() {
+        let handler = handler_for(&config_with(
+            r#", "filter_min_match": 2, "filters": [{ "column": "Id", "mode": "prefix", "values": ["AB"] }]"#,
+        ));
+        assert!(!handler.row_passes_filters(&row(["AB1", "", "", ""])));
+    }
+
+    #[test]
+    fn test_filters_combine_with_include_cols_with() {
+        let mut config = config_with(r#", "filters": [{ "column": "Status", "mode": "prefix", "values": ["Act"] }]"#);
+        config
+            .include_cols_with
+            .insert("Status".to_string(), vec!["Active".to_string()]);
+        let handler = handler_for(&config);
+
+        assert!(handler.row_passes_filters(&row(["1", "Active", "", ""])));
+        assert!(!handler.row_passes_filters(&row(["1", "Activated", "", ""])));
+    }
+
+    #[test]
+    fn test_filters_on_unknown_column_are_ignored() {
+        let handler =
+            handler_for(&config_with(r#", "filters": [{ "column": "Missing", "mode": "exact", "values": ["x"] }]"#));
+        assert!(handler.row_passes_filters(&row(["1", "Active", "", ""])));
+    }
+
+    #[test]
+    fn test_exclude_cols_with() {
+        let mut config = config_with("");
+        config
+            .exclude_cols_with
+            .insert("Status".to_string(), vec!["Closed".to_string(), "Deleted".to_string()]);
+        let handler = handler_for(&config);
+
+        assert!(handler.row_passes_filters(&row(["1", "Active", "", "5"])));
+        assert!(!handler.row_passes_filters(&row(["1", "Closed", "", "5"])));
+        assert!(!handler.row_passes_filters(&row(["1", "Deleted", "", "5"])));
+    }
+
+    #[test]
+    fn test_require_non_empty_and_empty() {
+        let handler = handler_for(&config_with(r##", "require_non_empty": ["Status"], "require_empty": ["#2"]"##));
+
+        // Neither column is retained, and whitespace-only cells count as empty
+        assert!(handler.row_passes_filters(&row(["1", "Active", "", "5"])));
+        assert!(handler.row_passes_filters(&row(["1", "Active", " \t", "5"])));
+        assert!(!handler.row_passes_filters(&row(["1", "  ", "", "5"])));
+        assert!(!handler.row_passes_filters(&row(["1", "", "", "5"])));
+        assert!(!handler.row_passes_filters(&row(["1", "Active", "note", "5"])));
+        // A short record is missing the cells, so they're empty
+        assert!(!handler.row_passes_filters(&StringRecord::from(vec!["1"])));
+    }
+
+    #[test]
+    fn test_exclude_cols_with_alongside_include() {
+        let mut config = config_with("");
+        config
+            .include_cols_with
+            .insert("Status".to_string(), vec!["Active".to_string(), "Pending".to_string()]);
+        config
+            .exclude_cols_with
+            .insert("Status".to_string(), vec!["Pending".to_string()]);
+        config
+            .exclude_cols_with
+            .insert("Id".to_string(), vec!["99".to_string()]);
+        let handler = handler_for(&config);
+
+        assert!(handler.row_passes_filters(&row(["1", "Active", "", ""])));
+        assert!(!handler.row_passes_filters(&row(["1", "Pending", "", ""])));
+        assert!(!handler.row_passes_filters(&row(["99", "Active", "", ""])));
+        assert!(!handler.row_passes_filters(&row(["1", "Closed", "", ""])));
+    }
+
+    #[test]
+    fn test_rename_retained_headers() {
+        let mut retained_data = RetainedData::default();
+        let config = config_with(
+            r#", "rename": { "Amount": "Total", "Description": "Notes" },
+                "filters": [{ "column": "Status", "mode": "exact", "values": ["Active"] }],
+                "boolean_fields": { "Amount": { "true_values": ["5"] } }"#,
+        );
+        let handler = CsvHandler::new(&config, &mut retained_data, &StringRecord::from(HEADERS.to_vec())).unwrap();
+
+        // "Description" isn't retained, so its rename is ignored
+        assert_eq!(retained_data.retained_headers, vec!["Id", "Total"]);
+        assert!(handler.row_passes_filters(&row(["1", "Active", "", "5"])));
+        assert_eq!(handler.keep_columns(&row(["1", "Active", "", "5"])), vec!["1", "true"]);
+    }
+
+    #[test]
+    fn test_post_filters_use_projected_columns() {
+        let mut retained_data = RetainedData::default();
+        let config =
+            config_with(r#", "post_filters": [{ "column": "Amount", "mode": "numeric_range", "values": ["10", ""] }]"#);
+        let handler = CsvHandler::new(&config, &mut retained_data, &StringRecord::from(HEADERS.to_vec())).unwrap();
+
+        // "Amount" is the second retained column, not the fourth source column
+        assert_eq!(retained_data.retained_headers, vec!["Id", "Amount"]);
+
+        let kept = handler.keep_columns(&row(["1", "Active", "", "25"]));
+        assert!(handler.row_passes_post_filters(&kept));
+
+        let dropped = handler.keep_columns(&row(["2", "Active", "", "5"]));
+        assert!(!handler.row_passes_post_filters(&dropped));
+    }
+
+    #[test]
+    fn test_post_filters_ignore_non_retained_columns() {
+        let handler = handler_for(&config_with(
+            r#", "post_filters": [{ "column": "Status", "mode": "exact", "values": ["x"] }]"#,
+        ));
+
+        // "Status" is a source column but not a retained one, so there is nothing to post-filter on
+        let kept = handler.keep_columns(&row(["1", "Active", "", "25"]));
+        assert!(handler.row_passes_post_filters(&kept));
+    }
+
+    #[test]
+    fn test_computed_columns() {
+        let mut retained_data = RetainedData::default();
+        let config = config_with(
+            r##", "computed": [
+                { "name": "Label", "expr": "Status + \": \" + Description" },
+                { "name": "Ref", "expr": "\"#\" + #0 + Missing" }
+            ],
+            "post_filters": [{ "column": "Label", "mode": "prefix", "values": ["Active"] }]"##,
+        );
+        let handler = CsvHandler::new(&config, &mut retained_data, &StringRecord::from(HEADERS.to_vec())).unwrap();
+
+        // Computed columns may reference source columns that aren't retained, a missing column is empty
+        assert_eq!(retained_data.retained_headers, vec!["Id", "Amount", "Label", "Ref"]);
+        let kept = handler.keep_columns(&row(["7", "Active", "paid", "5"]));
+        assert_eq!(kept, vec!["7", "5", "Active: paid", "#7"]);
+        assert!(handler.row_passes_post_filters(&kept));
+        assert!(!handler.row_passes_post_filters(&handler.keep_columns(&row(["8", "Closed", "", "5"]))));
+    }
+
+    #[test]
+    fn test_computed_column_errors() {
+        let mut retained_data = RetainedData::default();
+        let headers = StringRecord::from(HEADERS.to_vec());
+
+        let config = config_with(r#", "computed": [{ "name": "Bad", "expr": "Id +" }]"#);
+        assert!(matches!(CsvHandler::new(&config, &mut retained_data, &headers), Err(Error::InvalidConfig(_))));
+
+        let config = config_with(r#", "strict_fields": true, "computed": [{ "name": "Bad", "expr": "Missing" }]"#);
+        assert!(matches!(CsvHandler::new(&config, &mut retained_data, &headers), Err(Error::CsvHeaders(_))));
+    }
+
+    #[test]
+    fn test_boolean_fields_recognized_values() {
+        let handler = handler_for(&config_with(
+            r#", "boolean_fields": { "Amount": { "true_values": ["Y", "yes", "1"], "output_true": "T", "output_false": "F" } }"#,
+        ));
+
+        assert_eq!(handler.keep_columns(&row(["1", "", "", "Y"])), vec!["1", "T"]);
+        assert_eq!(handler.keep_columns(&row(["1", "", "", " YES "])), vec!["1", "T"]);
+        assert_eq!(handler.keep_columns(&row(["1", "", "", "1"])), vec!["1", "T"]);
+        assert_eq!(handler.keep_columns(&row(["1", "", "", "N"])), vec!["1", "F"]);
+    }
+
+    #[test]
+    fn test_boolean_fields_unrecognized_values() {
+        let handler = handler_for(&config_with(
+            r#", "boolean_fields": { "Amount": { "true_values": ["Y"], "false_values": ["N"] } }"#,
+        ));
+        // Without passthrough, anything that isn't a true value becomes false
+        assert_eq!(handler.keep_columns(&row(["1", "", "", "maybe"])), vec!["1", "false"]);
+
+        let handler = handler_for(&config_with(
+            r#", "boolean_fields": { "Amount": { "true_values": ["Y"], "false_values": ["N"], "passthrough_unrecognized": true } }"#,
+        ));
+        assert_eq!(handler.keep_columns(&row(["1", "", "", "Y"])), vec!["1", "true"]);
+        assert_eq!(handler.keep_columns(&row(["1", "", "", "n"])), vec!["1", "false"]);
+        assert_eq!(handler.keep_columns(&row(["1", "", "", "maybe"])), vec!["1", "maybe"]);
+    }
+}
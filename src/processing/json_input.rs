@@ -0,0 +1,105 @@
+use std::io::{Cursor, Read};
+
+use serde_json::{Map, Value};
+
+use crate::prelude::*;
+
+/// Converts a JSON array of objects into CSV, so it can be read like any other source.
+///
+/// The header row is the union of the objects' keys, in the order they're first seen. Every object becomes a row,
+/// with an empty cell for each key it doesn't have. Strings are written as-is, `null` as an empty cell, and
+/// numbers, booleans, arrays and objects as their (compact) JSON text.
+///
+/// # Arguments
+///
+/// * `input` - The JSON source.
+///
+/// # Returns
+///
+/// * `Result<Box<dyn Read>>` - The comma separated CSV, with a header row. Empty when the array is.
+///
+/// # Errors
+///
+/// This function will return an error if the input isn't a JSON array of objects.
+///
+/// # Example
+///
+/// ```ignore
+/// let input = json_to_csv(std::io::Cursor::new(r#"[{"Id": 1, "Name": "A"}, {"Id": 2}]"#))?;
+/// // Id,Name
+/// // 1,A
+/// // 2,
+/// ```
+pub(crate) fn json_to_csv(input: impl Read) -> Result<Box<dyn Read>> {
+    let objects: Vec<Map<String, Value>> = serde_json::from_reader(input)?;
+
+    let mut headers: Vec<&str> = Vec::new();
+    for key in objects.iter().flat_map(Map::keys) {
+        if !headers.contains(&key.as_str()) {
+            headers.push(key);
+        }
+    }
+    if headers.is_empty() {
+        return Ok(Box::new(Cursor::new(Vec::new())));
+    }
+
+    let mut wtr = csv::Writer::from_writer(Vec::new());
+    wtr.write_record(&headers)?;
+    for object in &objects {
+        wtr.write_record(headers.iter().map(|&key| object.get(key).map(cell).unwrap_or_default()))?;
+    }
+
+    let csv = wtr
+        .into_inner()
+        .map_err(|e| Error::CsvRead(format!("Failed to convert the JSON source to CSV: {e}")))?;
+    Ok(Box::new(Cursor::new(csv)))
+}
+
+/// The text of a single JSON value as a CSV cell.
+fn cell(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod json_input_tests {
+    use super::*;
+
+    fn convert(json: &str) -> String {
+        let mut csv = String::new();
+        json_to_csv(Cursor::new(json.to_string()))
+            .unwrap()
+            .read_to_string(&mut csv)
+            .unwrap();
+        csv
+    }
+
+    #[test]
+    fn test_json_to_csv_unions_keys() {
+        let csv = convert(
+            r#"[
+                {"Id": 1, "Name": "Alice", "Active": true},
+                {"Name": "Bob, Jr.", "Id": 2, "Tags": ["a"]},
+                {"Id": 3, "Name": null, "Extra": {"k": "v"}}
+            ]"#,
+        );
+
+        assert_eq!(
+            csv,
+            "Id,Name,Active,Tags,Extra\n\
+             1,Alice,true,,\n\
+             2,\"Bob, Jr.\",,\"[\"\"a\"\"]\",\n\
+             3,,,,\"{\"\"k\"\":\"\"v\"\"}\"\n"
+        );
+    }
+
+    #[test]
+    fn test_json_to_csv_empty_and_invalid() {
+        assert_eq!(convert("[]"), "");
+        assert!(json_to_csv(Cursor::new(r#"{"Id": 1}"#)).is_err());
+        assert!(json_to_csv(Cursor::new("[1, 2]")).is_err());
+    }
+}
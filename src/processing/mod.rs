@@ -1,7 +1,26 @@
+mod bloom;
+mod encoding;
+mod external;
+mod filter;
+mod gzip;
 mod handler;
+mod json_input;
 mod output;
 mod processor;
+mod running_total;
+mod stats;
+mod validate;
 
+pub use bloom::BloomFilter;
+pub use encoding::DecodeReader;
+pub(crate) use encoding::decode_source;
+pub use external::ExternalSorter;
 pub use handler::CsvHandler;
-pub use output::OutputData;
-pub use processor::CsvProcessor;
+pub(crate) use handler::normalize_headers;
+pub(crate) use json_input::json_to_csv;
+pub use output::{OutPathShape, OutputData, OutputSink, dated_output_path, finish_writer, resolve_output_path};
+pub(crate) use processor::composite_key;
+pub use processor::{CsvProcessor, DedupPreviewEntry};
+pub use running_total::RunningTotalColumn;
+pub use stats::{Stats, column_stats, stats_sidecar_path, write_column_stats, write_stats};
+pub use validate::{validate, write_validation_report};
@@ -1,6 +1,15 @@
-use std::path::PathBuf;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, TimeZone};
+use flate2::Compression;
+use flate2::write::GzEncoder;
 
 use crate::cli::OutputType;
+use crate::prelude::*;
+use crate::processing::gzip::is_gzip_path;
 
 /// Represents the output data configuration for the application.
 ///
@@ -10,16 +19,20 @@ use crate::cli::OutputType;
 ///
 /// * `output_type` - The type of output (e.g., stdout, CSV file).
 /// * `output_path` - The path to the output file.
+/// * `output_delimiter` - The `output_delimiter` setting, which replaces the delimiter of the output type.
+/// * `append` - Whether rows are appended to an existing output file rather than replacing it (see `OutputSink::append`).
 ///
 /// # Example
 ///
-/// ```rust
+/// ```ignore
 /// let output_data = OutputData::new(OutputType::Csv, PathBuf::from("output.csv"));
 /// ```
 #[derive(Debug)]
 pub struct OutputData {
     pub output_type: OutputType,
     pub output_path: PathBuf,
+    pub output_delimiter: Option<u8>,
+    pub append: bool,
 }
 
 impl OutputData {
@@ -36,13 +49,429 @@ impl OutputData {
     ///
     /// # Example
     ///
-    /// ```rust
+    /// ```ignore
     /// let output_data = OutputData::new(OutputType::Csv, PathBuf::from("output.csv"));
     /// ```
     pub fn new(output_type: OutputType, output_path: PathBuf) -> Self {
         Self {
             output_type,
             output_path,
+            output_delimiter: None,
+            append: false,
+        }
+    }
+
+    /// Sets the delimiter written with, instead of the one of the output type (see `delimiter`).
+    ///
+    /// # Arguments
+    ///
+    /// * `output_delimiter` - The delimiter to write with, `None` keeps the one of the output type.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - Returns the `OutputData` with the delimiter set.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let output_data = OutputData::new(OutputType::Csv, PathBuf::from("output.csv")).with_delimiter(Some(b';'));
+    /// ```
+    pub fn with_delimiter(mut self, output_delimiter: Option<u8>) -> Self {
+        self.output_delimiter = output_delimiter;
+        self
+    }
+
+    /// Sets whether rows are appended to an existing output file, rather than replacing it.
+    ///
+    /// # Arguments
+    ///
+    /// * `append` - Whether to append.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - Returns the `OutputData` with `append` set.
+    pub fn with_append(mut self, append: bool) -> Self {
+        self.append = append;
+        self
+    }
+
+    /// Creates a CSV writer for the configured output type, for writing rows as they are produced.
+    ///
+    /// File outputs have their parent directories created if they do not exist yet.
+    /// Rows are written with the `delimiter`. JSON and NDJSON output are not supported.
+    /// An `output_path` ending in `.gz` is gzip compressed, see `OutputSink::create`. With `append` the rows are added
+    /// to the end of an existing file instead, see `OutputSink::append`.
+    /// With `manual_quoting` the writer never quotes, leaving it to the caller (see `force_quote_fields`).
+    ///
+    /// # Arguments
+    ///
+    /// * `manual_quoting` - Whether the rows written are already quoted by hand.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<csv::Writer<OutputSink>>` - Returns the writer, or an `Error` if the output file cannot be created
+    ///   or the output type is JSON, NDJSON, a table, Markdown or HTML. Finish it with `finish_writer`.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let mut wtr = output_data.writer(false)?;
+    /// wtr.write_record(&["Header1", "Header2"])?;
+    /// ```
+    pub fn writer(&self, manual_quoting: bool) -> Result<csv::Writer<OutputSink>> {
+        let mut builder = csv::WriterBuilder::new();
+        builder.delimiter(self.delimiter());
+        if manual_quoting {
+            builder.quote_style(csv::QuoteStyle::Never);
+        }
+
+        let sink = match self.output_type {
+            OutputType::Json | OutputType::Ndjson | OutputType::Table | OutputType::Markdown | OutputType::Html => {
+                return Err(Error::Io(std::io::Error::new(
+                    std::io::ErrorKind::Unsupported,
+                    format!(
+                        "{} output isn't supported together with external, use csv or tsv output",
+                        self.output_type
+                    ),
+                )));
+            }
+            OutputType::Stdout => OutputSink::Stdout(std::io::stdout().lock()),
+            OutputType::Csv | OutputType::Tsv if self.append => OutputSink::append(&self.output_path)?,
+            OutputType::Csv | OutputType::Tsv => OutputSink::create(&self.output_path)?,
+        };
+
+        Ok(builder.from_writer(sink))
+    }
+
+    /// The field delimiter the output is written with.
+    ///
+    /// # Returns
+    ///
+    /// * `u8` - Returns the `output_delimiter` when set, otherwise a tab for TSV output and a comma for everything else.
+    pub fn delimiter(&self) -> u8 {
+        self.output_delimiter.unwrap_or(match self.output_type {
+            OutputType::Tsv => b'\t',
+            OutputType::Stdout
+            | OutputType::Csv
+            | OutputType::Json
+            | OutputType::Ndjson
+            | OutputType::Table
+            | OutputType::Markdown
+            | OutputType::Html => b',',
+        })
+    }
+}
+
+/// Where written output ends up: the standard output, a file, or a gzip compressed file.
+///
+/// # Variants
+///
+/// * `Stdout` - The standard output, for the `stdout` output type.
+/// * `File` - A plain output file.
+/// * `Gzip` - An output file ending in `.gz`, compressed as it is written.
+pub enum OutputSink {
+    Stdout(std::io::StdoutLock<'static>),
+    File(BufWriter<File>),
+    Gzip(GzEncoder<BufWriter<File>>),
+}
+
+impl OutputSink {
+    /// Creates (or truncates) the output file, creating its parent directories if they do not exist yet.
+    ///
+    /// A path ending in `.gz` (e.g. `output.csv.gz`) is gzip compressed.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the output file.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Self>` - Returns the sink, or an `Error` if the file cannot be created.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let mut sink = OutputSink::create(Path::new("output.csv.gz"))?;
+    /// sink.write_all(b"Id,Name\n")?;
+    /// sink.finish()?;
+    /// ```
+    pub fn create(path: &Path) -> Result<Self> {
+        Self::open(path, false)
+    }
+
+    /// Opens the output file to add to its end, creating it (and its parent directories) if it doesn't exist yet.
+    ///
+    /// A path ending in `.gz` has the data appended as a new gzip member, which gzip readers (including this one's
+    /// sources) read as part of one stream.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the output file.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Self>` - Returns the sink, or an `Error` if the file cannot be opened.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let mut sink = OutputSink::append(Path::new("daily.csv"))?;
+    /// sink.write_all(b"3,Carol\n")?;
+    /// sink.finish()?;
+    /// ```
+    pub fn append(path: &Path) -> Result<Self> {
+        Self::open(path, true)
+    }
+
+    /// Opens the output file either truncated (`create`) or to append to (`append`).
+    fn open(path: &Path, append: bool) -> Result<Self> {
+        if let Some(parent) = path.parent()
+            && !parent.as_os_str().is_empty()
+        {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let file = File::options()
+            .write(true)
+            .create(true)
+            .append(append)
+            .truncate(!append)
+            .open(path)?;
+        let file = BufWriter::new(file);
+        Ok(if is_gzip_path(path) {
+            Self::Gzip(GzEncoder::new(file, Compression::default()))
+        } else {
+            Self::File(file)
+        })
+    }
+
+    /// Flushes everything written, and for gzip output writes the trailer that completes the file.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<()>` - Returns `Ok(())` on success, or an `Error` if writing fails.
+    pub fn finish(self) -> Result<()> {
+        match self {
+            Self::Stdout(mut stdout) => stdout.flush()?,
+            Self::File(mut file) => file.flush()?,
+            Self::Gzip(encoder) => encoder.finish()?.flush()?,
+        }
+        Ok(())
+    }
+}
+
+impl Write for OutputSink {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            Self::Stdout(stdout) => stdout.write(buf),
+            Self::File(file) => file.write(buf),
+            Self::Gzip(encoder) => encoder.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            Self::Stdout(stdout) => stdout.flush(),
+            Self::File(file) => file.flush(),
+            Self::Gzip(encoder) => encoder.flush(),
+        }
+    }
+}
+
+/// Flushes a CSV writer and finishes its `OutputSink`, see `OutputSink::finish`.
+///
+/// # Arguments
+///
+/// * `wtr` - The writer to finish.
+///
+/// # Returns
+///
+/// * `Result<()>` - Returns `Ok(())` on success, or an `Error` if writing fails.
+pub fn finish_writer(wtr: csv::Writer<OutputSink>) -> Result<()> {
+    wtr.into_inner().map_err(|e| Error::Io(e.into_error()))?.finish()
+}
+
+/// Inserts a date-formatted subdirectory between the output file and its directory.
+///
+/// For example `output/report.csv` with the format `%Y-%m-%d` becomes `output/2025-01-15/report.csv`.
+/// The directory itself is created when the output is written.
+///
+/// # Arguments
+///
+/// * `output_path` - The configured output path.
+/// * `format` - A chrono `strftime` format for the subdirectory name.
+/// * `now` - The date and time to format.
+///
+/// # Returns
+///
+/// * `Result<PathBuf>` - Returns the output path inside the dated subdirectory, or an `Error` on failure.
+///
+/// # Errors
+///
+/// Returns `Error::DateFormat` if `format` is not a valid `strftime` format.
+///
+/// # Example
+///
+/// ```ignore
+/// let output_path = dated_output_path(Path::new("output/report.csv"), "%Y-%m-%d", &chrono::Local::now())?;
+/// ```
+pub fn dated_output_path<Tz>(output_path: &Path, format: &str, now: &DateTime<Tz>) -> Result<PathBuf>
+where
+    Tz: TimeZone,
+    Tz::Offset: std::fmt::Display,
+{
+    if StrftimeItems::new(format).any(|item| matches!(item, Item::Error)) {
+        return Err(Error::DateFormat(format.to_string()));
+    }
+
+    let subdir = now.format(format).to_string();
+    let file_name = output_path.file_name().unwrap_or_default();
+    Ok(output_path
+        .parent()
+        .unwrap_or(Path::new(""))
+        .join(subdir)
+        .join(file_name))
+}
+
+/// The shapes an `output_path` comes in, each resolved to an output file differently by `resolve_output_path`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutPathShape {
+    /// An existing directory, or a path ending with a separator (e.g. `out_dir/`).
+    Folder,
+    /// A file name with an extension (e.g. `out_dir/report.csv`).
+    File,
+    /// A file name without an extension (e.g. `out_dir/report`).
+    NoExtension,
+}
+
+impl OutPathShape {
+    /// Determines the shape of an `output_path`.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The configured output path.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - The shape of the path.
+    pub fn of(path: &Path) -> Self {
+        if path.is_dir() || path.to_string_lossy().ends_with(['/', '\\']) {
+            Self::Folder
+        } else if path.extension().is_some() {
+            Self::File
+        } else {
+            Self::NoExtension
+        }
+    }
+}
+
+/// Resolves the configured `output_path` to the file the output of a source is written to, by its `OutPathShape`:
+/// - `Folder` - a file in it named after the source with an `_out.csv` suffix, e.g. `data/claims.csv` is written to
+///   `out_dir/claims_out.csv`.
+/// - `File` - the path itself.
+/// - `NoExtension` - the path with a `.csv` extension.
+///
+/// Stdout (`-`) is kept as-is.
+///
+/// # Arguments
+///
+/// * `source` - The source the output is derived from.
+/// * `configured` - The configured output path.
+///
+/// # Returns
+///
+/// * `PathBuf` - The path of the output file.
+///
+/// # Example
+///
+/// ```ignore
+/// let output_path = resolve_output_path(Path::new("data/claims.csv"), Path::new("out_dir/"));
+/// assert_eq!(output_path, PathBuf::from("out_dir/claims_out.csv"));
+/// ```
+pub fn resolve_output_path(source: &Path, configured: &Path) -> PathBuf {
+    if configured.as_os_str() == STDIN_SOURCE {
+        return configured.to_path_buf();
+    }
+
+    match OutPathShape::of(configured) {
+        OutPathShape::Folder => {
+            let stem = source
+                .file_stem()
+                .map_or_else(|| "output".into(), |stem| stem.to_string_lossy());
+            configured.join(format!("{stem}_out.csv"))
         }
+        OutPathShape::File => configured.to_path_buf(),
+        OutPathShape::NoExtension => configured.with_extension("csv"),
+    }
+}
+
+#[cfg(test)]
+mod output_tests {
+    use chrono::Utc;
+    use tempfile::tempdir;
+
+    use super::*;
+    use crate::retained::RetainedData;
+
+    #[test]
+    fn test_dated_output_path_is_created_and_used() {
+        let dir = tempdir().unwrap();
+        let now = Utc.with_ymd_and_hms(2025, 1, 15, 9, 30, 0).unwrap();
+
+        let output_path = dated_output_path(&dir.path().join("report.csv"), "%Y-%m-%d", &now).unwrap();
+        assert_eq!(output_path, dir.path().join("2025-01-15").join("report.csv"));
+
+        let data = RetainedData {
+            retained_headers: vec!["Id".to_string()],
+            data: vec![vec!["1".to_string()]],
+            ..Default::default()
+        };
+        data.to_csv(&output_path).unwrap();
+
+        assert!(dir.path().join("2025-01-15").is_dir());
+        assert_eq!(std::fs::read_to_string(&output_path).unwrap(), "Id\n1\n");
+    }
+
+    #[test]
+    fn test_dated_output_path_invalid_format() {
+        let now = Utc.with_ymd_and_hms(2025, 1, 15, 9, 30, 0).unwrap();
+        assert!(matches!(dated_output_path(Path::new("report.csv"), "%Q", &now), Err(Error::DateFormat(_))));
+    }
+
+    #[test]
+    fn test_out_path_shape() {
+        let dir = tempdir().unwrap();
+
+        assert_eq!(OutPathShape::of(dir.path()), OutPathShape::Folder);
+        assert_eq!(OutPathShape::of(Path::new("out_dir/")), OutPathShape::Folder);
+        assert_eq!(OutPathShape::of(Path::new("out_dir\\")), OutPathShape::Folder);
+        assert_eq!(OutPathShape::of(&dir.path().join("report.csv")), OutPathShape::File);
+        assert_eq!(OutPathShape::of(&dir.path().join("report")), OutPathShape::NoExtension);
+    }
+
+    #[test]
+    fn test_resolve_output_path_folder() {
+        let dir = tempdir().unwrap();
+        let source = Path::new("data").join("claims.csv");
+
+        assert_eq!(resolve_output_path(&source, dir.path()), dir.path().join("claims_out.csv"));
+        assert_eq!(resolve_output_path(&source, Path::new("out_dir/")), Path::new("out_dir").join("claims_out.csv"));
+    }
+
+    #[test]
+    fn test_resolve_output_path_file() {
+        let dir = tempdir().unwrap();
+        let source = Path::new("data").join("claims.csv");
+
+        assert_eq!(resolve_output_path(&source, &dir.path().join("report.tsv")), dir.path().join("report.tsv"));
+        assert_eq!(resolve_output_path(&source, Path::new(STDIN_SOURCE)), PathBuf::from(STDIN_SOURCE));
+    }
+
+    #[test]
+    fn test_resolve_output_path_no_extension() {
+        let dir = tempdir().unwrap();
+        let source = Path::new("data").join("claims.csv");
+
+        assert_eq!(resolve_output_path(&source, &dir.path().join("report")), dir.path().join("report.csv"));
     }
 }
@@ -1,8 +1,31 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
+use std::hash::{DefaultHasher, Hash, Hasher};
 use std::pin::Pin;
 
-use crate::config::Config;
-use crate::retained::RetainedData;
+use crate::config::{AggOp, AggregateOp, Config, DedupKeep};
+use crate::prelude::*;
+use crate::processing::{ExternalSorter, RunningTotalColumn};
+use crate::retained::{RetainedData, compare_cells};
+
+/// A single line of the dedup preview report.
+///
+/// # Fields
+///
+/// * `key` - The values of the `unique_fields` that make up the key, in `unique_fields` order.
+/// * `before` - How many rows shared this key before deduplication.
+/// * `after` - How many rows will remain for this key after deduplication.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DedupPreviewEntry {
+    pub key: Vec<String>,
+    pub before: usize,
+    pub after: usize,
+}
+
+/// The name of the trailing column added by `CsvProcessor::delta`, holding `added`, `changed` or `removed`.
+pub(crate) const DELTA_COLUMN: &str = "_delta";
+
+/// The name of the trailing column added by `CsvProcessor::deduplicate` when `dedup_count` is set.
+pub(crate) const COUNT_COLUMN: &str = "_count";
 
 /// Represents the processor responsible for handling CSV data processing.
 ///
@@ -15,7 +38,7 @@ use crate::retained::RetainedData;
 ///
 /// # Example
 ///
-/// ```rust
+/// ```ignore
 /// let config = Config::new(cli).expect("Failed to create config");
 /// let processor = Processor::new(&config);
 /// ```
@@ -36,7 +59,7 @@ impl CsvProcessor {
     ///
     /// # Example
     ///
-    /// ```rust
+    /// ```ignore
     /// let config = Config::new(cli).expect("Failed to create config");
     /// let processor = Processor::new(&config);
     /// ```
@@ -48,38 +71,1048 @@ impl CsvProcessor {
 
     /// Deduplicates the retained data based on the unique fields specified in the configuration.
     ///
-    /// This function removes duplicate entries from the `retained_data` by retaining only unique values
-    /// for the specified fields.
+    /// This function removes duplicate entries from the `retained_data`, keyed on the combination of all
+    /// `unique_fields` values of a row (or on every retained column with `dedup_all`, see `dedup_key_idxs`).
+    /// Depending on `dedup_keep`, either the first or the last row of each key is retained; surviving rows keep
+    /// their original order.
+    /// When `dedup_count` is set, a trailing `_count` column records how many rows shared each surviving row's key.
+    /// The `dedup_aggregate` columns of each surviving row are replaced by the aggregate over every row sharing its key.
     ///
     /// # Arguments
     ///
     /// * `retained_data` - A mutable reference to `RetainedData` to deduplicate the data.
     ///
+    /// # Returns
+    ///
+    /// * `Result<usize>` - The number of rows removed as duplicates, or an `Error` on failure.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::CsvHeaders` if a `unique_fields` or `dedup_aggregate` column is not among the retained headers
+    /// (or an aggregated column is one of the `unique_fields`). The data is left untouched on error.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let removed = processor.deduplicate(&mut retained_data)?;
+    /// ```
+    pub(crate) fn deduplicate(&mut self, retained_data: &mut RetainedData) -> Result<usize> {
+        let idxs = self.dedup_key_idxs(retained_data)?;
+        if idxs.is_empty() {
+            return Ok(0);
+        }
+        let rows_before = retained_data.data.len();
+
+        let aggregate_idxs = self.dedup_aggregate_idxs(retained_data)?;
+        let aggregates = (!aggregate_idxs.is_empty()).then(|| dedup_aggregates(retained_data, &idxs, &aggregate_idxs));
+
+        let counts = self.config.dedup_count.then(|| {
+            let mut counts: HashMap<Vec<String>, usize> = HashMap::new();
+            for row in &retained_data.data {
+                *counts.entry(composite_key(row, &idxs)).or_default() += 1;
+            }
+            counts
+        });
+
+        match self.config.dedup_keep {
+            DedupKeep::First => {
+                let mut seen = HashSet::new();
+                retained_data.data.retain(|row| seen.insert(composite_key(row, &idxs)));
+            }
+            DedupKeep::Last => {
+                let mut last_seen = HashMap::new();
+                for (row_idx, row) in retained_data.data.iter().enumerate() {
+                    last_seen.insert(composite_key(row, &idxs), row_idx);
+                }
+                let keep = last_seen.into_values().collect::<HashSet<_>>();
+
+                let mut row_idx = 0;
+                retained_data.data.retain(|_| {
+                    row_idx += 1;
+                    keep.contains(&(row_idx - 1))
+                });
+            }
+        }
+
+        if let Some(aggregates) = aggregates {
+            for row in &mut retained_data.data {
+                for (&(col_idx, _), value) in aggregate_idxs.iter().zip(&aggregates[&composite_key(row, &idxs)]) {
+                    if let Some(value) = value {
+                        row[col_idx].clone_from(value);
+                    }
+                }
+            }
+        }
+
+        if let Some(counts) = counts {
+            let count_values = retained_data
+                .data
+                .iter()
+                .map(|row| counts[&composite_key(row, &idxs)].to_string())
+                .collect();
+            retained_data.push_column(COUNT_COLUMN, count_values);
+        }
+
+        Ok(rows_before - retained_data.data.len())
+    }
+
+    /// Resolves the index of every `unique_fields` entry within the retained headers.
+    ///
+    /// # Arguments
+    ///
+    /// * `retained_data` - A reference to the `RetainedData` whose headers are searched.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Vec<usize>>` - Returns the indices in `unique_fields` order, or an `Error` on failure.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::CsvHeaders` if a unique field is not among the retained headers.
+    pub(crate) fn unique_field_idxs(&self, retained_data: &RetainedData) -> Result<Vec<usize>> {
+        self.config
+            .unique_fields
+            .iter()
+            .map(|field| {
+                retained_data
+                    .retained_headers
+                    .iter()
+                    .position(|x| self.config.header_matches(x, field))
+                    .ok_or_else(|| Error::CsvHeaders(format!("Unique field is not a retained field: {field}")))
+            })
+            .collect()
+    }
+
+    /// Resolves the indices of the columns rows are deduplicated on: the `unique_fields`, or every retained column
+    /// when there are none and `dedup_all` is set.
+    ///
+    /// # Arguments
+    ///
+    /// * `retained_data` - A reference to the `RetainedData` whose headers are searched.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Vec<usize>>` - Returns the indices, empty when nothing is to be deduplicated, or an `Error` on failure.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::CsvHeaders` if a unique field is not among the retained headers.
+    pub(crate) fn dedup_key_idxs(&self, retained_data: &RetainedData) -> Result<Vec<usize>> {
+        if self.config.unique_fields.is_empty() && self.config.dedup_all {
+            return Ok((0..retained_data.retained_headers.len()).collect());
+        }
+        self.unique_field_idxs(retained_data)
+    }
+
+    /// Resolves the index of every `dedup_aggregate` column within the retained headers, paired with its `AggOp`.
+    ///
+    /// # Arguments
+    ///
+    /// * `retained_data` - A reference to the `RetainedData` whose headers are searched.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Vec<(usize, AggOp)>>` - Returns the indices in column order, or an `Error` on failure.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::CsvHeaders` if an aggregated column or a unique field is not among the retained headers,
+    /// or an aggregated column is itself one of the `unique_fields`.
+    pub(crate) fn dedup_aggregate_idxs(&self, retained_data: &RetainedData) -> Result<Vec<(usize, AggOp)>> {
+        let unique_idxs = self.unique_field_idxs(retained_data)?;
+        let mut idxs = self
+            .config
+            .dedup_aggregate
+            .iter()
+            .map(|(column, &op)| {
+                let idx = retained_data
+                    .retained_headers
+                    .iter()
+                    .position(|header| self.config.header_matches(header, column))
+                    .ok_or_else(|| Error::CsvHeaders(format!("Aggregated column is not a retained field: {column}")))?;
+                if unique_idxs.contains(&idx) {
+                    return Err(Error::CsvHeaders(format!(
+                        "Aggregated column is one of the unique_fields, so it can't differ between duplicates: {column}"
+                    )));
+                }
+                Ok((idx, op))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        idxs.sort_unstable_by_key(|&(idx, _)| idx);
+        Ok(idxs)
+    }
+
+    /// Computes how many rows share each composite `unique_fields` key, without modifying the data.
+    ///
+    /// Only keys that occur more than once are reported, ordered from most to least duplicated
+    /// (ties are ordered by key), and truncated to `top_n` entries.
+    ///
+    /// # Arguments
+    ///
+    /// * `retained_data` - A reference to the `RetainedData` to inspect.
+    /// * `top_n` - The maximum number of keys to report.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Vec<DedupPreviewEntry>>` - Returns the report entries, or an `Error` on failure.
+    ///
     /// # Example
     ///
-    /// ```rust
-    /// processor.deduplicate(&mut retained_data);
+    /// ```ignore
+    /// let preview = processor.dedup_preview(&retained_data, 10)?;
     /// ```
-    pub(crate) fn deduplicate(&mut self, retained_data: &mut RetainedData) {
-        // use rayon::prelude::*;
-        let mut seen = HashSet::new();
+    pub(crate) fn dedup_preview(&self, retained_data: &RetainedData, top_n: usize) -> Result<Vec<DedupPreviewEntry>> {
+        let idxs = self.dedup_key_idxs(retained_data)?;
 
-        for field in &self.config.as_ref().unique_fields {
-            let field_idx_in_existing = retained_data
+        let mut counts: HashMap<Vec<String>, usize> = HashMap::new();
+        for row in &retained_data.data {
+            *counts.entry(composite_key(row, &idxs)).or_default() += 1;
+        }
+
+        let mut preview = counts
+            .into_iter()
+            .filter(|(_, before)| *before > 1)
+            .map(|(key, before)| DedupPreviewEntry { key, before, after: 1 })
+            .collect::<Vec<_>>();
+
+        preview.sort_by(|a, b| b.before.cmp(&a.before).then_with(|| a.key.cmp(&b.key)));
+        preview.truncate(top_n);
+
+        Ok(preview)
+    }
+
+    /// Rolls the retained data up into one row per `group_by` key, if an `aggregate` is configured.
+    ///
+    /// Each group becomes a single row, in the order the groups are first seen, holding its `group_by` values followed by
+    /// the aggregate of `column` over the group's rows - so the headers become the `group_by` columns plus the aggregate
+    /// column (see `Aggregate::header`). Values the numeric ops can't parse are skipped, with one warning counting them.
+    /// Force quoted columns stay quoted if they are `group_by` columns.
+    ///
+    /// # Arguments
+    ///
+    /// * `retained_data` - A mutable reference to the `RetainedData`, replaced by the grouped rows.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<()>` - Returns `Ok(())` on success, or an `Error` on failure.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::CsvHeaders` if a `group_by` column or the aggregated `column` is not among the retained headers.
+    /// The data is left untouched on error.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// processor.deduplicate(&mut retained_data)?;
+    /// processor.aggregate(&mut retained_data)?;
+    /// ```
+    pub(crate) fn aggregate(&self, retained_data: &mut RetainedData) -> Result<()> {
+        let Some(aggregate) = &self.config.aggregate else {
+            return Ok(());
+        };
+
+        let position = |column: &str| {
+            retained_data
                 .retained_headers
                 .iter()
-                .position(|x| x == field)
-                .unwrap_or_else(|| {
-                    panic!(
-                        "{}",
-                        format!("Csv file headers are missing fields or are unevenly distributed. Failed on: {field}")
-                    );
-                });
+                .position(|header| self.config.header_matches(header, column))
+                .ok_or_else(|| Error::CsvHeaders(format!("Aggregate column is not a retained field: {column}")))
+        };
+        let group_idxs = aggregate
+            .group_by
+            .iter()
+            .map(|column| position(column))
+            .collect::<Result<Vec<_>>>()?;
+        let value_idx = position(&aggregate.column)?;
+
+        let mut groups: Vec<(Vec<String>, Vec<&str>)> = Vec::new();
+        let mut group_idx_of: HashMap<Vec<String>, usize> = HashMap::new();
+        for row in &retained_data.data {
+            let key = composite_key(row, &group_idxs);
+            let group_idx = *group_idx_of.entry(key.clone()).or_insert_with(|| {
+                groups.push((key, Vec::new()));
+                groups.len() - 1
+            });
+            groups[group_idx].1.push(row.get(value_idx).map_or("", String::as_str));
+        }
+
+        let mut skipped = 0;
+        let data = groups
+            .into_iter()
+            .map(|(mut row, values)| {
+                row.push(aggregate_values(aggregate.op, &values, &mut skipped));
+                row
+            })
+            .collect::<Vec<_>>();
+        if skipped > 0 {
+            warn!("Skipped {skipped} values of {:?} that aren't numbers while aggregating", aggregate.column);
+        }
+
+        let mut headers = aggregate.group_by.clone();
+        headers.push(aggregate.header());
+        retained_data.force_quoted = retained_data
+            .force_quoted
+            .iter()
+            .filter_map(|&idx| group_idxs.iter().position(|&group_idx| group_idx == idx))
+            .collect();
+        retained_data.retained_headers = headers;
+        retained_data.data = data;
+        Ok(())
+    }
+
+    /// Appends the configured `running_total` column to every row, accumulating in the current row order.
+    ///
+    /// This should be called once the rows are in their final order (after deduplication), and does nothing
+    /// when no `running_total` is configured.
+    ///
+    /// # Arguments
+    ///
+    /// * `retained_data` - A mutable reference to the `RetainedData` to extend with the new column.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<()>` - Returns `Ok(())` on success, or an `Error` on failure.
+    ///
+    /// # Errors
+    ///
+    /// This function can return errors if the `source` column is not retained, or a value is not numeric
+    /// while `strict_numeric` is set. The data is left untouched on error.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// processor.running_total(&mut retained_data)?;
+    /// ```
+    pub(crate) fn running_total(&self, retained_data: &mut RetainedData) -> Result<()> {
+        let Some(running_total) = &self.config.running_total else {
+            return Ok(());
+        };
+
+        let mut column = RunningTotalColumn::new(running_total, &retained_data.retained_headers)?;
+        let totals = retained_data
+            .data
+            .iter()
+            .map(|row| column.next(row))
+            .collect::<Result<Vec<_>>>()?;
+
+        retained_data.push_column(running_total.into.clone(), totals);
+
+        Ok(())
+    }
+
+    /// Reduces the retained data to the rows that are new or changed compared to a previous output.
+    ///
+    /// Rows are matched on the composite `unique_fields` key (or on every column when there are none),
+    /// and compared by a hash of their retained values. The previous columns are matched by header name,
+    /// so column order may differ and extra previous columns are ignored. Each surviving row gets a trailing
+    /// `_delta` column of `added` or `changed`; unchanged rows are dropped. When `delta_report_removed` is set,
+    /// keys only present in the previous output are appended with a `removed` tag.
+    ///
+    /// # Arguments
+    ///
+    /// * `retained_data` - A mutable reference to the current `RetainedData`, reduced in place.
+    /// * `previous` - The previous output, as read by `RetainedData::from_output_file`.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<()>` - Returns `Ok(())` on success, or an `Error` on failure.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::CsvHeaders` if a retained column is missing from the previous output.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let previous = RetainedData::from_output_file("last_run.csv")?;
+    /// processor.delta(&mut retained_data, &previous)?;
+    /// ```
+    pub(crate) fn delta(&self, retained_data: &mut RetainedData, previous: &RetainedData) -> Result<()> {
+        // Index of each current column within the previous output
+        let previous_idxs = retained_data
+            .retained_headers
+            .iter()
+            .map(|header| {
+                previous
+                    .retained_headers
+                    .iter()
+                    .position(|x| x == header)
+                    .ok_or_else(|| Error::CsvHeaders(format!("Column is missing from the previous output: {header}")))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut key_idxs = self.unique_field_idxs(retained_data)?;
+        if key_idxs.is_empty() {
+            key_idxs = (0..retained_data.retained_headers.len()).collect();
+        }
+
+        let mut previous_rows = previous
+            .data
+            .iter()
+            .map(|row| {
+                let aligned = composite_key(row, &previous_idxs);
+                (composite_key(&aligned, &key_idxs), (row_hash(&aligned), aligned))
+            })
+            .collect::<HashMap<_, _>>();
+
+        let mut delta = Vec::new();
+        for mut row in retained_data.data.drain(..) {
+            let tag = match previous_rows.remove(&composite_key(&row, &key_idxs)) {
+                None => "added",
+                Some((hash, _)) if hash != row_hash(&row) => "changed",
+                Some(_) => continue,
+            };
+            row.push(tag.to_string());
+            delta.push(row);
+        }
+
+        if self.config.delta_report_removed {
+            // Keep the previous file's order, so the output is deterministic
+            for row in &previous.data {
+                let aligned = composite_key(row, &previous_idxs);
+                if let Some((_, mut removed)) = previous_rows.remove(&composite_key(&aligned, &key_idxs)) {
+                    removed.push("removed".to_string());
+                    delta.push(removed);
+                }
+            }
+        }
+
+        retained_data.data = delta;
+        retained_data.retained_headers.push(DELTA_COLUMN.to_string());
+        Ok(())
+    }
 
-            retained_data.data.retain(|row| {
-                let val = row[field_idx_in_existing].clone();
-                seen.insert(val.clone())
+    /// Checks the number of output rows against the configured `expect_rows` and `expect_rows_range`.
+    ///
+    /// # Arguments
+    ///
+    /// * `actual` - The number of rows that are (or were) output, excluding the header.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<()>` - Returns `Ok(())` if every configured expectation holds, or an `Error` otherwise.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::RowCountMismatch` stating the expected and actual row counts.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// processor.check_row_count(retained_data.data.len())?;
+    /// ```
+    pub(crate) fn check_row_count(&self, actual: usize) -> Result<()> {
+        if let Some(expected) = self.config.expect_rows
+            && actual != expected
+        {
+            return Err(Error::RowCountMismatch {
+                expected: expected.to_string(),
+                actual,
             });
         }
+
+        if let Some((min, max)) = self.config.expect_rows_range
+            && !(min..=max).contains(&actual)
+        {
+            return Err(Error::RowCountMismatch {
+                expected: format!("between {min} and {max}"),
+                actual,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Creates an `ExternalSorter` that sorts and deduplicates on the `unique_fields`.
+    ///
+    /// When no unique fields are configured, rows are sorted on every retained column, and nothing is dropped
+    /// unless `dedup_all` is set.
+    ///
+    /// # Arguments
+    ///
+    /// * `retained_data` - A reference to the `RetainedData` holding the retained headers.
+    /// * `budget_bytes` - The approximate memory budget before rows are spilled to disk.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<ExternalSorter>` - Returns the sorter, or an `Error` on failure.
+    ///
+    /// # Errors
+    ///
+    /// This function can return errors if a unique field is not retained, or the temporary directory cannot be created.
+    pub(crate) fn external_sorter(&self, retained_data: &RetainedData, budget_bytes: usize) -> Result<ExternalSorter> {
+        let key_idxs = self.dedup_key_idxs(retained_data)?;
+        if key_idxs.is_empty() {
+            return ExternalSorter::new((0..retained_data.retained_headers.len()).collect(), None, budget_bytes);
+        }
+        ExternalSorter::new(key_idxs, Some(self.config.dedup_keep), budget_bytes)
+    }
+}
+
+/// Builds the composite dedup key for a row from the values at the given indices.
+pub(crate) fn composite_key(row: &[String], idxs: &[usize]) -> Vec<String> {
+    idxs.iter()
+        .map(|&idx| row.get(idx).cloned().unwrap_or_default())
+        .collect()
+}
+
+/// Hashes the values of a row, for cheap comparison against a previous version of it.
+pub(crate) fn row_hash(row: &[String]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    row.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Aggregates the `dedup_aggregate` columns over every row sharing a `unique_fields` key.
+///
+/// # Arguments
+///
+/// * `retained_data` - The data to aggregate, before any duplicates are removed.
+/// * `key_idxs` - The indices of the `unique_fields` columns.
+/// * `aggregate_idxs` - The aggregated columns and their `AggOp`, see `CsvProcessor::dedup_aggregate_idxs`.
+///
+/// # Returns
+///
+/// * `HashMap<Vec<String>, Vec<Option<String>>>` - Returns per key the aggregate of each column, in `aggregate_idxs` order.
+///   An aggregate is `None` when there was nothing to aggregate (e.g. `sum` without a single number), keeping the row's own value.
+fn dedup_aggregates(
+    retained_data: &RetainedData,
+    key_idxs: &[usize],
+    aggregate_idxs: &[(usize, AggOp)],
+) -> HashMap<Vec<String>, Vec<Option<String>>> {
+    let mut groups: HashMap<Vec<String>, Vec<&Vec<String>>> = HashMap::new();
+    for row in &retained_data.data {
+        groups.entry(composite_key(row, key_idxs)).or_default().push(row);
+    }
+
+    groups
+        .into_iter()
+        .map(|(key, rows)| {
+            let aggregates = aggregate_idxs
+                .iter()
+                .map(|&(col_idx, op)| {
+                    let mut values = rows.iter().filter_map(|row| row.get(col_idx));
+                    let non_empty = values.clone().filter(|value| !value.trim().is_empty());
+                    match op {
+                        AggOp::Sum => {
+                            let numbers = values
+                                .filter_map(|value| value.trim().parse::<f64>().ok())
+                                .collect::<Vec<_>>();
+                            (!numbers.is_empty()).then(|| numbers.iter().sum::<f64>().to_string())
+                        }
+                        AggOp::Max => non_empty.max_by(|a, b| compare_cells(Some(a), Some(b))).cloned(),
+                        AggOp::Min => non_empty.min_by(|a, b| compare_cells(Some(a), Some(b))).cloned(),
+                        AggOp::First => values.next().cloned(),
+                        AggOp::Last => values.next_back().cloned(),
+                        AggOp::Concat => Some(
+                            non_empty
+                                .map(String::as_str)
+                                .collect::<Vec<_>>()
+                                .join(DEDUP_CONCAT_SEPARATOR),
+                        ),
+                    }
+                })
+                .collect();
+            (key, aggregates)
+        })
+        .collect()
+}
+
+/// Combines the values of one `aggregate` group with `op`, counting the values skipped as they aren't numbers.
+///
+/// Empty values are ignored by every op. A group without a single number gets an empty cell from the numeric ops.
+fn aggregate_values(op: AggregateOp, values: &[&str], skipped: &mut usize) -> String {
+    let non_empty = values
+        .iter()
+        .map(|value| value.trim())
+        .filter(|value| !value.is_empty());
+    if op == AggregateOp::Count {
+        return non_empty.count().to_string();
+    }
+
+    let numbers = non_empty
+        .filter_map(|value| {
+            let number = value.parse::<f64>().ok();
+            *skipped += usize::from(number.is_none());
+            number
+        })
+        .collect::<Vec<_>>();
+    if numbers.is_empty() {
+        return String::new();
+    }
+
+    let result = match op {
+        AggregateOp::Sum => numbers.iter().sum(),
+        AggregateOp::Avg => numbers.iter().sum::<f64>() / numbers.len() as f64,
+        AggregateOp::Min => numbers.iter().copied().fold(f64::INFINITY, f64::min),
+        AggregateOp::Max => numbers.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+        AggregateOp::Count => unreachable!("counted above"),
+    };
+    result.to_string()
+}
+
+#[cfg(test)]
+mod processor_tests {
+    use super::*;
+    use crate::config::{Aggregate, RunningTotal};
+
+    fn processor_with_unique(unique_fields: &[&str]) -> CsvProcessor {
+        let config = Config {
+            unique_fields: unique_fields.iter().map(ToString::to_string).collect(),
+            ..Default::default()
+        };
+        CsvProcessor::new(&config)
+    }
+
+    fn retained(headers: &[&str], rows: &[&[&str]]) -> RetainedData {
+        RetainedData {
+            retained_headers: headers.iter().map(ToString::to_string).collect(),
+            data: rows
+                .iter()
+                .map(|row| row.iter().map(ToString::to_string).collect())
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_dedup_preview_lists_most_duplicated_keys() {
+        let processor = processor_with_unique(&["Id", "Kind"]);
+        let data = retained(
+            &["Id", "Kind", "Value"],
+            &[
+                &["1", "a", "x"],
+                &["2", "a", "x"],
+                &["1", "a", "y"],
+                &["3", "b", "x"],
+                &["1", "a", "z"],
+                &["2", "a", "z"],
+                &["1", "b", "z"],
+            ],
+        );
+
+        let preview = processor.dedup_preview(&data, 5).unwrap();
+
+        assert_eq!(
+            preview,
+            vec![
+                DedupPreviewEntry {
+                    key: vec!["1".to_string(), "a".to_string()],
+                    before: 3,
+                    after: 1,
+                },
+                DedupPreviewEntry {
+                    key: vec!["2".to_string(), "a".to_string()],
+                    before: 2,
+                    after: 1,
+                },
+            ]
+        );
+        // The preview must not touch the data itself
+        assert_eq!(data.data.len(), 7);
+    }
+
+    #[test]
+    fn test_dedup_preview_truncates_to_top_n() {
+        let processor = processor_with_unique(&["Id"]);
+        let data = retained(&["Id"], &[&["1"], &["1"], &["1"], &["2"], &["2"], &["3"], &["3"]]);
+
+        let preview = processor.dedup_preview(&data, 1).unwrap();
+
+        assert_eq!(preview.len(), 1);
+        assert_eq!(preview[0].key, vec!["1".to_string()]);
+        assert_eq!(preview[0].before, 3);
+    }
+
+    fn processor_with_aggregate(group_by: &[&str], op: AggregateOp, into: Option<&str>) -> CsvProcessor {
+        let config = Config {
+            aggregate: Some(Aggregate {
+                group_by: group_by.iter().map(ToString::to_string).collect(),
+                column: "Amount".to_string(),
+                op,
+                into: into.map(ToString::to_string),
+            }),
+            ..Default::default()
+        };
+        CsvProcessor::new(&config)
+    }
+
+    fn sales() -> RetainedData {
+        retained(
+            &["Id", "Region", "Status", "Amount"],
+            &[
+                &["1", "North", "Open", "10"],
+                &["2", "South", "Open", "5.5"],
+                &["3", "North", "Closed", "n/a"],
+                &["4", "North", "Open", "20"],
+                &["5", "South", "Open", ""],
+                &["6", "East", "Open", "x"],
+            ],
+        )
+    }
+
+    #[test]
+    fn test_aggregate_ops() {
+        for (op, expected) in [
+            (AggregateOp::Sum, ["30", "5.5", ""]),
+            (AggregateOp::Count, ["3", "1", "1"]),
+            (AggregateOp::Avg, ["15", "5.5", ""]),
+            (AggregateOp::Min, ["10", "5.5", ""]),
+            (AggregateOp::Max, ["20", "5.5", ""]),
+        ] {
+            let mut data = sales();
+            processor_with_aggregate(&["Region"], op, None)
+                .aggregate(&mut data)
+                .unwrap();
+
+            // Groups keep the order they're first seen in, non-numeric and empty values are skipped
+            assert_eq!(data.retained_headers, vec!["Region".to_string(), format!("{}_Amount", op.name())]);
+            let rows = data
+                .data
+                .iter()
+                .map(|row| (row[0].as_str(), row[1].as_str()))
+                .collect::<Vec<_>>();
+            assert_eq!(rows, vec![("North", expected[0]), ("South", expected[1]), ("East", expected[2])], "{op:?}");
+        }
+    }
+
+    #[test]
+    fn test_aggregate_multiple_and_no_group_columns() {
+        let mut data = sales();
+        data.force_quote(&["Status".to_string(), "Amount".to_string()], &Config::default())
+            .unwrap();
+        processor_with_aggregate(&["Status", "Region"], AggregateOp::Count, Some("Rows"))
+            .aggregate(&mut data)
+            .unwrap();
+
+        assert_eq!(data.retained_headers, vec!["Status", "Region", "Rows"]);
+        assert_eq!(
+            data.data,
+            vec![
+                vec!["Open", "North", "2"],
+                vec!["Open", "South", "1"],
+                vec!["Closed", "North", "1"],
+                vec!["Open", "East", "1"],
+            ]
+        );
+        // Only the group columns are left to quote
+        assert_eq!(data.force_quoted, vec![0]);
+
+        let mut data = sales();
+        processor_with_aggregate(&[], AggregateOp::Sum, Some("Total"))
+            .aggregate(&mut data)
+            .unwrap();
+        assert_eq!(data.retained_headers, vec!["Total"]);
+        assert_eq!(data.data, vec![vec!["35.5"]]);
+    }
+
+    #[test]
+    fn test_aggregate_missing_column_leaves_data_untouched() {
+        let mut data = sales();
+        let result = processor_with_aggregate(&["Missing"], AggregateOp::Sum, None).aggregate(&mut data);
+
+        assert!(matches!(result, Err(Error::CsvHeaders(_))));
+        assert_eq!(data.data, sales().data);
+    }
+
+    #[test]
+    fn test_running_total_over_sorted_rows() {
+        let config = Config {
+            running_total: Some(RunningTotal {
+                source: "Amount".to_string(),
+                into: "Balance".to_string(),
+                strict_numeric: false,
+            }),
+            ..Default::default()
+        };
+        let processor = CsvProcessor::new(&config);
+        let mut data = retained(
+            &["Date", "Amount"],
+            &[
+                &["2024-01-01", "100"],
+                &["2024-01-02", "-25.5"],
+                &["2024-01-03", "n/a"],
+                &["2024-01-04", "10"],
+            ],
+        );
+
+        processor.running_total(&mut data).unwrap();
+
+        assert_eq!(data.retained_headers, vec!["Date", "Amount", "Balance"]);
+        let balances = data.data.iter().map(|row| row[2].as_str()).collect::<Vec<_>>();
+        assert_eq!(balances, vec!["100", "74.5", "74.5", "84.5"]);
+    }
+
+    #[test]
+    fn test_running_total_strict_leaves_data_untouched() {
+        let config = Config {
+            running_total: Some(RunningTotal {
+                source: "Amount".to_string(),
+                into: "Balance".to_string(),
+                strict_numeric: true,
+            }),
+            ..Default::default()
+        };
+        let processor = CsvProcessor::new(&config);
+        let mut data = retained(&["Amount"], &[&["1"], &["oops"]]);
+
+        assert!(matches!(processor.running_total(&mut data), Err(Error::NumericParse(_))));
+        assert_eq!(data.retained_headers, vec!["Amount"]);
+        assert_eq!(data.data[0].len(), 1);
+    }
+
+    #[test]
+    fn test_deduplicate_uses_composite_key() {
+        let mut processor = processor_with_unique(&["Id", "Kind"]);
+        let mut data = retained(
+            &["Id", "Kind", "Value"],
+            &[
+                &["1", "a", "row A"],
+                &["1", "b", "row B"],
+                &["2", "a", "row C"],
+                &["1", "a", "duplicate of row A"],
+            ],
+        );
+
+        assert_eq!(processor.deduplicate(&mut data).unwrap(), 1);
+
+        // Rows A and B share "Id" but differ on "Kind", so both survive; only the exact pair is a duplicate
+        let values = data.data.iter().map(|row| row[2].as_str()).collect::<Vec<_>>();
+        assert_eq!(values, vec!["row A", "row B", "row C"]);
+    }
+
+    #[test]
+    fn test_deduplicate_all_columns() {
+        let rows: &[&[&str]] = &[&["1", "a"], &["1", "b"], &["1", "a"], &["2", "a"]];
+
+        // Without unique_fields, nothing is deduplicated unless dedup_all is set
+        let mut data = retained(&["Id", "Kind"], rows);
+        assert_eq!(processor_with_unique(&[]).deduplicate(&mut data).unwrap(), 0);
+
+        let mut processor = CsvProcessor::new(&Config {
+            unique_fields: Vec::new(),
+            dedup_all: true,
+            ..Default::default()
+        });
+        let mut data = retained(&["Id", "Kind"], rows);
+        assert_eq!(processor.deduplicate(&mut data).unwrap(), 1);
+        assert_eq!(data.data, retained(&[], &[&["1", "a"], &["1", "b"], &["2", "a"]]).data);
+    }
+
+    #[test]
+    fn test_deduplicate_with_count() {
+        let mut processor = CsvProcessor::new(&Config {
+            unique_fields: vec!["Id".to_string(), "Kind".to_string()],
+            dedup_count: true,
+            ..Default::default()
+        });
+        let mut data = retained(&["Id", "Kind"], &[&["1", "a"], &["2", "a"], &["1", "a"], &["1", "b"], &["1", "a"]]);
+
+        processor.deduplicate(&mut data).unwrap();
+
+        assert_eq!(data.retained_headers, vec!["Id", "Kind", COUNT_COLUMN]);
+        assert_eq!(data.data, retained(&[], &[&["1", "a", "3"], &["2", "a", "1"], &["1", "b", "1"]]).data);
+    }
+
+    #[test]
+    fn test_deduplicate_keep_first_and_last() {
+        let rows: &[&[&str]] = &[
+            &["1", "2024-01-01"],
+            &["2", "2024-01-02"],
+            &["1", "2024-01-03"],
+            &["3", "2024-01-04"],
+            &["2", "2024-01-05"],
+        ];
+
+        let mut processor = processor_with_unique(&["Id"]);
+        let mut data = retained(&["Id", "Date"], rows);
+        processor.deduplicate(&mut data).unwrap();
+        assert_eq!(data.data, retained(&[], &[&["1", "2024-01-01"], &["2", "2024-01-02"], &["3", "2024-01-04"]]).data);
+
+        let mut processor = CsvProcessor::new(&Config {
+            unique_fields: vec!["Id".to_string()],
+            dedup_keep: DedupKeep::Last,
+            ..Default::default()
+        });
+        let mut data = retained(&["Id", "Date"], rows);
+        processor.deduplicate(&mut data).unwrap();
+        assert_eq!(data.data, retained(&[], &[&["1", "2024-01-03"], &["3", "2024-01-04"], &["2", "2024-01-05"]]).data);
+    }
+
+    #[test]
+    fn test_deduplicate_aggregate_sum() {
+        let mut processor = CsvProcessor::new(&Config {
+            unique_fields: vec!["Member".to_string()],
+            dedup_aggregate: HashMap::from([("Amount".to_string(), AggOp::Sum), ("Date".to_string(), AggOp::Max)]),
+            ..Default::default()
+        });
+        let mut data = retained(
+            &["Member", "Amount", "Date"],
+            &[
+                &["m1", "10.5", "2024-01-03"],
+                &["m2", "7", "2024-01-01"],
+                &["m1", "n/a", "2024-01-05"],
+                &["m1", "2", "2024-01-02"],
+            ],
+        );
+
+        processor.deduplicate(&mut data).unwrap();
+
+        // The first m1 row is kept, but with the total amount and the latest date of all three m1 rows
+        assert_eq!(data.data, retained(&[], &[&["m1", "12.5", "2024-01-05"], &["m2", "7", "2024-01-01"]]).data);
+    }
+
+    #[test]
+    fn test_deduplicate_aggregate_concat() {
+        let mut processor = CsvProcessor::new(&Config {
+            unique_fields: vec!["Member".to_string()],
+            dedup_keep: DedupKeep::Last,
+            dedup_count: true,
+            dedup_aggregate: HashMap::from([("Tag".to_string(), AggOp::Concat), ("Note".to_string(), AggOp::First)]),
+            ..Default::default()
+        });
+        let mut data = retained(
+            &["Member", "Tag", "Note"],
+            &[
+                &["m1", "a", "first"],
+                &["m1", "", "second"],
+                &["m2", "x", "only"],
+                &["m1", "c", "third"],
+            ],
+        );
+
+        processor.deduplicate(&mut data).unwrap();
+
+        // Empty values are left out of the concatenation; `first` ignores that the last row is the one kept
+        assert_eq!(data.data, retained(&[], &[&["m2", "x", "only", "1"], &["m1", "a;c", "first", "3"]]).data);
+    }
+
+    #[test]
+    fn test_dedup_aggregate_idxs_errors() {
+        let data = retained(&["Member", "Amount"], &[]);
+        let processor = |column: &str| {
+            CsvProcessor::new(&Config {
+                unique_fields: vec!["Member".to_string()],
+                dedup_aggregate: HashMap::from([(column.to_string(), AggOp::Sum)]),
+                ..Default::default()
+            })
+        };
+
+        assert!(matches!(processor("Missing").dedup_aggregate_idxs(&data), Err(Error::CsvHeaders(_))));
+        assert!(matches!(processor("Member").dedup_aggregate_idxs(&data), Err(Error::CsvHeaders(_))));
+        assert_eq!(processor("Amount").dedup_aggregate_idxs(&data).unwrap(), vec![(1, AggOp::Sum)]);
+
+        // The aggregated column is resolved like the unique fields, so differently written names are still caught
+        let processor = CsvProcessor::new(&Config {
+            unique_fields: vec!["MEMBER".to_string()],
+            dedup_aggregate: HashMap::from([(" member ".to_string(), AggOp::Sum)]),
+            case_insensitive_headers: true,
+            ..Default::default()
+        });
+        assert!(
+            matches!(processor.dedup_aggregate_idxs(&data), Err(Error::CsvHeaders(msg)) if msg.contains("unique_fields"))
+        );
+    }
+
+    #[test]
+    fn test_delta_against_previous_output() {
+        let processor = CsvProcessor::new(&Config {
+            unique_fields: vec!["Id".to_string()],
+            delta_report_removed: true,
+            ..Default::default()
+        });
+        // Previous columns are in a different order, with an extra column that is no longer retained
+        let previous = retained(
+            &["Name", "Id", "Old"],
+            &[
+                &["Alice", "1", "x"],
+                &["Bob", "2", "x"],
+                &["Carl", "3", "x"],
+                &["Dave", "4", "x"],
+            ],
+        );
+        let mut current = retained(&["Id", "Name"], &[&["1", "Alice"], &["2", "Bobby"], &["4", "Dave"], &["5", "Eve"]]);
+
+        processor.delta(&mut current, &previous).unwrap();
+
+        assert_eq!(current.retained_headers, vec!["Id", "Name", DELTA_COLUMN]);
+        assert_eq!(
+            current.data,
+            retained(
+                &[],
+                &[
+                    &["2", "Bobby", "changed"],
+                    &["5", "Eve", "added"],
+                    &["3", "Carl", "removed"]
+                ]
+            )
+            .data
+        );
+    }
+
+    #[test]
+    fn test_delta_without_removed() {
+        let processor = processor_with_unique(&["Id"]);
+        let previous = retained(&["Id", "Name"], &[&["1", "Alice"], &["3", "Carl"]]);
+        let mut current = retained(&["Id", "Name"], &[&["1", "Alice"]]);
+
+        processor.delta(&mut current, &previous).unwrap();
+
+        assert!(current.data.is_empty());
+    }
+
+    #[test]
+    fn test_delta_missing_previous_column() {
+        let processor = processor_with_unique(&["Id"]);
+        let previous = retained(&["Id"], &[&["1"]]);
+        let mut current = retained(&["Id", "Name"], &[&["1", "Alice"]]);
+
+        assert!(matches!(processor.delta(&mut current, &previous), Err(Error::CsvHeaders(_))));
+    }
+
+    #[test]
+    fn test_check_row_count_exact() {
+        let processor = CsvProcessor::new(&Config {
+            expect_rows: Some(3),
+            ..Default::default()
+        });
+
+        assert!(processor.check_row_count(3).is_ok());
+        assert!(matches!(processor.check_row_count(2), Err(Error::RowCountMismatch { actual: 2, .. })));
+        assert!(matches!(processor.check_row_count(4), Err(Error::RowCountMismatch { actual: 4, .. })));
+    }
+
+    #[test]
+    fn test_check_row_count_range() {
+        let processor = CsvProcessor::new(&Config {
+            expect_rows_range: Some((10, 20)),
+            ..Default::default()
+        });
+
+        assert!(processor.check_row_count(10).is_ok());
+        assert!(processor.check_row_count(20).is_ok());
+
+        let under = processor.check_row_count(9).unwrap_err();
+        assert_eq!(
+            under.to_string(),
+            "Output row count does not match the expectation: expected between 10 and 20, got 9"
+        );
+        assert!(matches!(processor.check_row_count(21), Err(Error::RowCountMismatch { actual: 21, .. })));
+    }
+
+    #[test]
+    fn test_check_row_count_unset() {
+        let processor = processor_with_unique(&[]);
+        assert!(processor.check_row_count(0).is_ok());
+        assert!(processor.check_row_count(usize::MAX).is_ok());
+    }
+
+    #[test]
+    fn test_dedup_preview_missing_unique_field() {
+        let processor = processor_with_unique(&["Missing"]);
+        let data = retained(&["Id"], &[&["1"]]);
+
+        assert!(matches!(processor.dedup_preview(&data, 1), Err(Error::CsvHeaders(_))));
     }
 }
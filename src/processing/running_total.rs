@@ -0,0 +1,133 @@
+use crate::config::RunningTotal;
+use crate::prelude::*;
+
+/// A `RunningTotal` that has been resolved against the retained headers and carries the sum so far.
+///
+/// Rows must be fed through `next` in output order, as each call adds to the total.
+///
+/// # Example
+///
+/// ```ignore
+/// let mut column = RunningTotalColumn::new(&running_total, &retained_data.retained_headers)?;
+/// for row in &mut retained_data.data {
+///     let total = column.next(row)?;
+///     row.push(total);
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct RunningTotalColumn {
+    source_idx: usize,
+    strict_numeric: bool,
+    total: f64,
+}
+
+impl RunningTotalColumn {
+    /// Resolves the `source` column of a `RunningTotal` and starts the total at zero.
+    ///
+    /// # Arguments
+    ///
+    /// * `running_total` - The configured `RunningTotal`.
+    /// * `headers` - The retained headers the `source` column is looked up in.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Self>` - Returns the resolved column, or an `Error` on failure.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::CsvHeaders` if the `source` column is not a retained field.
+    pub fn new(running_total: &RunningTotal, headers: &[String]) -> Result<Self> {
+        let source_idx = headers
+            .iter()
+            .position(|header| *header == running_total.source)
+            .ok_or_else(|| {
+                Error::CsvHeaders(format!("Running total source is not a retained field: {}", running_total.source))
+            })?;
+
+        Ok(Self {
+            source_idx,
+            strict_numeric: running_total.strict_numeric,
+            total: 0.0,
+        })
+    }
+
+    /// Adds the row's `source` value to the total and returns the new total.
+    ///
+    /// # Arguments
+    ///
+    /// * `row` - The row to accumulate, in output order.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<String>` - Returns the running total including this row, or an `Error` on failure.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::NumericParse` if the value is not a number and `strict_numeric` is set.
+    pub fn next(&mut self, row: &[String]) -> Result<String> {
+        let val = row.get(self.source_idx).map_or("", |val| val.trim());
+
+        match val.parse::<f64>() {
+            Ok(num) => self.total += num,
+            Err(e) if self.strict_numeric => {
+                return Err(Error::NumericParse(format!("'{val}' in running total source column: {e}")));
+            }
+            Err(_) => {}
+        }
+
+        Ok(self.total.to_string())
+    }
+}
+
+#[cfg(test)]
+mod running_total_tests {
+    use super::*;
+
+    fn headers() -> Vec<String> {
+        vec!["Id".to_string(), "Amount".to_string()]
+    }
+
+    fn running_total(strict_numeric: bool) -> RunningTotal {
+        RunningTotal {
+            source: "Amount".to_string(),
+            into: "Balance".to_string(),
+            strict_numeric,
+        }
+    }
+
+    fn row(id: &str, amount: &str) -> Vec<String> {
+        vec![id.to_string(), amount.to_string()]
+    }
+
+    #[test]
+    fn test_running_total_accumulates() {
+        let mut column = RunningTotalColumn::new(&running_total(false), &headers()).unwrap();
+
+        assert_eq!(column.next(&row("1", "10")).unwrap(), "10");
+        assert_eq!(column.next(&row("2", " 2.5 ")).unwrap(), "12.5");
+        assert_eq!(column.next(&row("3", "-4.5")).unwrap(), "8");
+    }
+
+    #[test]
+    fn test_running_total_non_numeric_is_zero() {
+        let mut column = RunningTotalColumn::new(&running_total(false), &headers()).unwrap();
+
+        assert_eq!(column.next(&row("1", "10")).unwrap(), "10");
+        assert_eq!(column.next(&row("2", "n/a")).unwrap(), "10");
+        assert_eq!(column.next(&row("3", "")).unwrap(), "10");
+    }
+
+    #[test]
+    fn test_running_total_non_numeric_strict() {
+        let mut column = RunningTotalColumn::new(&running_total(true), &headers()).unwrap();
+
+        assert!(column.next(&row("1", "10")).is_ok());
+        assert!(matches!(column.next(&row("2", "n/a")), Err(Error::NumericParse(_))));
+    }
+
+    #[test]
+    fn test_running_total_missing_source() {
+        let column = RunningTotalColumn::new(&running_total(false), &["Id".to_string()]);
+        assert!(matches!(column, Err(Error::CsvHeaders(_))));
+    }
+}
@@ -0,0 +1,271 @@
+use std::collections::HashSet;
+use std::fmt::Display;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::prelude::{Serialize, *};
+use crate::retained::{RetainedData, compare_cells};
+
+/// The profile of a single output column.
+///
+/// Cells that are empty (or only whitespace) count as nulls and are left out of `distinct`, `min` and `max`.
+/// `min` and `max` compare numerically when both values are numbers, like `RetainedData::sort`.
+///
+/// # Fields
+///
+/// * `column` - The column name.
+/// * `nulls` - The number of empty cells.
+/// * `distinct` - The number of distinct non-empty values.
+/// * `min` - The smallest non-empty value, if any.
+/// * `max` - The largest non-empty value, if any.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColumnStats {
+    pub column: String,
+    pub nulls: usize,
+    pub distinct: usize,
+    pub min: Option<String>,
+    pub max: Option<String>,
+}
+
+/// Profiles every retained column of the given data.
+///
+/// # Arguments
+///
+/// * `retained_data` - The data to profile, usually the final output.
+///
+/// # Returns
+///
+/// * `Vec<ColumnStats>` - Returns one entry per retained header, in header order.
+///
+/// # Example
+///
+/// ```ignore
+/// let stats = column_stats(&retained_data);
+/// ```
+pub fn column_stats(retained_data: &RetainedData) -> Vec<ColumnStats> {
+    retained_data
+        .retained_headers
+        .iter()
+        .enumerate()
+        .map(|(idx, column)| {
+            let mut nulls = 0;
+            let mut distinct = HashSet::new();
+            let mut min: Option<&String> = None;
+            let mut max: Option<&String> = None;
+
+            for cell in retained_data.data.iter().filter_map(|row| row.get(idx)) {
+                if cell.trim().is_empty() {
+                    nulls += 1;
+                    continue;
+                }
+                distinct.insert(cell);
+                if min.is_none_or(|min| compare_cells(Some(cell), Some(min)).is_lt()) {
+                    min = Some(cell);
+                }
+                if max.is_none_or(|max| compare_cells(Some(cell), Some(max)).is_gt()) {
+                    max = Some(cell);
+                }
+            }
+
+            ColumnStats {
+                column: column.clone(),
+                nulls,
+                distinct: distinct.len(),
+                min: min.cloned(),
+                max: max.cloned(),
+            }
+        })
+        .collect()
+}
+
+/// Writes column statistics to a CSV file with the columns `column,nulls,distinct,min,max`.
+///
+/// # Arguments
+///
+/// * `stats` - The statistics to write, as returned by `column_stats`.
+/// * `path` - The path of the stats file. Missing parent directories are created.
+///
+/// # Returns
+///
+/// * `Result<()>` - Returns `Ok(())` on success, or an `Error` on failure.
+///
+/// # Errors
+///
+/// This function can return errors if the file cannot be created or written.
+///
+/// # Example
+///
+/// ```ignore
+/// write_column_stats(&column_stats(&retained_data), "stats.csv")?;
+/// ```
+pub fn write_column_stats(stats: &[ColumnStats], path: impl AsRef<Path>) -> Result<()> {
+    let path = path.as_ref();
+    if let Some(parent) = path.parent()
+        && !parent.as_os_str().is_empty()
+    {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    let mut wtr = csv::Writer::from_path(path)?;
+    wtr.write_record(["column", "nulls", "distinct", "min", "max"])?;
+    for column in stats {
+        wtr.write_record([
+            column.column.clone(),
+            column.nulls.to_string(),
+            column.distinct.to_string(),
+            column.min.clone().unwrap_or_default(),
+            column.max.clone().unwrap_or_default(),
+        ])?;
+    }
+    wtr.flush()?;
+
+    info!("Column stats written to: {}", path.display());
+    Ok(())
+}
+
+/// The row counts of a whole run, logged once it has finished and written by `--stats`.
+///
+/// Every record read ends up in exactly one of `rows_malformed`, `rows_filtered_out`, `rows_deduplicated` or `rows_kept`,
+/// except for the rows `head`, `tail`, `sample` or `delta_against` leave out of the output.
+/// With `parallel` and a `limit`, the records of the last batch read past the limit count as filtered out.
+///
+/// # Fields
+///
+/// * `rows_read` - The number of records read from the source(s), including malformed ones.
+/// * `rows_malformed` - The number of records that failed to parse and were skipped (see `on_error`).
+/// * `rows_filtered_out` - The number of records rejected by the filters.
+/// * `rows_deduplicated` - The number of rows removed as duplicates, by `dedup_bloom` as they are read and by `unique_fields`.
+/// * `rows_kept` - The number of rows in the output.
+#[derive(Serialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Stats {
+    pub rows_read: usize,
+    pub rows_malformed: usize,
+    pub rows_filtered_out: usize,
+    pub rows_deduplicated: usize,
+    pub rows_kept: usize,
+}
+
+impl Display for Stats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} rows read, {} malformed, {} filtered out, {} removed as duplicates, {} kept",
+            self.rows_read, self.rows_malformed, self.rows_filtered_out, self.rows_deduplicated, self.rows_kept
+        )
+    }
+}
+
+/// Returns the path of the `--stats` sidecar of an output file: the output path with `.stats.json` appended,
+/// e.g. `out.csv` becomes `out.csv.stats.json`.
+pub fn stats_sidecar_path(output_path: &Path) -> PathBuf {
+    let mut path = output_path.as_os_str().to_os_string();
+    path.push(".stats.json");
+    PathBuf::from(path)
+}
+
+/// Writes the run `Stats` as a pretty-printed JSON object to the given path.
+///
+/// # Arguments
+///
+/// * `stats` - The row counts of the run.
+/// * `path` - The path of the JSON file, see `stats_sidecar_path`.
+///
+/// # Returns
+///
+/// * `Result<()>` - Returns `Ok(())` on success, or an `Error` on failure.
+///
+/// # Errors
+///
+/// This function can return errors if the file cannot be created or written to.
+pub fn write_stats(stats: &Stats, path: impl AsRef<Path>) -> Result<()> {
+    let path = path.as_ref();
+    let mut file = std::fs::File::create(path)?;
+    serde_json::to_writer_pretty(&mut file, stats)?;
+    writeln!(file)?;
+
+    info!("Run stats written to: {}", path.display());
+    Ok(())
+}
+
+#[cfg(test)]
+mod stats_tests {
+    use tempfile::tempdir;
+
+    use super::*;
+
+    fn retained() -> RetainedData {
+        RetainedData {
+            retained_headers: vec!["Id".to_string(), "Name".to_string(), "Amount".to_string()],
+            data: [
+                ["1", "Alice", "9"],
+                ["2", "", "10"],
+                ["3", "Bob", "100"],
+                ["4", "Alice", " "],
+            ]
+            .iter()
+            .map(|row| row.iter().map(ToString::to_string).collect())
+            .collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_write_stats_sidecar() {
+        let dir = tempdir().unwrap();
+        let path = stats_sidecar_path(&dir.path().join("out.csv"));
+        assert_eq!(path, dir.path().join("out.csv.stats.json"));
+
+        let stats = Stats {
+            rows_read: 10,
+            rows_malformed: 1,
+            rows_filtered_out: 4,
+            rows_deduplicated: 2,
+            rows_kept: 3,
+        };
+        write_stats(&stats, &path).unwrap();
+
+        let written: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(written["rows_read"], 10);
+        assert_eq!(written["rows_kept"], 3);
+        assert_eq!(stats.to_string(), "10 rows read, 1 malformed, 4 filtered out, 2 removed as duplicates, 3 kept");
+    }
+
+    #[test]
+    fn test_column_stats() {
+        let stats = column_stats(&retained());
+
+        assert_eq!(
+            stats[1],
+            ColumnStats {
+                column: "Name".to_string(),
+                nulls: 1,
+                distinct: 2,
+                min: Some("Alice".to_string()),
+                max: Some("Bob".to_string()),
+            }
+        );
+        // Numeric-aware, so "100" is the max rather than "9"
+        assert_eq!(stats[2].min.as_deref(), Some("9"));
+        assert_eq!(stats[2].max.as_deref(), Some("100"));
+        assert_eq!(stats[2].nulls, 1);
+    }
+
+    #[test]
+    fn test_data_and_stats_files_are_written() {
+        let dir = tempdir().unwrap();
+        let data_path = dir.path().join("output.csv");
+        let stats_path = dir.path().join("reports").join("stats.csv");
+        let data = retained();
+
+        data.to_csv(&data_path).unwrap();
+        write_column_stats(&column_stats(&data), &stats_path).unwrap();
+
+        let written_data = RetainedData::from_output_file(&data_path).unwrap();
+        assert_eq!(written_data.data, data.data);
+
+        assert_eq!(
+            std::fs::read_to_string(&stats_path).unwrap(),
+            "column,nulls,distinct,min,max\nId,0,4,1,4\nName,1,2,Alice,Bob\nAmount,1,3,9,100\n"
+        );
+    }
+}
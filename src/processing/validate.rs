@@ -0,0 +1,263 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::config::{ColumnValidation, ValueType};
+use crate::prelude::*;
+use crate::retained::RetainedData;
+
+/// What a value failed to live up to.
+///
+/// # Variants
+///
+/// * `Empty` - The column is `required`, but the value is empty.
+/// * `WrongType` - The value is not of the configured `type`.
+/// * `NotAllowed` - The value is not one of the `allowed` values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ViolationKind {
+    Empty,
+    WrongType(ValueType),
+    NotAllowed,
+}
+
+impl std::fmt::Display for ViolationKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Empty => write!(f, "required value is empty"),
+            Self::WrongType(value_type) => write!(f, "not a valid {value_type}"),
+            Self::NotAllowed => write!(f, "not an allowed value"),
+        }
+    }
+}
+
+/// A single row/column that violated its `validations` entry.
+///
+/// # Fields
+///
+/// * `row` - The 1-based number of the row in the output (excluding the header row).
+/// * `column` - The column name.
+/// * `value` - The offending value.
+/// * `kind` - What the value violated.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Violation {
+    pub row: usize,
+    pub column: String,
+    pub value: String,
+    pub kind: ViolationKind,
+}
+
+/// A `Violation` as written to the report.
+#[derive(Serialize)]
+struct ReportRow<'a> {
+    row: usize,
+    column: &'a str,
+    value: &'a str,
+    violation: String,
+}
+
+/// Checks every row of the data against the configured `validations`.
+///
+/// Each column is checked in turn: an empty value only violates `required`, a non-empty one is checked against
+/// the `type` and then the `allowed` values. At most one violation is reported per cell.
+///
+/// # Arguments
+///
+/// * `retained_data` - The data to validate, usually the final output.
+/// * `validations` - The expectations per column name.
+///
+/// # Returns
+///
+/// * `Result<Vec<Violation>>` - Returns every violation, ordered by row and then column, or an `Error` on failure.
+///
+/// # Errors
+///
+/// Returns `Error::CsvHeaders` if a validated column is not among the retained headers.
+///
+/// # Example
+///
+/// ```ignore
+/// let violations = validate(&retained_data, &config.validations)?;
+/// ```
+pub fn validate(
+    retained_data: &RetainedData,
+    validations: &HashMap<String, ColumnValidation>,
+) -> Result<Vec<Violation>> {
+    let mut checks = validations
+        .iter()
+        .map(|(column, validation)| {
+            retained_data
+                .retained_headers
+                .iter()
+                .position(|header| header == column)
+                .map(|idx| (idx, column, validation))
+                .ok_or_else(|| Error::CsvHeaders(format!("Validated column is not a retained field: {column}")))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    checks.sort_by_key(|&(idx, _, _)| idx);
+
+    let mut violations = vec![];
+    for (row_idx, row) in retained_data.data.iter().enumerate() {
+        for &(idx, column, validation) in &checks {
+            let value = row.get(idx).map_or("", String::as_str);
+
+            let kind = if value.trim().is_empty() {
+                validation.required.then_some(ViolationKind::Empty)
+            } else if let Some(value_type) = validation.value_type
+                && !value_type.matches(value)
+            {
+                Some(ViolationKind::WrongType(value_type))
+            } else if !validation.allowed.is_empty() && !validation.allowed.iter().any(|allowed| allowed == value) {
+                Some(ViolationKind::NotAllowed)
+            } else {
+                None
+            };
+
+            if let Some(kind) = kind {
+                violations.push(Violation {
+                    row: row_idx + 1,
+                    column: column.clone(),
+                    value: value.to_string(),
+                    kind,
+                });
+            }
+        }
+    }
+    Ok(violations)
+}
+
+/// Writes the violations to a report file, with the columns `row,column,value,violation`.
+///
+/// A path ending in `.json` gets a JSON array of objects with the same keys, anything else a CSV file.
+///
+/// # Arguments
+///
+/// * `violations` - The violations to write, as returned by `validate`.
+/// * `path` - The path of the report. Missing parent directories are created.
+///
+/// # Returns
+///
+/// * `Result<()>` - Returns `Ok(())` on success, or an `Error` on failure.
+///
+/// # Errors
+///
+/// This function can return errors if the report cannot be created or written.
+///
+/// # Example
+///
+/// ```ignore
+/// write_validation_report(&validate(&retained_data, &config.validations)?, "violations.csv")?;
+/// ```
+pub fn write_validation_report(violations: &[Violation], path: impl AsRef<Path>) -> Result<()> {
+    let path = path.as_ref();
+    if let Some(parent) = path.parent()
+        && !parent.as_os_str().is_empty()
+    {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    let rows = violations.iter().map(|violation| ReportRow {
+        row: violation.row,
+        column: &violation.column,
+        value: &violation.value,
+        violation: violation.kind.to_string(),
+    });
+
+    if path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("json")) {
+        let file = std::io::BufWriter::new(std::fs::File::create(path)?);
+        serde_json::to_writer_pretty(file, &rows.collect::<Vec<_>>())?;
+    } else {
+        let mut wtr = csv::Writer::from_path(path)?;
+        // Written by hand, so the header row is there even without any violations
+        wtr.write_record(["row", "column", "value", "violation"])?;
+        for row in rows {
+            wtr.write_record([row.row.to_string().as_str(), row.column, row.value, &row.violation])?;
+        }
+        wtr.flush()?;
+    }
+
+    info!("Validation report with {} violations written to: {}", violations.len(), path.display());
+    Ok(())
+}
+
+#[cfg(test)]
+mod validate_tests {
+    use tempfile::tempdir;
+
+    use super::*;
+
+    fn retained() -> RetainedData {
+        RetainedData {
+            retained_headers: vec!["Id".to_string(), "Status".to_string(), "Date".to_string()],
+            data: [
+                ["1", "Open", "2025-01-15"],
+                ["", "Closed", "2025-01-16"],
+                ["x3", "Pending", ""],
+                ["4", "Open", "15/01/2025"],
+            ]
+            .iter()
+            .map(|row| row.iter().map(ToString::to_string).collect())
+            .collect(),
+            ..Default::default()
+        }
+    }
+
+    fn validations() -> HashMap<String, ColumnValidation> {
+        serde_json::from_str(
+            r#"{
+                "Id": { "required": true, "type": "integer" },
+                "Status": { "allowed": ["Open", "Closed"] },
+                "Date": { "type": "date" }
+            }"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_validate_finds_each_violation_kind() {
+        let violations = validate(&retained(), &validations()).unwrap();
+
+        let found = violations
+            .iter()
+            .map(|v| (v.row, v.column.as_str(), v.kind))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            found,
+            vec![
+                (2, "Id", ViolationKind::Empty),
+                (3, "Id", ViolationKind::WrongType(ValueType::Integer)),
+                (3, "Status", ViolationKind::NotAllowed),
+                (4, "Date", ViolationKind::WrongType(ValueType::Date)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_validate_unknown_column() {
+        let validations = HashMap::from([("Missing".to_string(), ColumnValidation::default())]);
+        assert!(matches!(validate(&retained(), &validations), Err(Error::CsvHeaders(_))));
+    }
+
+    #[test]
+    fn test_validation_report_csv_and_json() {
+        let dir = tempdir().unwrap();
+        let violations = validate(&retained(), &validations()).unwrap();
+
+        let csv_path = dir.path().join("report.csv");
+        write_validation_report(&violations, &csv_path).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&csv_path).unwrap(),
+            "row,column,value,violation\n\
+             2,Id,,required value is empty\n\
+             3,Id,x3,not a valid integer\n\
+             3,Status,Pending,not an allowed value\n\
+             4,Date,15/01/2025,not a valid date\n"
+        );
+
+        let json_path = dir.path().join("report.json");
+        write_validation_report(&violations[..1], &json_path).unwrap();
+        let json: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&json_path).unwrap()).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!([{ "row": 2, "column": "Id", "value": "", "violation": "required value is empty" }])
+        );
+    }
+}
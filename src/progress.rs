@@ -0,0 +1,181 @@
+use std::io::Read;
+use std::sync::LazyLock;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use indicatif::{MultiProgress, ProgressBar, ProgressDrawTarget, ProgressStyle};
+use log::{LevelFilter, Log, Metadata, Record};
+
+use crate::cli::VerbosityLevel;
+
+/// How often a spinner is redrawn, so it keeps turning while a slow source is waited on.
+const SPINNER_TICK: Duration = Duration::from_millis(120);
+
+/// Every progress bar is drawn through this, so that log lines can be printed above them (see `ProgressLogger`).
+/// Nothing is drawn when stderr isn't a terminal.
+static PROGRESS: LazyLock<MultiProgress> =
+    LazyLock::new(|| MultiProgress::with_draw_target(ProgressDrawTarget::stderr()));
+
+/// The number of `SourceProgress` bars currently shown, when `0` log lines are printed straight away.
+static ACTIVE_BARS: AtomicUsize = AtomicUsize::new(0);
+
+/// The `--progress` indicator of reading a source, drawn on stderr.
+///
+/// When the size of the source is known (every source is a regular file), this is a bar of the bytes consumed
+/// out of the total, with an estimate of the time left. Otherwise (stdin, a named pipe) it's a spinner
+/// with a running count of the records read. The indicator is cleared once it's dropped.
+///
+/// # Example
+///
+/// ```ignore
+/// let progress = SourceProgress::new(Some(file.metadata()?.len()));
+/// let input = progress.wrap_read(file);
+/// ```
+pub struct SourceProgress {
+    bar: ProgressBar,
+    sized: bool,
+}
+
+impl SourceProgress {
+    /// Creates and shows a new progress indicator.
+    ///
+    /// # Arguments
+    ///
+    /// * `total_bytes` - The combined size of the source(s) in bytes, or `None` when it can't be known.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - A byte progress bar with a `total_bytes`, or a record counting spinner without.
+    pub fn new(total_bytes: Option<u64>) -> Self {
+        let bar = match total_bytes {
+            Some(total) => ProgressBar::new(total).with_style(
+                ProgressStyle::with_template("{bar:40} {percent:>3}% {bytes}/{total_bytes} (eta {eta})")
+                    .expect("The progress bar template is valid"),
+            ),
+            None => {
+                let spinner = ProgressBar::new_spinner().with_style(
+                    ProgressStyle::with_template("{spinner} {pos} records read ({elapsed})")
+                        .expect("The spinner template is valid"),
+                );
+                spinner.enable_steady_tick(SPINNER_TICK);
+                spinner
+            }
+        };
+
+        ACTIVE_BARS.fetch_add(1, Ordering::SeqCst);
+        Self {
+            bar: PROGRESS.add(bar),
+            sized: total_bytes.is_some(),
+        }
+    }
+
+    /// Wraps a source so the bytes read from it advance the progress bar. A spinner leaves the source as-is.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - The raw source, before any decompression, so the bytes match its size on disk.
+    ///
+    /// # Returns
+    ///
+    /// * `Box<dyn Read>` - The (wrapped) source.
+    pub fn wrap_read(&self, input: impl Read + 'static) -> Box<dyn Read> {
+        if self.sized {
+            Box::new(self.bar.wrap_read(input))
+        } else {
+            Box::new(input)
+        }
+    }
+
+    /// Updates the running record count of a spinner. A progress bar follows the bytes read instead.
+    pub fn records_read(&self, records: usize) {
+        if !self.sized {
+            self.bar.set_position(records as u64);
+        }
+    }
+}
+
+impl Drop for SourceProgress {
+    fn drop(&mut self) {
+        self.bar.finish_and_clear();
+        PROGRESS.remove(&self.bar);
+        ACTIVE_BARS.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// A logger that hides any progress bars while a line is logged, then redraws them below it,
+/// so log lines and `--progress` bars don't overwrite each other on stderr.
+///
+/// # Fields
+///
+/// * `inner` - The `stderrlog` logger that formats and writes every line.
+struct ProgressLogger {
+    inner: stderrlog::StdErrLog,
+}
+
+impl Log for ProgressLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.inner.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record) {
+        if ACTIVE_BARS.load(Ordering::SeqCst) == 0 {
+            self.inner.log(record);
+        } else {
+            PROGRESS.suspend(|| self.inner.log(record));
+        }
+    }
+
+    fn flush(&self) {
+        self.inner.flush();
+    }
+}
+
+/// Installs the given `stderrlog` logger as the global logger, wrapped so it plays nicely with `--progress` bars.
+///
+/// # Arguments
+///
+/// * `inner` - The configured `stderrlog` logger.
+/// * `verbosity` - The verbosity `inner` was configured with, which sets the maximum log level.
+///
+/// # Returns
+///
+/// * `Result<(), log::SetLoggerError>` - Returns an error if a global logger was already installed.
+///
+/// # Example
+///
+/// ```ignore
+/// let mut logger = stderrlog::new();
+/// logger.verbosity(VerbosityLevel::Info);
+/// let _ = init_logger(logger, VerbosityLevel::Info);
+/// ```
+pub fn init_logger(inner: stderrlog::StdErrLog, verbosity: VerbosityLevel) -> Result<(), log::SetLoggerError> {
+    log::set_max_level(LevelFilter::from(verbosity));
+    log::set_boxed_logger(Box::new(ProgressLogger { inner }))
+}
+
+#[cfg(test)]
+mod progress_tests {
+    use super::*;
+
+    #[test]
+    fn test_progress_bar_counts_bytes() {
+        let progress = SourceProgress::new(Some(11));
+        let mut input = progress.wrap_read(std::io::Cursor::new("Id,Name\n1,A"));
+        std::io::copy(&mut input, &mut std::io::sink()).unwrap();
+
+        assert_eq!(progress.bar.position(), 11);
+        progress.records_read(2);
+        assert_eq!(progress.bar.position(), 11);
+    }
+
+    #[test]
+    fn test_spinner_counts_records() {
+        let progress = SourceProgress::new(None);
+        let mut input = progress.wrap_read(std::io::Cursor::new("Id,Name\n1,A"));
+        std::io::copy(&mut input, &mut std::io::sink()).unwrap();
+
+        assert_eq!(progress.bar.position(), 0);
+        progress.records_read(2);
+        assert_eq!(progress.bar.position(), 2);
+    }
+}
@@ -1,7 +1,13 @@
-use std::fs::File;
+use std::cmp::Ordering;
 use std::path::Path;
 
+use chrono::format::{Item, StrftimeItems};
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+use unicode_width::UnicodeWidthStr;
+
+use crate::config::{Config, SortDirection, SortKey, SortKind};
 use crate::prelude::*;
+use crate::processing::{OutputSink, finish_writer};
 
 /// Represents the retained data after processing the CSV file.
 ///
@@ -12,11 +18,18 @@ use crate::prelude::*;
 /// * `all_headers` - A vector of all headers from the CSV file.
 /// * `retained_headers` - A vector of headers that are retained after processing.
 /// * `data` - A vector of vectors containing the retained data.
+/// * `force_quoted` - Indices of the retained columns whose values are always quoted on output.
+/// * `omit_headers` - Whether the header row is left out of CSV, TSV and stdout output (see `write_headers`).
+/// * `empty_placeholder` - What empty cells are written as, in every output format (see `empty_placeholder`).
+///   Only the written output changes, `data` keeps the empty cells.
 #[derive(Debug, Default, Clone)]
 pub struct RetainedData {
     pub all_headers: Vec<String>,
     pub retained_headers: Vec<String>,
     pub data: Vec<Vec<String>>,
+    pub force_quoted: Vec<usize>,
+    pub omit_headers: bool,
+    pub empty_placeholder: Option<String>,
 }
 
 impl RetainedData {
@@ -32,7 +45,7 @@ impl RetainedData {
     ///
     /// # Example
     ///
-    /// ```rust
+    /// ```ignore
     /// let retained_data = RetainedData::new(10);
     /// ```
     pub fn new(fields_len: usize) -> Self {
@@ -43,9 +56,302 @@ impl RetainedData {
             all_headers,
             retained_headers,
             data,
+            force_quoted: Vec::new(),
+            omit_headers: false,
+            empty_placeholder: None,
+        }
+    }
+
+    /// Marks the given columns to always be quoted when the data is written out.
+    ///
+    /// The values themselves are left untouched, so deduplication, sorting and `delta_against`
+    /// still see the raw values - the quotes are only added by `to_csv`, `to_tsv` and `to_stdout`.
+    ///
+    /// # Arguments
+    ///
+    /// * `columns` - The retained headers of the columns to quote.
+    /// * `config` - The `Config` whose `header_matches` decides which retained header a column names.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<()>` - Returns `Ok(())` on success, or an `Error` on failure.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::CsvHeaders` if a column is not among the retained headers.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// retained_data.force_quote(&["Id".to_string()], &config)?;
+    /// ```
+    pub fn force_quote(&mut self, columns: &[String], config: &Config) -> Result<()> {
+        self.force_quoted = columns
+            .iter()
+            .map(|column| {
+                self.retained_headers
+                    .iter()
+                    .position(|header| config.header_matches(header, column))
+                    .ok_or_else(|| Error::CsvHeaders(format!("Force quoted column is not a retained field: {column}")))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(())
+    }
+
+    /// Appends a trailing column, keeping the headers and the row widths in sync.
+    ///
+    /// # Arguments
+    ///
+    /// * `header` - The name of the new column.
+    /// * `values` - One value per row, in row order.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let counts = retained_data.data.iter().map(|_| "1".to_string()).collect::<Vec<_>>();
+    /// retained_data.push_column("_count", counts);
+    /// ```
+    pub fn push_column(&mut self, header: impl Into<String>, values: Vec<String>) {
+        debug_assert_eq!(values.len(), self.data.len(), "one value is needed per row");
+
+        for (row, value) in self.data.iter_mut().zip(values) {
+            row.push(value);
+        }
+        self.retained_headers.push(header.into());
+    }
+
+    /// Keeps only the first `n` rows, dropping the rest.
+    ///
+    /// # Arguments
+    ///
+    /// * `n` - The number of rows to keep from the front.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// retained_data.keep_head(10);
+    /// ```
+    pub fn keep_head(&mut self, n: usize) {
+        self.data.truncate(n);
+    }
+
+    /// Keeps only the last `n` rows, dropping the rest.
+    ///
+    /// # Arguments
+    ///
+    /// * `n` - The number of rows to keep from the back.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// retained_data.keep_tail(10);
+    /// ```
+    pub fn keep_tail(&mut self, n: usize) {
+        let skip = self.data.len().saturating_sub(n);
+        self.data.drain(..skip);
+    }
+
+    /// Keeps `n` randomly chosen rows, in their current relative order.
+    ///
+    /// The same `seed` always picks the same rows from the same data. When there are `n` rows or fewer, all of them are kept.
+    ///
+    /// # Arguments
+    ///
+    /// * `n` - The number of rows to keep.
+    /// * `seed` - The seed for the random number generator.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// retained_data.sample(100, 42);
+    /// ```
+    pub fn sample(&mut self, n: usize, seed: u64) {
+        if self.data.len() <= n {
+            return;
+        }
+
+        // Partial Fisher-Yates: the first `n` indices end up a uniform random pick
+        let mut rng = XorShift64::new(seed);
+        let mut idxs = (0..self.data.len()).collect::<Vec<_>>();
+        for i in 0..n {
+            let j = i + (rng.next() % (idxs.len() - i) as u64) as usize;
+            idxs.swap(i, j);
+        }
+        idxs.truncate(n);
+        idxs.sort_unstable();
+
+        let mut keep = vec![false; self.data.len()];
+        for idx in idxs {
+            keep[idx] = true;
+        }
+        let mut keep = keep.into_iter();
+        self.data.retain(|_| keep.next().unwrap_or(false));
+    }
+
+    /// Stably sorts the rows by the given keys, in order of priority.
+    ///
+    /// Each key compares its column according to its `SortKind` - `Auto` keys are first resolved to `Numeric` or `String`
+    /// by looking at the column - and sorts in its own `direction`, or in the `descending` direction if it has none.
+    /// Rows that compare equal on every key keep their current relative order, also when sorting descending.
+    ///
+    /// # Arguments
+    ///
+    /// * `keys` - The keys to sort by, the first being the primary sort key.
+    /// * `descending` - Whether keys without a `direction` sort from largest to smallest.
+    /// * `config` - The `Config` whose `header_matches` decides which retained header a key's column names.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<()>` - Returns `Ok(())` on success, or an `Error` on failure.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::CsvHeaders` if a column is not among the retained headers,
+    /// or `Error::DateFormat` if a `Date` key's format is not a valid chrono format.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// retained_data.sort(&[SortKey::column("Date"), SortKey::column("Amount")], false, &config)?;
+    /// ```
+    pub fn sort(&mut self, keys: &[SortKey], descending: bool, config: &Config) -> Result<()> {
+        let columns = keys
+            .iter()
+            .map(|key| {
+                let idx = self
+                    .retained_headers
+                    .iter()
+                    .position(|header| config.header_matches(header, &key.column))
+                    .ok_or_else(|| Error::CsvHeaders(format!("Sort column is not a retained field: {}", key.column)))?;
+
+                let kind = match &key.kind {
+                    SortKind::Auto => self.detect_sort_kind(idx),
+                    SortKind::Date(format) if StrftimeItems::new(format).any(|item| matches!(item, Item::Error)) => {
+                        return Err(Error::DateFormat(format.clone()));
+                    }
+                    kind => kind.clone(),
+                };
+                let descending = key
+                    .direction
+                    .map_or(descending, |direction| direction == SortDirection::Desc);
+                Ok((idx, kind, descending))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        // Every cell is parsed once up front, rather than on every comparison
+        let mut keyed = std::mem::take(&mut self.data)
+            .into_iter()
+            .map(|row| {
+                let values = columns
+                    .iter()
+                    .map(|(idx, kind, _)| SortValue::parse(row.get(*idx).map_or("", String::as_str), kind))
+                    .collect::<Vec<_>>();
+                (values, row)
+            })
+            .collect::<Vec<_>>();
+
+        keyed.sort_by(|(a, _), (b, _)| {
+            a.iter()
+                .zip(b)
+                .zip(&columns)
+                .map(|((a, b), (_, _, descending))| if *descending { b.cmp(a) } else { a.cmp(b) })
+                .find(|ordering| ordering.is_ne())
+                .unwrap_or(Ordering::Equal)
+        });
+
+        self.data = keyed.into_iter().map(|(_, row)| row).collect();
+        Ok(())
+    }
+
+    /// Resolves `SortKind::Auto` for a column: `Numeric` if most of its non-empty cells are numbers, otherwise `String`.
+    ///
+    /// Going by the majority keeps the odd `n/a` from turning a column of numbers into text.
+    fn detect_sort_kind(&self, idx: usize) -> SortKind {
+        let (non_empty, numeric) = self
+            .data
+            .iter()
+            .filter_map(|row| row.get(idx).map(|cell| cell.trim()))
+            .filter(|cell| !cell.is_empty())
+            .fold((0usize, 0usize), |(non_empty, numeric), cell| {
+                (non_empty + 1, numeric + usize::from(cell.parse::<f64>().is_ok()))
+            });
+
+        if numeric * 2 > non_empty {
+            SortKind::Numeric
+        } else {
+            SortKind::String
         }
     }
 
+    /// Reads a previously written output file back into a `RetainedData`.
+    ///
+    /// The header row becomes both `all_headers` and `retained_headers`. Files with a `.tsv` extension
+    /// are read as tab-separated, anything else as comma-separated - matching `to_tsv` / `to_csv`.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path to the file to read.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Self>` - Returns the data read from the file, or an `Error` on failure.
+    ///
+    /// # Errors
+    ///
+    /// This function can return errors if the file cannot be opened or is not valid CSV.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let previous = RetainedData::from_output_file("output.csv")?;
+    /// ```
+    pub fn from_output_file(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let delimiter = if path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("tsv")) {
+            b'\t'
+        } else {
+            b','
+        };
+
+        Self::from_delimited_file(path, delimiter)
+    }
+
+    /// Reads a previously written output file back into a `RetainedData`, like `from_output_file`,
+    /// but with the given delimiter rather than one going by the extension (see `output_delimiter`).
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path to the file to read.
+    /// * `delimiter` - The field delimiter the file was written with.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Self>` - Returns the data read from the file, or an `Error` on failure.
+    ///
+    /// # Errors
+    ///
+    /// This function can return errors if the file cannot be opened or is not valid CSV.
+    pub fn from_delimited_file(path: impl AsRef<Path>, delimiter: u8) -> Result<Self> {
+        let path = path.as_ref();
+        let mut rdr = csv::ReaderBuilder::new()
+            .delimiter(delimiter)
+            .from_path(path)
+            .map_err(|e| Error::CsvRead(format!("{}: {e}", path.display())))?;
+
+        let retained_headers = rdr.headers()?.iter().map(ToString::to_string).collect::<Vec<_>>();
+        let data = rdr
+            .records()
+            .map(|record| Ok(record?.iter().map(ToString::to_string).collect()))
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self {
+            all_headers: retained_headers.clone(),
+            retained_headers,
+            data,
+            ..Default::default()
+        })
+    }
+
     /// Writes the retained data to the provided CSV writer.
     ///
     /// # Arguments
@@ -55,13 +361,25 @@ impl RetainedData {
     /// # Returns
     ///
     /// * `Result<()>` - Returns `Ok(())` on success, or an `Error` on failure.
-    fn write<W>(&self, wtr: &mut csv::Writer<W>) -> Result<()>
+    fn write<W>(&self, wtr: &mut csv::Writer<W>, delimiter: u8) -> Result<()>
     where
         W: std::io::Write,
     {
-        wtr.write_record(&self.retained_headers)?;
-        for row in &self.data {
-            wtr.write_record(row)?;
+        if self.force_quoted.is_empty() {
+            if !self.omit_headers {
+                wtr.write_record(&self.retained_headers)?;
+            }
+            for row in &self.data {
+                wtr.write_record(self.output_cells(row))?;
+            }
+        } else {
+            if !self.omit_headers {
+                wtr.write_record(quote_cells(&self.retained_headers, &[], delimiter))?;
+            }
+            for row in &self.data {
+                let row = self.output_cells(row).map(str::to_string).collect::<Vec<_>>();
+                wtr.write_record(quote_cells(&row, &self.force_quoted, delimiter))?;
+            }
         }
         wtr.flush()?;
         Ok(())
@@ -82,33 +400,71 @@ impl RetainedData {
     ///
     /// # Example
     ///
-    /// ```rust
+    /// ```ignore
     /// retained_data.to_csv("output.csv").expect("Failed to write to CSV");
     /// ```
     #[allow(dead_code)]
     pub fn to_csv(&self, output_path: impl AsRef<Path>) -> Result<()> {
-        let printable = output_path.as_ref().display();
-        let output_path = output_path.as_ref();
-
-        // Handle the case where user has provided a directory
-        // but the directory doesn't exist yet
-        if !output_path.exists() {
-            std::fs::create_dir_all(output_path.parent().unwrap())?;
-            let mut file = File::create(output_path)?;
-            std::io::Write::write_all(&mut file, b"")?;
-        }
-
-        let mut wtr = csv::Writer::from_path(output_path)?;
+        self.to_delimited(output_path, b',')
+    }
 
-        self.write(&mut wtr)?;
-        wtr.flush()?;
+    /// Writes the retained data to a tab-separated file at the specified output path.
+    ///
+    /// This behaves exactly like `to_csv`, but uses a tab as the field delimiter.
+    ///
+    /// # Arguments
+    ///
+    /// * `output_path` - The path to the output TSV file.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<()>` - Returns `Ok(())` on success, or an `Error` on failure.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// retained_data.to_tsv("output.tsv").expect("Failed to write to TSV");
+    /// ```
+    pub fn to_tsv(&self, output_path: impl AsRef<Path>) -> Result<()> {
+        self.to_delimited(output_path, b'\t')
+    }
 
-        info!("Output written to: {printable}");
+    /// Writes the retained data to a file with any single-byte field delimiter, e.g. the `output_delimiter`.
+    ///
+    /// This function handles the case where the output directory does not exist
+    /// and creates it if necessary. A path ending in `.gz` is gzip compressed.
+    ///
+    /// # Arguments
+    ///
+    /// * `output_path` - The path to the output file.
+    /// * `delimiter` - The field delimiter to write with.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<()>` - Returns `Ok(())` on success, or an `Error` on failure.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// retained_data.to_delimited("output.csv", b';').expect("Failed to write to CSV");
+    /// ```
+    pub fn to_delimited(&self, output_path: impl AsRef<Path>, delimiter: u8) -> Result<()> {
+        let output_path = output_path.as_ref();
+        self.write_sink(OutputSink::create(output_path)?, delimiter)?;
 
+        info!("Output written to: {}", output_path.display());
         Ok(())
     }
 
-    /// Writes the retained data to the standard output.
+    /// Appends the retained data to the end of a file, like `to_delimited` but without replacing what's already there.
+    ///
+    /// The file is created if it doesn't exist yet. The header row is written as usual, set `omit_headers`
+    /// to leave it out when the file already has one.
+    ///
+    /// # Arguments
+    ///
+    /// * `output_path` - The path to the output file.
+    /// * `delimiter` - The field delimiter to write with.
     ///
     /// # Returns
     ///
@@ -116,64 +472,1157 @@ impl RetainedData {
     ///
     /// # Example
     ///
-    /// ```rust
-    /// retained_data.to_stdout().expect("Failed to write to stdout");
+    /// ```ignore
+    /// retained_data.append_delimited("daily.csv", b',').expect("Failed to append to CSV");
     /// ```
-    pub fn to_stdout(&self) -> Result<()> {
-        let mut wtr = csv::Writer::from_writer(std::io::stderr());
+    pub fn append_delimited(&self, output_path: impl AsRef<Path>, delimiter: u8) -> Result<()> {
+        let output_path = output_path.as_ref();
+        self.write_sink(OutputSink::append(output_path)?, delimiter)?;
 
-        self.write(&mut wtr)?;
+        info!("Output appended to: {}", output_path.display());
         Ok(())
     }
-}
-
-// #[cfg(debug_assertions)]
-#[cfg(test)]
-mod output_retained_tests {
-    use tempdir::TempDir;
-
-    use super::*;
 
-    fn gen_default_retained_data() -> RetainedData {
-        RetainedData {
-            all_headers: vec!["Header1".to_string(), "Header2".to_string()],
-            retained_headers: vec!["Header1".to_string(), "Header2".to_string()],
-            data: vec![vec!["Value1".to_string(), "Value2".to_string()]],
-        }
+    /// Writes the retained data to an opened output sink with the given delimiter, and finishes it.
+    fn write_sink(&self, sink: OutputSink, delimiter: u8) -> Result<()> {
+        let mut wtr = self.writer_builder(delimiter).from_writer(sink);
+        self.write(&mut wtr, delimiter)?;
+        finish_writer(wtr)
     }
 
-    #[test]
-    fn test_retained_data_to_csv() {
-        let temp_dir = TempDir::new("test").unwrap();
-        let output_path = temp_dir.path().join("output.csv");
-
-        let data = gen_default_retained_data();
+    /// Writes the retained data to a JSON file, as an array of objects keyed by the retained headers.
+    ///
+    /// Every value is written as a JSON string, exactly as it appears in the CSV - numbers are not converted.
+    /// When there are no rows, `[]` is written.
+    ///
+    /// # Arguments
+    ///
+    /// * `output_path` - The path to the output JSON file.
+    /// * `pretty` - Whether to pretty-print the JSON rather than write it compactly.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<()>` - Returns `Ok(())` on success, or an `Error` on failure.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// retained_data.to_json("output.json", true).expect("Failed to write to JSON");
+    /// ```
+    pub fn to_json(&self, output_path: impl AsRef<Path>, pretty: bool) -> Result<()> {
+        let output_path = output_path.as_ref();
+        let mut sink = OutputSink::create(output_path)?;
+        self.to_json_writer(&mut sink, pretty)?;
+        sink.finish()?;
+        info!("Output written to: {}", output_path.display());
+        Ok(())
+    }
 
-        data.to_csv(output_path.clone()).unwrap();
+    /// Writes the retained data as JSON to any writer, see `to_json`.
+    ///
+    /// # Arguments
+    ///
+    /// * `wtr` - The writer to write the JSON to.
+    /// * `pretty` - Whether to pretty-print the JSON rather than write it compactly.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<()>` - Returns `Ok(())` on success, or an `Error` on failure.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// retained_data.to_json_writer(std::io::stdout().lock(), false)?;
+    /// ```
+    pub fn to_json_writer(&self, mut wtr: impl std::io::Write, pretty: bool) -> Result<()> {
+        let rows = self
+            .data
+            .iter()
+            .map(|row| JsonRow {
+                headers: &self.retained_headers,
+                row,
+                empty_placeholder: self.empty_placeholder.as_deref(),
+            })
+            .collect::<Vec<_>>();
 
-        let mut rdr = csv::Reader::from_path(output_path).unwrap();
-        let mut records = rdr.records();
-        let first_record = records.next().unwrap().unwrap();
-        assert_eq!(&first_record[0], "Value1");
-        assert_eq!(&first_record[1], "Value2");
+        if pretty {
+            serde_json::to_writer_pretty(&mut wtr, &rows)?;
+        } else {
+            serde_json::to_writer(&mut wtr, &rows)?;
+        }
+        writeln!(wtr)?;
+        wtr.flush()?;
+        Ok(())
     }
 
-    #[test]
-    fn test_retained_data_to_csv_nested_dir() {
-        let temp_dir = TempDir::new("test").unwrap();
-        let output_path = temp_dir.path().join("nested").join("output.csv");
+    /// Writes the retained data to a newline-delimited JSON file, one object per row keyed by the retained headers.
+    ///
+    /// Rows are serialized one at a time, so the serialized output is never held in memory as a whole.
+    /// Values are written as strings, exactly like `to_json`. When there are no rows, the file is empty.
+    ///
+    /// # Arguments
+    ///
+    /// * `output_path` - The path to the output NDJSON file.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<()>` - Returns `Ok(())` on success, or an `Error` on failure.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// retained_data.to_ndjson("output.ndjson").expect("Failed to write to NDJSON");
+    /// ```
+    pub fn to_ndjson(&self, output_path: impl AsRef<Path>) -> Result<()> {
+        let output_path = output_path.as_ref();
+        let mut sink = OutputSink::create(output_path)?;
+        self.to_ndjson_writer(&mut sink)?;
+        sink.finish()?;
+        info!("Output written to: {}", output_path.display());
+        Ok(())
+    }
 
-        let data = gen_default_retained_data();
+    /// Writes the retained data as NDJSON to any writer, see `to_ndjson`.
+    ///
+    /// The writer is flushed every `NDJSON_FLUSH_ROWS` rows, so a consumer reading along sees rows as they are written.
+    ///
+    /// # Arguments
+    ///
+    /// * `wtr` - The writer to write the NDJSON to.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<()>` - Returns `Ok(())` on success, or an `Error` on failure.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// retained_data.to_ndjson_writer(std::io::stdout().lock())?;
+    /// ```
+    pub fn to_ndjson_writer(&self, mut wtr: impl std::io::Write) -> Result<()> {
+        for (written, row) in self.data.iter().enumerate() {
+            let row = JsonRow {
+                headers: &self.retained_headers,
+                row,
+                empty_placeholder: self.empty_placeholder.as_deref(),
+            };
+            serde_json::to_writer(&mut wtr, &row)?;
+            writeln!(wtr)?;
 
-        data.to_csv(output_path.clone()).unwrap();
+            if (written + 1) % NDJSON_FLUSH_ROWS == 0 {
+                wtr.flush()?;
+            }
+        }
+        wtr.flush()?;
+        Ok(())
+    }
 
-        let mut rdr = csv::Reader::from_path(output_path).unwrap();
-        let mut records = rdr.records();
-        let first_record = records.next().unwrap().unwrap();
+    /// Writes the retained data to a text file as a column-aligned table, see `to_table_writer`.
+    ///
+    /// # Arguments
+    ///
+    /// * `output_path` - The path to the output text file.
+    /// * `max_col_width` - The width cells are truncated to, if any.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<()>` - Returns `Ok(())` on success, or an `Error` on failure.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// retained_data.to_table("output.txt", Some(40)).expect("Failed to write the table");
+    /// ```
+    pub fn to_table(&self, output_path: impl AsRef<Path>, max_col_width: Option<usize>) -> Result<()> {
+        let output_path = output_path.as_ref();
+        let mut sink = OutputSink::create(output_path)?;
+        self.to_table_writer(&mut sink, max_col_width)?;
+        sink.finish()?;
+        info!("Output written to: {}", output_path.display());
+        Ok(())
+    }
+
+    /// Writes the retained data to any writer as a table for reading in a terminal.
+    ///
+    /// Every column is padded to its widest cell (measured in terminal columns, so wide characters line up), and the
+    /// columns are separated by two spaces. The header row is underlined with a line of `-` per column, unless
+    /// `omit_headers` is set. Cells wider than `max_col_width` are cut short, ending in `…`.
+    ///
+    /// # Arguments
+    ///
+    /// * `wtr` - The writer to write the table to.
+    /// * `max_col_width` - The width cells are truncated to, if any.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<()>` - Returns `Ok(())` on success, or an `Error` on failure.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// retained_data.to_table_writer(std::io::stdout().lock(), None)?;
+    /// ```
+    pub fn to_table_writer(&self, mut wtr: impl std::io::Write, max_col_width: Option<usize>) -> Result<()> {
+        let cell = |value: &str| match max_col_width {
+            Some(max) => truncate_to_width(value, max),
+            None => value.to_string(),
+        };
+        let headers = self
+            .retained_headers
+            .iter()
+            .map(|header| cell(header))
+            .collect::<Vec<_>>();
+        let rows = self
+            .data
+            .iter()
+            .map(|row| self.output_cells(row).map(cell).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+
+        let mut widths = vec![0; headers.len()];
+        let shown = (!self.omit_headers).then_some(&headers);
+        for row in shown.into_iter().chain(&rows) {
+            for (width, value) in widths.iter_mut().zip(row) {
+                *width = (*width).max(value.width());
+            }
+        }
+
+        let mut write_line = |cells: &[String]| -> Result<()> {
+            let mut line = String::new();
+            for (idx, (value, &width)) in cells.iter().zip(&widths).enumerate() {
+                if idx > 0 {
+                    line.push_str("  ");
+                }
+                line.push_str(value);
+                line.push_str(&" ".repeat(width - value.width()));
+            }
+            // The padding of the last column(s) would only leave trailing whitespace
+            writeln!(wtr, "{}", line.trim_end())?;
+            Ok(())
+        };
+
+        if !self.omit_headers {
+            write_line(&headers)?;
+            write_line(&widths.iter().map(|&width| "-".repeat(width)).collect::<Vec<_>>())?;
+        }
+        for row in &rows {
+            write_line(row)?;
+        }
+        wtr.flush()?;
+        Ok(())
+    }
+
+    /// Writes the retained data to a Markdown file as a GitHub-flavored Markdown table, see `to_markdown_writer`.
+    ///
+    /// # Arguments
+    ///
+    /// * `output_path` - The path to the output Markdown file.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<()>` - Returns `Ok(())` on success, or an `Error` on failure.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// retained_data.to_markdown("output.md").expect("Failed to write to Markdown");
+    /// ```
+    pub fn to_markdown(&self, output_path: impl AsRef<Path>) -> Result<()> {
+        let output_path = output_path.as_ref();
+        let mut sink = OutputSink::create(output_path)?;
+        self.to_markdown_writer(&mut sink)?;
+        sink.finish()?;
+        info!("Output written to: {}", output_path.display());
+        Ok(())
+    }
+
+    /// Writes the retained data to any writer as a GitHub-flavored Markdown table.
+    ///
+    /// The table starts with a `| h1 | h2 |` header row and a `| --- | --- |` separator, then has one line per row - so
+    /// without any rows, only the header and separator are written. A Markdown table always has a header row, so
+    /// `omit_headers` doesn't apply. Pipes in cells are escaped as `\|`, and line breaks are written as `<br>`.
+    ///
+    /// # Arguments
+    ///
+    /// * `wtr` - The writer to write the table to.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<()>` - Returns `Ok(())` on success, or an `Error` on failure.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// retained_data.to_markdown_writer(std::io::stdout().lock())?;
+    /// ```
+    pub fn to_markdown_writer(&self, mut wtr: impl std::io::Write) -> Result<()> {
+        let mut write_line = |cells: Vec<String>| -> Result<()> {
+            writeln!(wtr, "| {} |", cells.join(" | "))?;
+            Ok(())
+        };
+
+        write_line(
+            self.retained_headers
+                .iter()
+                .map(|header| markdown_cell(header))
+                .collect(),
+        )?;
+        write_line(vec!["---".to_string(); self.retained_headers.len()])?;
+        for row in &self.data {
+            write_line(self.output_cells(row).map(markdown_cell).collect())?;
+        }
+        wtr.flush()?;
+        Ok(())
+    }
+
+    /// Writes the retained data to an HTML file as a table, see `to_html_writer`.
+    ///
+    /// # Arguments
+    ///
+    /// * `output_path` - The path to the output HTML file.
+    /// * `standalone` - Whether to write a complete HTML document rather than just the `<table>`.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<()>` - Returns `Ok(())` on success, or an `Error` on failure.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// retained_data.to_html("output.html", true).expect("Failed to write to HTML");
+    /// ```
+    pub fn to_html(&self, output_path: impl AsRef<Path>, standalone: bool) -> Result<()> {
+        let output_path = output_path.as_ref();
+        let mut sink = OutputSink::create(output_path)?;
+        self.to_html_writer(&mut sink, standalone)?;
+        sink.finish()?;
+        info!("Output written to: {}", output_path.display());
+        Ok(())
+    }
+
+    /// Writes the retained data to any writer as an HTML table.
+    ///
+    /// The table is a `<thead>` row of `<th>` headers and a `<tbody>` with a `<tr>` of `<td>` cells per row, with
+    /// `<`, `>`, `&` and `"` in headers and cells escaped. Like JSON, `omit_headers` doesn't apply. With `standalone`,
+    /// the table is wrapped in a minimal UTF-8 HTML document, so the file can be opened in a browser as is.
+    ///
+    /// # Arguments
+    ///
+    /// * `wtr` - The writer to write the HTML to.
+    /// * `standalone` - Whether to write a complete HTML document rather than just the `<table>`.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<()>` - Returns `Ok(())` on success, or an `Error` on failure.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// retained_data.to_html_writer(std::io::stdout().lock(), false)?;
+    /// ```
+    pub fn to_html_writer(&self, mut wtr: impl std::io::Write, standalone: bool) -> Result<()> {
+        if standalone {
+            writeln!(wtr, "<!DOCTYPE html>")?;
+            writeln!(wtr, "<html>")?;
+            writeln!(wtr, "<head>")?;
+            writeln!(wtr, "<meta charset=\"utf-8\">")?;
+            writeln!(wtr, "<title>{}</title>", env!("CARGO_PKG_NAME"))?;
+            writeln!(wtr, "</head>")?;
+            writeln!(wtr, "<body>")?;
+        }
+
+        writeln!(wtr, "<table>")?;
+        writeln!(wtr, "<thead>")?;
+        write_html_row(&mut wtr, "th", self.retained_headers.iter().map(String::as_str))?;
+        writeln!(wtr, "</thead>")?;
+        writeln!(wtr, "<tbody>")?;
+        for row in &self.data {
+            write_html_row(&mut wtr, "td", self.output_cells(row))?;
+        }
+        writeln!(wtr, "</tbody>")?;
+        writeln!(wtr, "</table>")?;
+
+        if standalone {
+            writeln!(wtr, "</body>")?;
+            writeln!(wtr, "</html>")?;
+        }
+        wtr.flush()?;
+        Ok(())
+    }
+
+    /// Writes the retained data to the standard output, as CSV - so it can be piped into another tool.
+    ///
+    /// Log messages go to stderr, and never end up mixed into the data.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<()>` - Returns `Ok(())` on success, or an `Error` on failure.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// retained_data.to_stdout().expect("Failed to write to stdout");
+    /// ```
+    pub fn to_stdout(&self) -> Result<()> {
+        self.to_stdout_delimited(b',')
+    }
+
+    /// Writes the retained data to the standard output, like `to_stdout`, with any single-byte field delimiter.
+    ///
+    /// # Arguments
+    ///
+    /// * `delimiter` - The field delimiter to write with.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<()>` - Returns `Ok(())` on success, or an `Error` on failure.
+    pub fn to_stdout_delimited(&self, delimiter: u8) -> Result<()> {
+        let mut wtr = self.writer_builder(delimiter).from_writer(std::io::stdout().lock());
+
+        self.write(&mut wtr, delimiter)?;
+        Ok(())
+    }
+
+    /// The cells of a row as they're written, with empty cells replaced by the `empty_placeholder` (if one is set).
+    fn output_cells<'a>(&'a self, row: &'a [String]) -> impl Iterator<Item = &'a str> {
+        row.iter()
+            .map(|cell| with_placeholder(cell, self.empty_placeholder.as_deref()))
+    }
+
+    /// Creates the writer settings for the given delimiter.
+    ///
+    /// When any column is force quoted, quoting is done by hand in `write`, so the writer must not quote at all.
+    fn writer_builder(&self, delimiter: u8) -> csv::WriterBuilder {
+        let mut builder = csv::WriterBuilder::new();
+        builder.delimiter(delimiter);
+        if !self.force_quoted.is_empty() {
+            builder.quote_style(csv::QuoteStyle::Never);
+        }
+        builder
+    }
+}
+
+/// Escapes a cell for a Markdown table: pipes as `\|`, and line breaks (which would end the row) as `<br>`.
+fn markdown_cell(value: &str) -> String {
+    value
+        .replace('|', "\\|")
+        .replace("\r\n", "<br>")
+        .replace(['\r', '\n'], "<br>")
+}
+
+/// Returns the `placeholder` for an empty cell, if there is one, otherwise the cell itself.
+pub(crate) fn with_placeholder<'a>(cell: &'a str, placeholder: Option<&'a str>) -> &'a str {
+    match placeholder {
+        Some(placeholder) if cell.is_empty() => placeholder,
+        _ => cell,
+    }
+}
+
+/// Writes one `<tr>` of an HTML table, each cell escaped and wrapped in a `tag` (`th` or `td`) element.
+fn write_html_row<'c>(
+    wtr: &mut impl std::io::Write,
+    tag: &str,
+    cells: impl IntoIterator<Item = &'c str>,
+) -> Result<()> {
+    write!(wtr, "<tr>")?;
+    for cell in cells {
+        write!(wtr, "<{tag}>{}</{tag}>", escape_html(cell))?;
+    }
+    writeln!(wtr, "</tr>")?;
+    Ok(())
+}
+
+/// Escapes the characters with a meaning in HTML text and attribute values: `&`, `<`, `>` and `"`.
+fn escape_html(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Cuts a cell short to at most `max` terminal columns, ending it in `…` when anything was cut.
+fn truncate_to_width(value: &str, max: usize) -> String {
+    if value.width() <= max {
+        return value.to_string();
+    }
+
+    let mut truncated = String::new();
+    let mut width = 0;
+    for c in value.chars() {
+        let char_width = unicode_width::UnicodeWidthChar::width(c).unwrap_or(0);
+        if width + char_width + 1 > max {
+            break;
+        }
+        truncated.push(c);
+        width += char_width;
+    }
+    if max > 0 {
+        truncated.push('…');
+    }
+    truncated
+}
+
+/// Quotes a row by hand, for writers using `csv::QuoteStyle::Never`.
+///
+/// Columns in `forced` are always quoted, any other cell only when it needs to be - i.e. it holds
+/// the delimiter, a quote or a line break. Embedded quotes are doubled.
+///
+/// # Arguments
+///
+/// * `row` - The cells to quote.
+/// * `forced` - The indices of the cells that are always quoted.
+/// * `delimiter` - The field delimiter the row will be written with.
+///
+/// # Returns
+///
+/// * `Vec<String>` - Returns the quoted cells, ready to be written as-is.
+///
+/// # Example
+///
+/// ```ignore
+/// let quoted = quote_cells(&["007".to_string(), "Bond".to_string()], &[0], b',');
+/// assert_eq!(quoted, vec!["\"007\"", "Bond"]);
+/// ```
+pub(crate) fn quote_cells(row: &[String], forced: &[usize], delimiter: u8) -> Vec<String> {
+    row.iter()
+        .enumerate()
+        .map(|(idx, cell)| {
+            let needs_quotes = cell
+                .bytes()
+                .any(|b| b == delimiter || matches!(b, b'"' | b'\n' | b'\r'));
+            if forced.contains(&idx) || needs_quotes {
+                format!("\"{}\"", cell.replace('"', "\"\""))
+            } else {
+                cell.clone()
+            }
+        })
+        .collect()
+}
+
+/// A row serialized as a JSON object, keeping the columns in header order.
+struct JsonRow<'a> {
+    headers: &'a [String],
+    row: &'a [String],
+    empty_placeholder: Option<&'a str>,
+}
+
+impl Serialize for JsonRow<'_> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        use serde::ser::SerializeMap;
+
+        let mut map = serializer.serialize_map(Some(self.headers.len()))?;
+        for (header, value) in self.headers.iter().zip(self.row) {
+            map.serialize_entry(header, with_placeholder(value, self.empty_placeholder))?;
+        }
+        map.end()
+    }
+}
+
+/// A small xorshift64* generator - plenty for picking sample rows, without pulling in `rand`.
+struct XorShift64(u64);
+
+impl XorShift64 {
+    fn new(seed: u64) -> Self {
+        // The state must never be zero, and spreading the seed keeps small seeds from starting out alike
+        Self(seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1)
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+}
+
+/// A cell parsed according to the `SortKind` of its `sort_by` key.
+///
+/// Cells that don't parse as their key's kind are kept as `Text`, which sorts after `Number` and `Date`.
+#[derive(Debug)]
+enum SortValue {
+    Number(f64),
+    Date(NaiveDateTime),
+    Text(String),
+}
+
+impl SortValue {
+    fn parse(cell: &str, kind: &SortKind) -> Self {
+        match kind {
+            SortKind::Numeric => cell
+                .trim()
+                .parse()
+                .map_or_else(|_| Self::Text(cell.to_string()), Self::Number),
+            SortKind::Date(format) => {
+                let trimmed = cell.trim();
+                NaiveDateTime::parse_from_str(trimmed, format)
+                    .ok()
+                    .or_else(|| {
+                        NaiveDate::parse_from_str(trimmed, format)
+                            .ok()
+                            .map(|date| date.and_time(NaiveTime::MIN))
+                    })
+                    .map_or_else(|| Self::Text(cell.to_string()), Self::Date)
+            }
+            SortKind::Auto | SortKind::String => Self::Text(cell.to_string()),
+        }
+    }
+
+    fn rank(&self) -> u8 {
+        match self {
+            Self::Number(_) => 0,
+            Self::Date(_) => 1,
+            Self::Text(_) => 2,
+        }
+    }
+}
+
+impl Ord for SortValue {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Self::Number(a), Self::Number(b)) => a.total_cmp(b),
+            (Self::Date(a), Self::Date(b)) => a.cmp(b),
+            (Self::Text(a), Self::Text(b)) => a.cmp(b),
+            _ => self.rank().cmp(&other.rank()),
+        }
+    }
+}
+
+impl PartialOrd for SortValue {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for SortValue {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other).is_eq()
+    }
+}
+
+impl Eq for SortValue {}
+
+/// Compares two cells numerically if both are numbers, falling back to a string comparison.
+pub(crate) fn compare_cells(a: Option<&String>, b: Option<&String>) -> Ordering {
+    let (a, b) = (a.map_or("", String::as_str), b.map_or("", String::as_str));
+
+    match (a.trim().parse::<f64>(), b.trim().parse::<f64>()) {
+        (Ok(a), Ok(b)) => a.total_cmp(&b),
+        _ => a.cmp(b),
+    }
+}
+
+// #[cfg(debug_assertions)]
+#[cfg(test)]
+mod output_retained_tests {
+    use std::fs::File;
+
+    use tempdir::TempDir;
+
+    use super::*;
+
+    fn gen_default_retained_data() -> RetainedData {
+        RetainedData {
+            all_headers: vec!["Header1".to_string(), "Header2".to_string()],
+            retained_headers: vec!["Header1".to_string(), "Header2".to_string()],
+            data: vec![vec!["Value1".to_string(), "Value2".to_string()]],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_to_table_writer() {
+        let data = RetainedData {
+            retained_headers: vec!["Id".to_string(), "Name".to_string(), "City".to_string()],
+            data: vec![
+                vec!["1".to_string(), "Alexandria Longname".to_string(), "Oslo".to_string()],
+                vec!["22".to_string(), "日本".to_string(), String::new()],
+            ],
+            ..Default::default()
+        };
+        let table = |data: &RetainedData, max_col_width| {
+            let mut out = Vec::new();
+            data.to_table_writer(&mut out, max_col_width).unwrap();
+            String::from_utf8(out).unwrap()
+        };
+
+        assert_eq!(
+            table(&data, None),
+            "Id  Name                 City\n\
+             --  -------------------  ----\n\
+             1   Alexandria Longname  Oslo\n\
+             22  日本\n"
+        );
+        assert_eq!(
+            table(&data, Some(8)),
+            "Id  Name      City\n\
+             --  --------  ----\n\
+             1   Alexand…  Oslo\n\
+             22  日本\n"
+        );
+
+        let data = RetainedData {
+            omit_headers: true,
+            ..data
+        };
+        assert_eq!(table(&data, Some(3)), "1   Al…  Os…\n22  日…\n");
+    }
+
+    #[test]
+    fn test_to_markdown_writer() {
+        let markdown = |data: &RetainedData| {
+            let mut out = Vec::new();
+            data.to_markdown_writer(&mut out).unwrap();
+            String::from_utf8(out).unwrap()
+        };
+        let data = RetainedData {
+            retained_headers: vec!["Id".to_string(), "Note|Text".to_string()],
+            data: vec![
+                vec!["1".to_string(), "a | b".to_string()],
+                vec!["2".to_string(), "two\r\nlines".to_string()],
+            ],
+            ..Default::default()
+        };
+
+        assert_eq!(markdown(&data), "| Id | Note\\|Text |\n| --- | --- |\n| 1 | a \\| b |\n| 2 | two<br>lines |\n");
+
+        let empty = RetainedData { data: vec![], ..data };
+        assert_eq!(markdown(&empty), "| Id | Note\\|Text |\n| --- | --- |\n");
+    }
+
+    #[test]
+    fn test_empty_placeholder_in_every_writer() {
+        let data = RetainedData {
+            retained_headers: vec!["Id".to_string(), "Name".to_string()],
+            data: vec![vec!["1".to_string(), String::new()]],
+            empty_placeholder: Some("NULL".to_string()),
+            ..Default::default()
+        };
+        let written = |write: &dyn Fn(&mut Vec<u8>) -> Result<()>| {
+            let mut out = Vec::new();
+            write(&mut out).unwrap();
+            String::from_utf8(out).unwrap()
+        };
+
+        assert_eq!(written(&|out| data.to_ndjson_writer(out)), "{\"Id\":\"1\",\"Name\":\"NULL\"}\n");
+        assert_eq!(written(&|out| data.to_table_writer(out, None)), "Id  Name\n--  ----\n1   NULL\n");
+        assert_eq!(written(&|out| data.to_markdown_writer(out)), "| Id | Name |\n| --- | --- |\n| 1 | NULL |\n");
+        assert!(written(&|out| data.to_html_writer(out, false)).contains("<td>NULL</td>"));
+        // The data itself keeps the empty cell
+        assert_eq!(data.data[0][1], "");
+    }
+
+    #[test]
+    fn test_to_html_writer() {
+        let html = |data: &RetainedData, standalone| {
+            let mut out = Vec::new();
+            data.to_html_writer(&mut out, standalone).unwrap();
+            String::from_utf8(out).unwrap()
+        };
+        let data = RetainedData {
+            retained_headers: vec!["Id".to_string(), "<Name>".to_string()],
+            data: vec![vec!["1".to_string(), r#"Tom & "Jerry""#.to_string()]],
+            ..Default::default()
+        };
+
+        let table = "<table>\n<thead>\n<tr><th>Id</th><th>&lt;Name&gt;</th></tr>\n</thead>\n<tbody>\n\
+                     <tr><td>1</td><td>Tom &amp; &quot;Jerry&quot;</td></tr>\n</tbody>\n</table>\n";
+        assert_eq!(html(&data, false), table);
+
+        let document = html(&data, true);
+        assert!(document.starts_with("<!DOCTYPE html>\n<html>\n"));
+        assert!(document.contains(table));
+        assert!(document.ends_with("</body>\n</html>\n"));
+    }
+
+    #[test]
+    fn test_retained_data_to_csv() {
+        let temp_dir = TempDir::new("test").unwrap();
+        let output_path = temp_dir.path().join("output.csv");
+
+        let data = gen_default_retained_data();
+
+        data.to_csv(output_path.clone()).unwrap();
+
+        let mut rdr = csv::Reader::from_path(output_path).unwrap();
+        let mut records = rdr.records();
+        let first_record = records.next().unwrap().unwrap();
         assert_eq!(&first_record[0], "Value1");
         assert_eq!(&first_record[1], "Value2");
     }
 
+    fn sortable() -> RetainedData {
+        RetainedData {
+            retained_headers: vec!["Group".to_string(), "Amount".to_string(), "Id".to_string()],
+            data: [
+                ["b", "10", "1"],
+                ["a", "9", "2"],
+                ["b", "100", "3"],
+                ["a", "9", "4"],
+                ["a", "n/a", "5"],
+            ]
+            .iter()
+            .map(|row| row.iter().map(ToString::to_string).collect())
+            .collect(),
+            ..Default::default()
+        }
+    }
+
+    fn ids(data: &RetainedData) -> Vec<&str> {
+        data.data.iter().map(|row| row[2].as_str()).collect()
+    }
+
+    #[test]
+    fn test_retained_data_to_csv_gzip() {
+        let temp_dir = TempDir::new("test").unwrap();
+        let output_path = temp_dir.path().join("output.csv.gz");
+
+        gen_default_retained_data().to_csv(output_path.clone()).unwrap();
+
+        let decoder = flate2::read::MultiGzDecoder::new(File::open(output_path).unwrap());
+        let mut rdr = csv::Reader::from_reader(decoder);
+        let records: Vec<csv::StringRecord> = rdr.records().map(|r| r.unwrap()).collect();
+        assert_eq!(records, vec![csv::StringRecord::from(vec!["Value1", "Value2"])]);
+    }
+
+    #[test]
+    fn test_retained_data_sort_numeric_aware() {
+        let mut data = sortable();
+        data.sort(&[SortKey::column("Amount")], false, &Config::default())
+            .unwrap();
+
+        // 9 < 10 < 100 numerically, and "n/a" isn't a number so it sorts after them
+        assert_eq!(ids(&data), vec!["2", "4", "1", "3", "5"]);
+    }
+
+    #[test]
+    fn test_retained_data_sort_multiple_columns_descending() {
+        let mut data = sortable();
+        data.sort(&[SortKey::column("Group"), SortKey::column("Amount")], true, &Config::default())
+            .unwrap();
+
+        // Equal rows ("2" and "4") keep their input order
+        assert_eq!(ids(&data), vec!["3", "1", "5", "2", "4"]);
+    }
+
+    #[test]
+    fn test_retained_data_sort_mixed_kinds_and_directions() {
+        let mut data = RetainedData {
+            retained_headers: vec!["Score".to_string(), "Date".to_string(), "Name".to_string()],
+            data: [
+                ["9", "03/01/2024", "carl"],
+                ["10", "15/02/2024", "bob"],
+                ["9", "01/12/2023", "dave"],
+                ["10", "15/02/2024", "anna"],
+                ["9", "03/01/2024", "bea"],
+                ["10", "02/01/2024", "erin"],
+            ]
+            .iter()
+            .map(|row| row.iter().map(ToString::to_string).collect())
+            .collect(),
+            ..Default::default()
+        };
+
+        let keys: Vec<SortKey> = serde_json::from_str(
+            r#"[
+                { "column": "Score", "direction": "desc", "kind": "numeric" },
+                { "column": "Date", "direction": "asc", "kind": { "date": "%d/%m/%Y" } },
+                { "column": "Name", "kind": "string" }
+            ]"#,
+        )
+        .unwrap();
+        // `descending` only applies to keys without a direction - here the string key stays ascending
+        data.sort(&keys, false, &Config::default()).unwrap();
+
+        // Numerically 10 > 9 (not "10" < "9"), and 01/12/2023 < 02/01/2024 < 15/02/2024 as dates (not as text)
+        let names = data.data.iter().map(|row| row[2].as_str()).collect::<Vec<_>>();
+        assert_eq!(names, vec!["erin", "anna", "bob", "dave", "bea", "carl"]);
+    }
+
+    #[test]
+    fn test_retained_data_sort_auto_detects_string_columns() {
+        let mut data = sortable();
+        data.data[0][1] = "ten".to_string();
+        data.data[2][1] = "hundred".to_string();
+
+        // Only 2 of the 5 Amount cells are numbers, so they're all compared as text
+        data.sort(&[SortKey::column("Amount")], false, &Config::default())
+            .unwrap();
+        assert_eq!(ids(&data), vec!["2", "4", "3", "5", "1"]);
+    }
+
+    #[test]
+    fn test_retained_data_sort_invalid_date_format() {
+        let mut data = sortable();
+        let key = SortKey {
+            kind: SortKind::Date("%Q".to_string()),
+            ..SortKey::column("Amount")
+        };
+        assert!(matches!(data.sort(&[key], false, &Config::default()), Err(Error::DateFormat(_))));
+    }
+
+    #[test]
+    fn test_retained_data_sort_unknown_column() {
+        let mut data = sortable();
+        assert!(matches!(
+            data.sort(&[SortKey::column("Missing")], false, &Config::default()),
+            Err(Error::CsvHeaders(_))
+        ));
+    }
+
+    #[test]
+    fn test_retained_data_force_quote_only_named_columns() {
+        let temp_dir = TempDir::new("test").unwrap();
+        let output_path = temp_dir.path().join("output.csv");
+
+        let mut data = RetainedData {
+            retained_headers: vec!["Id".to_string(), "Name".to_string(), "Note".to_string()],
+            data: vec![
+                vec!["007".to_string(), "Bond".to_string(), "plain".to_string()],
+                vec!["0\"1".to_string(), "Smith".to_string(), "a, b".to_string()],
+            ],
+            ..Default::default()
+        };
+        data.force_quote(&["Id".to_string()], &Config::default()).unwrap();
+        data.to_csv(&output_path).unwrap();
+
+        // Only `Id` is always quoted, `Note` just where the comma requires it
+        assert_eq!(
+            std::fs::read_to_string(&output_path).unwrap(),
+            "Id,Name,Note\n\"007\",Bond,plain\n\"0\"\"1\",Smith,\"a, b\"\n"
+        );
+
+        // And it still reads back to the original values
+        let read_back = RetainedData::from_output_file(&output_path).unwrap();
+        assert_eq!(read_back.data, data.data);
+    }
+
+    #[test]
+    fn test_retained_data_force_quote_unknown_column() {
+        let mut data = gen_default_retained_data();
+        assert!(matches!(data.force_quote(&["Missing".to_string()], &Config::default()), Err(Error::CsvHeaders(_))));
+    }
+
+    #[test]
+    fn test_retained_data_columns_match_like_fields() {
+        let config = Config {
+            case_insensitive_headers: true,
+            ..Default::default()
+        };
+        let mut data = gen_default_retained_data();
+
+        data.force_quote(&[" header2 ".to_string()], &config).unwrap();
+        assert_eq!(data.force_quoted, vec![1]);
+        data.sort(&[SortKey::column("HEADER1")], false, &config).unwrap();
+
+        assert!(matches!(data.force_quote(&["header2".to_string()], &Config::default()), Err(Error::CsvHeaders(_))));
+    }
+
+    #[test]
+    fn test_retained_data_keep_head_and_tail() {
+        let mut head = sortable();
+        head.keep_head(2);
+        assert_eq!(ids(&head), vec!["1", "2"]);
+
+        let mut tail = sortable();
+        tail.keep_tail(2);
+        assert_eq!(ids(&tail), vec!["4", "5"]);
+
+        // Asking for more rows than there are keeps them all
+        let mut all = sortable();
+        all.keep_tail(10);
+        assert_eq!(ids(&all), vec!["1", "2", "3", "4", "5"]);
+    }
+
+    #[test]
+    fn test_retained_data_sample_is_reproducible() {
+        let rows = || RetainedData {
+            retained_headers: vec!["Id".to_string()],
+            data: (0..100).map(|id| vec![id.to_string()]).collect(),
+            ..Default::default()
+        };
+
+        let mut first = rows();
+        first.sample(10, 42);
+        let mut second = rows();
+        second.sample(10, 42);
+        let mut other_seed = rows();
+        other_seed.sample(10, 7);
+
+        assert_eq!(first.data.len(), 10);
+        assert_eq!(first.data, second.data);
+        assert_ne!(first.data, other_seed.data);
+        // The picked rows keep their relative order
+        let ids = first
+            .data
+            .iter()
+            .map(|row| row[0].parse::<usize>().unwrap())
+            .collect::<Vec<_>>();
+        assert!(ids.is_sorted());
+    }
+
+    #[test]
+    fn test_retained_data_sample_more_than_available() {
+        let mut data = sortable();
+        data.sample(10, 1);
+        assert_eq!(ids(&data), vec!["1", "2", "3", "4", "5"]);
+    }
+
+    #[test]
+    fn test_retained_data_push_column() {
+        let mut data = gen_default_retained_data();
+
+        data.push_column("Header3", vec!["Value3".to_string()]);
+
+        assert_eq!(data.retained_headers, vec!["Header1", "Header2", "Header3"]);
+        assert_eq!(data.data, vec![vec!["Value1", "Value2", "Value3"]]);
+    }
+
+    #[test]
+    fn test_retained_data_from_output_file_round_trip() {
+        let temp_dir = TempDir::new("test").unwrap();
+        let data = gen_default_retained_data();
+
+        for name in ["output.csv", "output.tsv"] {
+            let output_path = temp_dir.path().join(name);
+            if name.ends_with(".tsv") {
+                data.to_tsv(&output_path).unwrap();
+            } else {
+                data.to_csv(&output_path).unwrap();
+            }
+
+            let read = RetainedData::from_output_file(&output_path).unwrap();
+            assert_eq!(read.retained_headers, data.retained_headers);
+            assert_eq!(read.data, data.data);
+        }
+    }
+
+    #[test]
+    fn test_retained_data_to_csv_nested_dir() {
+        let temp_dir = TempDir::new("test").unwrap();
+        let output_path = temp_dir.path().join("nested").join("output.csv");
+
+        let data = gen_default_retained_data();
+
+        data.to_csv(output_path.clone()).unwrap();
+
+        let mut rdr = csv::Reader::from_path(output_path).unwrap();
+        let mut records = rdr.records();
+        let first_record = records.next().unwrap().unwrap();
+        assert_eq!(&first_record[0], "Value1");
+        assert_eq!(&first_record[1], "Value2");
+    }
+
+    #[test]
+    fn test_retained_data_to_tsv() {
+        let temp_dir = TempDir::new("test").unwrap();
+        let output_path = temp_dir.path().join("output.tsv");
+
+        let data = gen_default_retained_data();
+
+        data.to_tsv(output_path.clone()).unwrap();
+
+        let contents = std::fs::read_to_string(&output_path).unwrap();
+        assert_eq!(contents, "Header1\tHeader2\nValue1\tValue2\n");
+    }
+
+    #[test]
+    fn test_retained_data_to_json() {
+        let temp_dir = TempDir::new("test").unwrap();
+        let output_path = temp_dir.path().join("output.json");
+
+        let data = RetainedData {
+            retained_headers: vec!["Name".to_string(), "Id".to_string()],
+            data: vec![
+                vec!["Alice".to_string(), "007".to_string()],
+                vec!["Bob \"B\"".to_string(), "2".to_string()],
+            ],
+            ..Default::default()
+        };
+        data.to_json(&output_path, false).unwrap();
+
+        // Keys follow the header order, and numeric-looking values stay strings
+        assert_eq!(
+            std::fs::read_to_string(&output_path).unwrap(),
+            "[{\"Name\":\"Alice\",\"Id\":\"007\"},{\"Name\":\"Bob \\\"B\\\"\",\"Id\":\"2\"}]\n"
+        );
+    }
+
+    #[test]
+    fn test_retained_data_to_json_empty() {
+        let mut compact = vec![];
+        let mut pretty = vec![];
+        let data = gen_default_retained_data();
+        let empty = RetainedData { data: vec![], ..data };
+
+        empty.to_json_writer(&mut compact, false).unwrap();
+        empty.to_json_writer(&mut pretty, true).unwrap();
+
+        assert_eq!(String::from_utf8(compact).unwrap(), "[]\n");
+        assert_eq!(String::from_utf8(pretty).unwrap(), "[]\n");
+    }
+
+    #[test]
+    fn test_retained_data_to_ndjson() {
+        let temp_dir = TempDir::new("test").unwrap();
+        let output_path = temp_dir.path().join("output.ndjson");
+
+        let data = RetainedData {
+            retained_headers: vec!["Name".to_string(), "Id".to_string()],
+            data: vec![
+                vec!["Alice".to_string(), "007".to_string()],
+                vec!["Bob".to_string(), "2".to_string()],
+            ],
+            ..Default::default()
+        };
+        data.to_ndjson(&output_path).unwrap();
+
+        assert_eq!(
+            std::fs::read_to_string(&output_path).unwrap(),
+            "{\"Name\":\"Alice\",\"Id\":\"007\"}\n{\"Name\":\"Bob\",\"Id\":\"2\"}\n"
+        );
+    }
+
+    #[test]
+    fn test_retained_data_to_ndjson_flushes_while_writing() {
+        /// Records how many bytes had been written at each flush.
+        #[derive(Default)]
+        struct FlushLog {
+            written: usize,
+            flushes: Vec<usize>,
+        }
+
+        impl std::io::Write for FlushLog {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                self.written += buf.len();
+                Ok(buf.len())
+            }
+
+            fn flush(&mut self) -> std::io::Result<()> {
+                self.flushes.push(self.written);
+                Ok(())
+            }
+        }
+
+        let data = RetainedData {
+            retained_headers: vec!["Id".to_string()],
+            data: (0..NDJSON_FLUSH_ROWS * 2 + 1).map(|id| vec![id.to_string()]).collect(),
+            ..Default::default()
+        };
+        let mut log = FlushLog::default();
+        data.to_ndjson_writer(&mut log).unwrap();
+
+        // Once per `NDJSON_FLUSH_ROWS` rows, and once at the end
+        assert_eq!(log.flushes.len(), 3);
+        assert!(log.flushes[0] < log.flushes[1] && log.flushes[1] < log.flushes[2]);
+        assert_eq!(log.flushes[2], log.written);
+    }
+
     #[test]
     fn test_retained_data_to_stdout() {
         let data = RetainedData {
@@ -200,8 +1649,41 @@ mod output_retained_tests {
 
         data.to_csv(output_path.clone()).unwrap();
 
-        // Check that the file exists and is empty (or contains only header row if headers present)
+        // The empty header row is still written, as `""` and a line break
         let file_size = std::fs::metadata(&output_path).unwrap().len();
         assert_eq!(file_size, 3);
+
+        // Without the header row, the file is truly empty
+        let data = RetainedData {
+            omit_headers: true,
+            ..data
+        };
+        data.to_csv(output_path.clone()).unwrap();
+        assert_eq!(std::fs::metadata(&output_path).unwrap().len(), 0);
+    }
+
+    #[test]
+    fn test_to_stdout_writes_to_stdout() {
+        // The test binary re-runs just this test in a child process, whose stdout and stderr are captured separately
+        if std::env::var_os("CSV_PARSER_RS_STDOUT_CHILD").is_some() {
+            gen_default_retained_data().to_stdout().unwrap();
+            return;
+        }
+
+        let output = std::process::Command::new(std::env::current_exe().unwrap())
+            .args([
+                "--exact",
+                "retained::output_retained_tests::test_to_stdout_writes_to_stdout",
+                "--nocapture",
+            ])
+            .env("CSV_PARSER_RS_STDOUT_CHILD", "1")
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        assert!(stdout.contains("Header1,Header2\nValue1,Value2\n"), "stdout: {stdout}");
+        assert!(!stderr.contains("Value1"), "stderr: {stderr}");
     }
 }
@@ -1,13 +1,17 @@
 #![allow(clippy::needless_doctest_main)]
 
 use std::marker::PhantomData;
+use std::path::Path;
 
 use crate::cli::{Cli, OutputType};
-use crate::config::Config;
+use crate::config::{Config, Delimiter};
 use crate::csv_pipeline::CsvPipeline;
 pub(crate) use crate::prelude::*;
-use crate::processing::OutputData;
-use crate::retained::RetainedData;
+use crate::processing::{
+    OutputData, OutputSink, RunningTotalColumn, Stats, column_stats, dated_output_path, finish_writer,
+    resolve_output_path, stats_sidecar_path, validate, write_column_stats, write_stats, write_validation_report,
+};
+use crate::retained::{RetainedData, quote_cells};
 
 /// Represents the state of the application, encapsulating configuration, data, and processing components.
 ///
@@ -21,11 +25,15 @@ use crate::retained::RetainedData;
 /// * `handler` - The handler for managing CSV processing.
 /// * `processor` - The processor for performing data processing tasks.
 /// * `output_data` - The data related to output configuration.
+/// * `streaming` - Whether rows are written to the output as they are read (see `process_streaming`), rather than
+///   collected in `retained_data` first.
+/// * `rows_written` - The number of rows `process_streaming` or `process_external` wrote, as they never reach `retained_data`.
 /// * `phantom_data` - A phantom data marker to manage lifetimes.
 ///
 /// # Example
 ///
-/// ```rust
+/// ```no_run
+/// # use csv_parser_rs::{Cli, State};
 /// let cli = Cli::new();
 /// let state = State::new(cli).expect("Failed to create state");
 /// ```
@@ -34,6 +42,8 @@ pub struct State<'a> {
     pub retained_data: RetainedData,
     pub csv_pipeline: CsvPipeline,
     pub output_data: OutputData,
+    pub streaming: bool,
+    rows_written: Option<usize>,
     phantom_data: PhantomData<&'a ()>,
 }
 
@@ -66,22 +76,69 @@ impl State<'_> {
     ///
     /// # Example
     ///
-    /// ```rust
+    /// ```no_run
+    /// # use csv_parser_rs::{Cli, State};
     /// let cli = Cli::new();
     /// let state = State::new(cli).expect("Failed to create state");
     /// ```
     pub fn new(cli: Cli) -> Result<Self> {
-        let config = Config::new(cli)?;
-        let output_data = OutputData::new(config.output_type, config.output_path.clone());
+        Self::from_config(Config::new(cli)?)
+    }
+
+    /// Creates a new `State` instance from an already resolved `Config`, without any `Cli` input.
+    ///
+    /// This is steps 2-7 of `State::new`, for library users who build their `Config` programmatically.
+    /// As the `Cli` is what resolves `{regex}` and glob patterns, the `source` has to be a concrete path.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The configuration to build the state from.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Self>` - Returns a `State` instance on success, or an `Error` on failure.
+    ///
+    /// # Errors
+    ///
+    /// This function can return errors if the source cannot be read, or its headers cannot be parsed.
+    pub fn from_config(config: Config) -> Result<Self> {
+        // An `output_path` naming a directory gets a file named after the source in it
+        let output_path = resolve_output_path(&config.source, &config.output_path);
+        let output_path = match &config.output_subdir_date {
+            Some(format) => dated_output_path(&output_path, format, &chrono::Local::now())?,
+            None => output_path,
+        };
+        check_output_is_not_source(&config, &output_path)?;
+        if config.append
+            && matches!(
+                config.output_type,
+                OutputType::Json | OutputType::Ndjson | OutputType::Table | OutputType::Markdown | OutputType::Html
+            )
+        {
+            warn!("append is only supported for csv and tsv output, the {} output is replaced", config.output_type);
+        }
+        // Appending to a file that already has content mustn't add a second header row in the middle of it, and a
+        // headerless source only has the synthetic column names, which aren't written as a header row either
+        let omit_headers =
+            !config.write_headers || !config.has_header_row() || (config.append && has_content(&output_path));
+        let output_data = OutputData::new(config.output_type, output_path)
+            .with_delimiter(config.output_delimiter.map(Delimiter::as_byte))
+            .with_append(config.append);
         let mut retained_data = RetainedData::new(config.fields.len());
 
         let csv_pipeline = CsvPipeline::new(&config, &mut retained_data)?;
+        retained_data.force_quote(&config.force_quote_fields, &config)?;
+        retained_data.omit_headers = omit_headers;
+        retained_data.empty_placeholder.clone_from(&config.empty_placeholder);
+        let streaming = can_stream(&config);
 
         Ok(Self {
             config,
             retained_data,
             csv_pipeline,
             output_data,
+            streaming,
+            rows_written: None,
             phantom_data: PhantomData,
         })
     }
@@ -92,6 +149,7 @@ impl State<'_> {
     ///
     /// This function delegates the processing of CSV data to the `Processor`
     /// and updates the `retained_data` with the results.
+    /// When `head` or `tail` is set, the retained rows are then cut down to the first or last N rows.
     ///
     /// # Arguments
     ///
@@ -107,12 +165,172 @@ impl State<'_> {
     ///
     /// # Example
     ///
-    /// ```rust
-    /// let mut rdr = csv::Reader::from_path("data.csv").expect("Failed to open CSV file");
-    /// state.process(&mut rdr).expect("Failed to process CSV data");
+    /// ```no_run
+    /// # use csv_parser_rs::{Cli, State};
+    /// let mut state = State::new(Cli::new()).expect("Failed to create state");
+    /// state.process().expect("Failed to process CSV data");
     /// ```
     pub fn process(&mut self) -> Result<()> {
-        self.csv_pipeline.process(&mut self.retained_data)
+        self.csv_pipeline.process(&mut self.retained_data)?;
+
+        match (self.config.head, self.config.tail) {
+            (Some(head), tail) => {
+                if tail.is_some() {
+                    warn!("Both head and tail are set, only head is applied");
+                }
+                self.retained_data.keep_head(head);
+            }
+            (None, Some(tail)) => self.retained_data.keep_tail(tail),
+            (None, None) => {}
+        }
+        Ok(())
+    }
+
+    /// Processes the CSV data, sorting and deduplicating it on disk, and writes it straight to the output.
+    ///
+    /// This is the `external` counterpart of calling `process`, `deduplicate` and `output` in turn:
+    /// rows are spilled to sorted chunk files once `external_memory_mb` is exceeded and then merged,
+    /// so they are never all held in `retained_data`. The output is sorted by the `unique_fields`
+    /// (or by every retained column when there are none), and any `running_total` follows that sorted order.
+    /// As the rows are streamed out, `expect_rows` / `expect_rows_range` can only be checked once the output is written.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<()>` - Returns `Ok(())` on success, or an `Error` on failure.
+    ///
+    /// # Errors
+    ///
+    /// This function can return errors if reading the CSV, writing the chunk files, or writing the output fails.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// state.process_external().expect("Failed to process CSV data");
+    /// ```
+    pub fn process_external(&mut self) -> Result<()> {
+        if self.config.delta_against.is_some() {
+            warn!("delta_against is not supported together with external, the full output will be written");
+        }
+        if self.config.tail.is_some() {
+            warn!("tail is not supported together with external, every row will be output");
+        }
+        if self.config.sample.is_some() {
+            warn!("sample is not supported together with external, every row will be output");
+        }
+        if self.config.validate_report.is_some() {
+            warn!("validate_report is not supported together with external, no validation report will be written");
+        }
+        if self.config.with_stats.is_some() {
+            warn!("with_stats is not supported together with external, no stats file will be written");
+        }
+        if !self.config.sort_by.is_empty() {
+            warn!("sort_by is not supported together with external, the output is sorted by the unique_fields instead");
+        }
+        if self.config.dedup_count {
+            warn!("dedup_count is not supported together with external, no _count column will be written");
+        }
+        if !self.config.dedup_aggregate.is_empty() {
+            warn!("dedup_aggregate is not supported together with external, the kept rows' own values will be written");
+        }
+        if self.config.aggregate.is_some() {
+            warn!("aggregate is not supported together with external, the rows will be written without grouping");
+        }
+
+        // Created up front, so an unsupported output type fails before the whole source is read and spilled
+        let mut running_total = self.running_total_column()?;
+        let mut wtr = self.output_writer()?;
+
+        let budget_bytes = self.config.external_memory_mb.saturating_mul(1024 * 1024);
+        let mut sorter = self.csv_pipeline.external_sorter(&self.retained_data, budget_bytes)?;
+
+        self.csv_pipeline.process_with(|row| sorter.push(row))?;
+
+        let forced = &self.retained_data.force_quoted;
+        let delimiter = self.output_data.delimiter();
+        let placeholder = self.retained_data.empty_placeholder.as_deref();
+        let written =
+            sorter.finish(&mut wtr, |row| finish_row(row, running_total.as_mut(), forced, delimiter, placeholder))?;
+        finish_writer(wtr)?;
+
+        info!("External sort wrote {written} rows");
+        self.rows_written = Some(written);
+        self.csv_pipeline.external_written(written);
+        self.csv_pipeline.check_row_count(written)?;
+        Ok(())
+    }
+
+    /// Processes the CSV data, writing every row to the output as soon as it passes the filters.
+    ///
+    /// This is the `streaming` counterpart of calling `process` and `output` in turn: rows never accumulate
+    /// in `retained_data`, so memory use doesn't grow with the size of the source. It's only chosen when nothing
+    /// needs every row at once - see `can_stream`. Any `running_total` is computed as the rows are written.
+    /// As with `process_external`, `expect_rows` / `expect_rows_range` can only be checked once the output is written.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<()>` - Returns `Ok(())` on success, or an `Error` on failure.
+    ///
+    /// # Errors
+    ///
+    /// This function can return errors if reading the CSV or writing the output fails.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// if state.streaming {
+    ///     state.process_streaming().expect("Failed to process CSV data");
+    /// }
+    /// ```
+    pub fn process_streaming(&mut self) -> Result<()> {
+        let mut running_total = self.running_total_column()?;
+        let mut wtr = self.output_writer()?;
+
+        let mut written = 0;
+        let forced = &self.retained_data.force_quoted;
+        let delimiter = self.output_data.delimiter();
+        let placeholder = self.retained_data.empty_placeholder.as_deref();
+        self.csv_pipeline.process_with(|mut row| {
+            finish_row(&mut row, running_total.as_mut(), forced, delimiter, placeholder)?;
+            wtr.write_record(&row)?;
+            written += 1;
+            Ok(())
+        })?;
+        finish_writer(wtr)?;
+
+        info!("Streamed {written} rows to the output");
+        self.rows_written = Some(written);
+        self.csv_pipeline.check_row_count(written)?;
+        Ok(())
+    }
+
+    /// Creates the `RunningTotalColumn` for rows written straight to the output, if `running_total` is configured.
+    fn running_total_column(&self) -> Result<Option<RunningTotalColumn>> {
+        self.config
+            .running_total
+            .as_ref()
+            .map(|running_total| RunningTotalColumn::new(running_total, &self.retained_data.retained_headers))
+            .transpose()
+    }
+
+    /// Creates the output writer for rows written straight to the output, and writes the header row (unless it's omitted,
+    /// see `RetainedData::omit_headers`).
+    ///
+    /// When any column is force quoted, the writer never quotes and rows must be quoted by hand with `quote_cells`.
+    fn output_writer(&self) -> Result<csv::Writer<OutputSink>> {
+        let mut headers = self.retained_data.retained_headers.clone();
+        if let Some(running_total) = &self.config.running_total {
+            headers.push(running_total.into.clone());
+        }
+
+        let manual_quoting = !self.retained_data.force_quoted.is_empty();
+        let mut wtr = self.output_data.writer(manual_quoting)?;
+        if manual_quoting {
+            headers = quote_cells(&headers, &[], self.output_data.delimiter());
+        }
+        if !self.retained_data.omit_headers {
+            wtr.write_record(&headers)?;
+        }
+        Ok(wtr)
     }
 
     /// Deduplicates the retained data using the `Processor`.
@@ -120,18 +338,330 @@ impl State<'_> {
     /// This function calls the `deduplicate` method of the `Processor`
     /// to remove duplicate entries from the `retained_data`.
     ///
+    /// # Returns
+    ///
+    /// * `Result<()>` - Returns `Ok(())` on success, or an `Error` on failure.
+    ///
+    /// # Errors
+    ///
+    /// This function can return errors if a `unique_fields` or `dedup_aggregate` column is not among the retained headers.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// state.deduplicate().expect("Failed to deduplicate");
+    /// ```
+    pub fn deduplicate(&mut self) -> Result<()> {
+        self.csv_pipeline.deduplicate(&mut self.retained_data)
+    }
+
+    /// Rolls the retained data up into one row per group, if an `aggregate` is configured.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<()>` - Returns `Ok(())` on success, or an `Error` on failure.
+    ///
+    /// # Errors
+    ///
+    /// This function can return errors if a `group_by` column or the aggregated column is not among the retained headers.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// state.deduplicate()?;
+    /// state.aggregate().expect("Failed to aggregate");
+    /// ```
+    pub fn aggregate(&mut self) -> Result<()> {
+        self.csv_pipeline.aggregate(&mut self.retained_data)
+    }
+
+    /// Reduces the retained data to the rows added or changed since the `delta_against` output, if configured.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<()>` - Returns `Ok(())` on success, or an `Error` on failure.
+    ///
+    /// # Errors
+    ///
+    /// This function can return errors if the previous output cannot be read, or lacks one of the retained columns.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// state.deduplicate()?;
+    /// state.delta().expect("Failed to compute delta");
+    /// ```
+    pub fn delta(&mut self) -> Result<()> {
+        let Some(previous_path) = &self.config.delta_against else {
+            return Ok(());
+        };
+
+        // The previous output was presumably written with the same `output_delimiter`, otherwise it goes by its extension
+        let previous = match self.config.output_delimiter {
+            Some(delimiter) => RetainedData::from_delimited_file(previous_path, delimiter.as_byte())?,
+            None => RetainedData::from_output_file(previous_path)?,
+        };
+        self.csv_pipeline.delta(&mut self.retained_data, &previous)?;
+        info!("Delta against {:?}: {} added or changed rows", previous_path, self.retained_data.data.len());
+        Ok(())
+    }
+
+    /// Cuts the retained data down to the configured `sample` of random rows, if any.
+    ///
+    /// Without a configured `seed` one is derived from the current time, and logged so the pick can be repeated.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// state.deduplicate()?;
+    /// state.sample();
+    /// ```
+    pub fn sample(&mut self) {
+        let Some(sample) = self.config.sample else {
+            return;
+        };
+        let seed = self.config.seed.unwrap_or_else(|| {
+            let seed = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map_or(0, |elapsed| elapsed.as_nanos() as u64);
+            info!("Sampling with seed {seed}");
+            seed
+        });
+        self.retained_data.sample(sample, seed);
+    }
+
+    /// Sorts the retained data by the configured `sort_by` columns, if any.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<()>` - Returns `Ok(())` on success, or an `Error` on failure.
+    ///
+    /// # Errors
+    ///
+    /// This function can return errors if a `sort_by` column is not among the retained headers.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// state.sort().expect("Failed to sort");
+    /// ```
+    pub fn sort(&mut self) -> Result<()> {
+        if self.config.sort_by.is_empty() {
+            return Ok(());
+        }
+        self.retained_data
+            .sort(&self.config.sort_by, self.config.sort_desc, &self.config)
+    }
+
+    /// Appends the configured `running_total` column, accumulating in the current row order.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<()>` - Returns `Ok(())` on success, or an `Error` on failure.
+    ///
+    /// # Errors
+    ///
+    /// This function can return errors if the `source` column is not retained, or a value is not numeric
+    /// while `strict_numeric` is set.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// state.deduplicate()?;
+    /// state.running_total().expect("Failed to compute running total");
+    /// ```
+    pub fn running_total(&mut self) -> Result<()> {
+        self.csv_pipeline.running_total(&mut self.retained_data)
+    }
+
+    /// Checks the number of retained rows against the configured `expect_rows` / `expect_rows_range`.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<()>` - Returns `Ok(())` if the expectations hold, or an `Error` on failure.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::RowCountMismatch` stating the expected and actual row counts.
+    ///
     /// # Example
     ///
-    /// ```rust
-    /// state.deduplicate();
+    /// ```ignore
+    /// state.check_row_count().expect("Unexpected number of rows");
     /// ```
-    pub fn deduplicate(&mut self) {
-        self.csv_pipeline.deduplicate(&mut self.retained_data);
+    pub fn check_row_count(&self) -> Result<()> {
+        self.csv_pipeline.check_row_count(self.retained_data.data.len())
+    }
+
+    /// Logs a before/after row count for the most duplicated `unique_fields` keys.
+    ///
+    /// This does not modify the retained data; it is intended to be called before `deduplicate`
+    /// to help tune the choice of unique fields.
+    ///
+    /// # Arguments
+    ///
+    /// * `top_n` - The maximum number of keys to report.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<()>` - Returns `Ok(())` on success, or an `Error` on failure.
+    ///
+    /// # Errors
+    ///
+    /// This function can return errors if a unique field is not among the retained headers.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// state.dedup_preview(10).expect("Failed to preview deduplication");
+    /// ```
+    pub fn dedup_preview(&self, top_n: usize) -> Result<()> {
+        let preview = self.csv_pipeline.dedup_preview(&self.retained_data, top_n)?;
+
+        if preview.is_empty() {
+            info!("Dedup preview: no duplicated keys found for {:?}", self.config.unique_fields);
+            return Ok(());
+        }
+
+        info!("Dedup preview: top {} duplicated keys for {:?}", preview.len(), self.config.unique_fields);
+        for entry in &preview {
+            info!("  [{}] before: {}, after: {}", entry.key.join(", "), entry.before, entry.after);
+        }
+
+        Ok(())
+    }
+
+    /// Counts the rows of the source that pass the filters, without keeping or writing any of them, for `--count`.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<usize>` - Returns the number of matching rows, or an `Error` on failure.
+    ///
+    /// # Errors
+    ///
+    /// This function can return errors if reading the CSV fails.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// println!("{}", state.count().expect("Failed to count CSV data"));
+    /// ```
+    pub fn count(&mut self) -> Result<usize> {
+        self.csv_pipeline.count()
+    }
+
+    /// Returns the row counts of the run, see `Stats`. Only complete once the output has been written.
+    pub fn stats(&self) -> Stats {
+        self.csv_pipeline
+            .stats(self.rows_written.unwrap_or(self.retained_data.data.len()))
+    }
+
+    /// Writes the run `Stats` as a JSON sidecar next to the output file (see `stats_sidecar_path`), for `--stats`.
+    /// Output to stdout has no file to put it next to, so nothing is written then.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<()>` - Returns `Ok(())` on success, or an `Error` on failure.
+    ///
+    /// # Errors
+    ///
+    /// This function can return errors if the sidecar file cannot be written.
+    pub fn write_run_stats(&self) -> Result<()> {
+        if matches!(self.output_data.output_type, OutputType::Stdout) || self.config.writes_to_stdout() {
+            warn!("The output is written to stdout, so there's no output file to write the --stats sidecar next to");
+            return Ok(());
+        }
+        write_stats(&self.stats(), stats_sidecar_path(&self.output_data.output_path))
+    }
+
+    /// Describes what a run of this `State` would do, for `--dry-run`: the resolved source, the retained columns,
+    /// the columns filtered on and the output target.
+    ///
+    /// Only the headers of the source have been read at this point, and nothing has been written.
+    /// Filter columns that aren't in the source are left out, as they are ignored when processing (see `CsvHandler::new`).
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<String>` - One line per item of the report.
+    pub fn dry_run_report(&self) -> Vec<String> {
+        let config = &self.config;
+        let in_source = |column: &&str| {
+            self.retained_data
+                .all_headers
+                .iter()
+                .any(|header| config.header_matches(header, column))
+        };
+        let in_output = |column: &&str| {
+            self.retained_data
+                .retained_headers
+                .iter()
+                .any(|header| config.header_matches(header, column))
+        };
+        let sorted_unique = |columns: Vec<&str>| {
+            let mut columns = columns.into_iter().map(str::to_string).collect::<Vec<_>>();
+            columns.sort();
+            columns.dedup();
+            columns
+        };
+
+        let filter_columns = sorted_unique(
+            config
+                .include_cols_with
+                .keys()
+                .chain(config.numeric_filters.keys())
+                .map(String::as_str)
+                .chain(config.filters.iter().map(|rule| rule.column.as_str()))
+                .filter(in_source)
+                .collect(),
+        );
+        let exclude_columns = sorted_unique(
+            config
+                .exclude_cols_with
+                .keys()
+                .map(String::as_str)
+                .filter(in_source)
+                .collect(),
+        );
+        let post_filter_columns = sorted_unique(
+            config
+                .post_filters
+                .iter()
+                .map(|rule| rule.column.as_str())
+                .filter(in_output)
+                .collect(),
+        );
+
+        let source = if config.sources.len() > 1 {
+            format!("{:?}", config.sources)
+        } else if config.reads_from_stdin() {
+            "stdin".to_string()
+        } else {
+            config.source.display().to_string()
+        };
+        let output = if matches!(self.output_data.output_type, OutputType::Stdout) || config.writes_to_stdout() {
+            "stdout".to_string()
+        } else {
+            format!("{} file {}", self.output_data.output_type, self.output_data.output_path.display())
+        };
+
+        vec![
+            format!("Source: {source}"),
+            format!(
+                "Retained columns ({} of {}): {:?}",
+                self.retained_data.retained_headers.len(),
+                self.retained_data.all_headers.len(),
+                self.retained_data.retained_headers
+            ),
+            format!("Filter columns: {filter_columns:?}"),
+            format!("Exclude columns: {exclude_columns:?}"),
+            format!("Post filter columns: {post_filter_columns:?}"),
+            format!("Output: {output}"),
+        ]
     }
 
     /// Outputs the retained data based on the configured output type.
     ///
-    /// This function writes the retained data to either stdout or a CSV file,
+    /// This function writes the retained data to either stdout, a CSV file or a TSV file,
     /// depending on the `output_type` specified in the configuration.
     ///
     /// # Returns
@@ -144,18 +674,509 @@ impl State<'_> {
     ///
     /// # Example
     ///
-    /// ```rust
+    /// ```ignore
     /// state.output().expect("Failed to output data");
     /// ```
     pub fn output(&self) -> Result<()> {
         match self.output_data.output_type {
             OutputType::Stdout => {
-                self.retained_data.to_stdout()?;
+                self.retained_data.to_stdout_delimited(self.output_data.delimiter())?;
+            }
+            OutputType::Csv | OutputType::Tsv if self.output_data.append => {
+                self.retained_data
+                    .append_delimited(&self.output_data.output_path, self.output_data.delimiter())?;
+            }
+            OutputType::Csv | OutputType::Tsv => {
+                self.retained_data
+                    .to_delimited(&self.output_data.output_path, self.output_data.delimiter())?;
+            }
+            OutputType::Json if self.config.writes_to_stdout() => {
+                self.retained_data
+                    .to_json_writer(std::io::stdout().lock(), self.config.json_pretty)?;
+            }
+            OutputType::Json => {
+                self.retained_data
+                    .to_json(self.output_data.output_path.clone(), self.config.json_pretty)?;
+            }
+            OutputType::Ndjson if self.config.writes_to_stdout() => {
+                self.retained_data.to_ndjson_writer(std::io::stdout().lock())?;
+            }
+            OutputType::Ndjson => {
+                self.retained_data.to_ndjson(self.output_data.output_path.clone())?;
+            }
+            OutputType::Table if self.config.writes_to_stdout() => {
+                self.retained_data
+                    .to_table_writer(std::io::stdout().lock(), self.config.max_col_width)?;
+            }
+            OutputType::Table => {
+                self.retained_data
+                    .to_table(self.output_data.output_path.clone(), self.config.max_col_width)?;
+            }
+            OutputType::Markdown if self.config.writes_to_stdout() => {
+                self.retained_data.to_markdown_writer(std::io::stdout().lock())?;
+            }
+            OutputType::Markdown => {
+                self.retained_data.to_markdown(self.output_data.output_path.clone())?;
             }
-            OutputType::Csv => {
-                self.retained_data.to_csv(self.output_data.output_path.clone())?;
+            OutputType::Html if self.config.writes_to_stdout() => {
+                self.retained_data
+                    .to_html_writer(std::io::stdout().lock(), self.config.html_standalone)?;
+            }
+            OutputType::Html => {
+                self.retained_data
+                    .to_html(self.output_data.output_path.clone(), self.config.html_standalone)?;
             }
         }
         Ok(())
     }
+
+    /// Writes per-column statistics of the retained data to the `with_stats` path, if configured.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<()>` - Returns `Ok(())` on success, or an `Error` on failure.
+    ///
+    /// # Errors
+    ///
+    /// This function can return errors if the stats file cannot be written.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// state.output().expect("Failed to output data");
+    /// state.write_stats().expect("Failed to write stats");
+    /// ```
+    pub fn write_stats(&self) -> Result<()> {
+        match &self.config.with_stats {
+            Some(stats_path) => write_column_stats(&column_stats(&self.retained_data), stats_path),
+            None => Ok(()),
+        }
+    }
+
+    /// Checks the retained data against the configured `validations` and writes the violations to `validate_report`.
+    ///
+    /// Violations don't fail the run - the main output is still produced - but their number is logged.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<()>` - Returns `Ok(())` on success, or an `Error` on failure.
+    ///
+    /// # Errors
+    ///
+    /// This function can return errors if a validated column is not retained, or the report cannot be written.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// state.output().expect("Failed to output data");
+    /// state.write_validation_report().expect("Failed to write the validation report");
+    /// ```
+    pub fn write_validation_report(&self) -> Result<()> {
+        let Some(report_path) = &self.config.validate_report else {
+            return Ok(());
+        };
+        if self.config.validations.is_empty() {
+            warn!("validate_report is set, but there are no validations configured");
+        }
+
+        let violations = validate(&self.retained_data, &self.config.validations)?;
+        if !violations.is_empty() {
+            warn!("Found {} validation violations, see: {}", violations.len(), report_path.display());
+        }
+        write_validation_report(&violations, report_path)
+    }
+}
+
+/// Makes sure the output won't be written over the source file while - or before - it is read.
+///
+/// Stdin, stdout and FIFO sources are never checked: a pipe can't be overwritten, and a FIFO's path
+/// says nothing about where its data comes from.
+///
+/// # Arguments
+///
+/// * `config` - The configuration holding the `source`.
+/// * `output_path` - The resolved output path.
+///
+/// # Returns
+///
+/// * `Result<()>` - Returns `Ok(())` if the output is a different file to the source.
+///
+/// # Errors
+///
+/// Returns `Error::OutputIsSource` if both paths lead to the same file.
+fn check_output_is_not_source(config: &Config, output_path: &Path) -> Result<()> {
+    if config.reads_from_stdin() || config.writes_to_stdout() || config.reads_from_stream() {
+        return Ok(());
+    }
+
+    // The output doesn't need to exist yet, in which case it can't be the source
+    let Ok(output) = output_path.canonicalize() else {
+        return Ok(());
+    };
+    if std::iter::once(&config.source)
+        .chain(&config.sources)
+        .any(|source| source.canonicalize().is_ok_and(|source| source == output))
+    {
+        return Err(Error::OutputIsSource(output));
+    }
+    Ok(())
+}
+
+/// Appends the running total to a row written straight to the output, fills its empty cells with the `empty_placeholder`,
+/// and quotes it by hand if any column is force quoted.
+fn finish_row(
+    row: &mut Vec<String>,
+    running_total: Option<&mut RunningTotalColumn>,
+    forced: &[usize],
+    delimiter: u8,
+    empty_placeholder: Option<&str>,
+) -> Result<()> {
+    if let Some(column) = running_total {
+        let total = column.next(row)?;
+        row.push(total);
+    }
+    if let Some(placeholder) = empty_placeholder {
+        for cell in row.iter_mut().filter(|cell| cell.is_empty()) {
+            placeholder.clone_into(cell);
+        }
+    }
+    if !forced.is_empty() {
+        *row = quote_cells(row, forced, delimiter);
+    }
+    Ok(())
+}
+
+/// Whether a (previous) output file exists and isn't empty, so appending to it must leave out the header row.
+fn has_content(path: &Path) -> bool {
+    std::fs::metadata(path).is_ok_and(|metadata| metadata.len() > 0)
+}
+
+/// Whether rows can be written to the output as they are read, instead of being collected first.
+///
+/// Streaming is only possible when no step needs every row at once - deduplication, sorting, `tail`, `sample`,
+/// `delta_against`, `with_stats` and `validate_report` all do - and the output is CSV or TSV (JSON is written as a whole).
+/// `external` mode streams its sorted output by itself.
+///
+/// # Arguments
+///
+/// * `config` - The configuration to check.
+///
+/// # Returns
+///
+/// * `bool` - Returns `true` if the rows can be streamed to the output.
+fn can_stream(config: &Config) -> bool {
+    !config.external
+        && !config.deduplicates()
+        && config.sort_by.is_empty()
+        && config.tail.is_none()
+        && config.sample.is_none()
+        && config.aggregate.is_none()
+        && config.delta_against.is_none()
+        && config.with_stats.is_none()
+        && config.validate_report.is_none()
+        && matches!(config.output_type, OutputType::Stdout | OutputType::Csv | OutputType::Tsv)
+}
+
+#[cfg(test)]
+mod state_tests {
+    use clap::Parser;
+    use tempfile::tempdir;
+
+    use super::*;
+
+    fn state_for(dir: &std::path::Path, extra: &str) -> State<'static> {
+        state_for_source(dir, "Id,Name,Amount\n1,Alice,5\n2,Bob,x\n3,Carl,2\n1,Alice,5\n", extra)
+    }
+
+    fn state_for_source(dir: &std::path::Path, contents: &str, extra: &str) -> State<'static> {
+        let source = dir.join("input.csv");
+        std::fs::write(&source, contents).unwrap();
+
+        let config = dir.join("config.json");
+        let json = format!(
+            r#"{{
+                "source": {source:?},
+                "output_type": "csv",
+                "output_path": {:?},
+                "has_headers": true,
+                "fields": ["Id", "Name", "Amount"],
+                "unique_fields": [],
+                "include_cols_with": {{}},
+                "exclude_cols_with": {{ "Name": ["Bob"] }}
+                {extra}
+            }}"#,
+            dir.join("output.csv")
+        );
+        std::fs::write(&config, json).unwrap();
+
+        State::new(Cli::parse_from(["csv_parser_rs", "-c", config.to_str().unwrap()])).unwrap()
+    }
+
+    #[test]
+    fn test_dry_run_report() {
+        let dir = tempdir().unwrap();
+        let state = state_for(
+            dir.path(),
+            r#", "numeric_filters": { "Amount": [1, null], "Missing": [1, 2] },
+                "post_filters": [{ "column": "Name", "values": ["Alice"] }]"#,
+        );
+
+        let report = state.dry_run_report();
+
+        assert_eq!(report[0], format!("Source: {}", dir.path().join("input.csv").display()));
+        assert_eq!(report[1], r#"Retained columns (3 of 3): ["Id", "Name", "Amount"]"#);
+        assert_eq!(report[2], r#"Filter columns: ["Amount"]"#);
+        assert_eq!(report[3], r#"Exclude columns: ["Name"]"#);
+        assert_eq!(report[4], r#"Post filter columns: ["Name"]"#);
+        assert_eq!(report[5], format!("Output: csv file {}", dir.path().join("output.csv").display()));
+        assert!(!dir.path().join("output.csv").exists());
+    }
+
+    #[test]
+    fn test_process_streaming() {
+        let dir = tempdir().unwrap();
+        let mut state = state_for(
+            dir.path(),
+            r#", "running_total": { "source": "Amount", "into": "Total" }, "force_quote_fields": ["Id"]"#,
+        );
+        assert!(state.streaming);
+
+        state.process_streaming().unwrap();
+
+        // Every row went straight to the output, none were held on to
+        assert!(state.retained_data.data.is_empty());
+        let output = std::fs::read_to_string(dir.path().join("output.csv")).unwrap();
+        assert_eq!(output, "Id,Name,Amount,Total\n\"1\",Alice,5,5\n\"3\",Carl,2,7\n\"1\",Alice,5,12\n");
+    }
+
+    #[test]
+    fn test_empty_placeholder_only_applies_on_write() {
+        let dir = tempdir().unwrap();
+        let output_path = dir.path().join("output.csv");
+        let blanks = "Id,Name,Amount\n1,,5\n2,Dana,\n1,,5\n";
+
+        // Deduplication and the require_empty filter see the empty cells, not the placeholder
+        let placeholder = r#", "empty_placeholder": "NULL", "require_empty": ["Name"]"#;
+        let mut state =
+            state_for_source(dir.path(), blanks, &format!(r#"{placeholder}, "unique_fields": ["Id", "Name"]"#));
+        state.process().unwrap();
+        state.deduplicate().unwrap();
+        state.output().unwrap();
+        assert_eq!(state.retained_data.data, vec![vec!["1", "", "5"]]);
+        assert_eq!(std::fs::read_to_string(&output_path).unwrap(), "Id,Name,Amount\n1,NULL,5\n");
+
+        let mut state = state_for_source(dir.path(), blanks, r#", "empty_placeholder": "\\N""#);
+        assert!(state.streaming);
+        state.process_streaming().unwrap();
+        assert_eq!(std::fs::read_to_string(&output_path).unwrap(), "Id,Name,Amount\n1,\\N,5\n2,Dana,\\N\n1,\\N,5\n");
+    }
+
+    #[test]
+    fn test_output_delimiter() {
+        let dir = tempdir().unwrap();
+        let output_path = dir.path().join("output.csv");
+
+        let mut state = state_for(dir.path(), r#", "output_delimiter": ";""#);
+        assert_eq!(state.output_data.delimiter(), b';');
+        state.process().unwrap();
+        state.output().unwrap();
+        let written = std::fs::read_to_string(&output_path).unwrap();
+
+        let mut state = state_for(dir.path(), r#", "output_delimiter": ";""#);
+        assert!(state.streaming);
+        state.process_streaming().unwrap();
+        let streamed = std::fs::read_to_string(&output_path).unwrap();
+
+        for output in [written, streamed] {
+            assert_eq!(output, "Id;Name;Amount\n1;Alice;5\n3;Carl;2\n1;Alice;5\n");
+        }
+    }
+
+    #[test]
+    fn test_write_headers_off() {
+        let dir = tempdir().unwrap();
+        let output_path = dir.path().join("output.csv");
+
+        let mut state = state_for(dir.path(), r#", "write_headers": false"#);
+        state.process().unwrap();
+        state.output().unwrap();
+        let written = std::fs::read_to_string(&output_path).unwrap();
+
+        let mut state = state_for(dir.path(), r#", "write_headers": false"#);
+        state.process_streaming().unwrap();
+        let streamed = std::fs::read_to_string(&output_path).unwrap();
+
+        for output in [written, streamed] {
+            assert_eq!(output, "1,Alice,5\n3,Carl,2\n1,Alice,5\n");
+        }
+    }
+
+    #[test]
+    fn test_headerless_source_writes_no_header_row() {
+        let dir = tempdir().unwrap();
+        let output_path = dir.path().join("output.csv");
+        let contents = "1,Alice,5\n2,Bob,x\n3,Carl,2\n";
+        let extra =
+            r#", "has_headers": false, "fields": ["col0", "col1", "col2"], "exclude_cols_with": { "col1": ["Bob"] }"#;
+
+        let mut state = state_for_source(dir.path(), contents, extra);
+        state.process().unwrap();
+        state.output().unwrap();
+        let written = std::fs::read_to_string(&output_path).unwrap();
+
+        let mut state = state_for_source(dir.path(), contents, extra);
+        state.process_streaming().unwrap();
+        let streamed = std::fs::read_to_string(&output_path).unwrap();
+
+        // The first row is data, and the synthetic column names aren't written
+        for output in [written, streamed] {
+            assert_eq!(output, "1,Alice,5\n3,Carl,2\n");
+        }
+    }
+
+    #[test]
+    fn test_append_skips_header_of_existing_output() {
+        let dir = tempdir().unwrap();
+        let output_path = dir.path().join("output.csv");
+
+        let mut state = state_for(dir.path(), r#", "append": true"#);
+        state.process().unwrap();
+        state.output().unwrap();
+
+        let mut state = state_for(dir.path(), r#", "append": true"#);
+        assert!(state.streaming);
+        state.process_streaming().unwrap();
+
+        let output = std::fs::read_to_string(&output_path).unwrap();
+        assert_eq!(output, format!("Id,Name,Amount\n{0}{0}", "1,Alice,5\n3,Carl,2\n1,Alice,5\n"));
+    }
+
+    #[test]
+    fn test_yaml_config() {
+        let dir = tempdir().unwrap();
+        let source = dir.path().join("input.csv");
+        std::fs::write(&source, "Id,Name,Status\n1,Alice,Active\n2,Bob,Closed\n1,Alice,Active\n").unwrap();
+
+        let config = dir.path().join("config.yaml");
+        let yaml = format!(
+            "source: {source:?}\n\
+             output_type: csv\n\
+             output_path: {:?}\n\
+             has_headers: true\n\
+             fields:\n  - Id\n  - Name\n\
+             unique_fields:\n  - Id\n\
+             include_cols_with:\n  Status:\n    - Active\n",
+            dir.path().join("output.csv")
+        );
+        std::fs::write(&config, yaml).unwrap();
+
+        let mut state = State::new(Cli::parse_from(["csv_parser_rs", "-c", config.to_str().unwrap()])).unwrap();
+        assert_eq!(state.config.include_cols_with["Status"], vec!["Active"]);
+
+        state.process().unwrap();
+        state.deduplicate().unwrap();
+        state.output().unwrap();
+
+        let output = std::fs::read_to_string(dir.path().join("output.csv")).unwrap();
+        assert_eq!(output, "Id,Name\n1,Alice\n");
+    }
+
+    #[test]
+    fn test_output_is_source() {
+        let dir = tempdir().unwrap();
+        let source = dir.path().join("input.csv");
+        std::fs::write(&source, "Id,Name\n1,Alice\n").unwrap();
+        let config = dir.path().join("config.json");
+        let json = serde_json::json!({
+            "source": source, "output_type": "csv", "output_path": source, "has_headers": true,
+            "fields": ["Id"], "unique_fields": [], "include_cols_with": {}
+        });
+        std::fs::write(&config, json.to_string()).unwrap();
+
+        let state = State::new(Cli::parse_from(["csv_parser_rs", "-c", config.to_str().unwrap()]));
+        assert!(matches!(state, Err(Error::OutputIsSource(_))));
+        assert_eq!(std::fs::read_to_string(&source).unwrap(), "Id,Name\n1,Alice\n");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_fifo_source() {
+        let dir = tempdir().unwrap();
+        let fifo = dir.path().join("input.fifo");
+        let status = std::process::Command::new("mkfifo").arg(&fifo).status().unwrap();
+        assert!(status.success());
+
+        let config = dir.path().join("config.json");
+        let output = dir.path().join("output.csv");
+        let json = serde_json::json!({
+            "source": fifo, "output_type": "csv", "output_path": output, "has_headers": true,
+            "fields": ["Name"], "unique_fields": [], "include_cols_with": {}
+        });
+        std::fs::write(&config, json.to_string()).unwrap();
+
+        // Opening a FIFO blocks until both ends are open, so the writer has to run alongside the reader
+        let writer = {
+            let fifo = fifo.clone();
+            std::thread::spawn(move || std::fs::write(fifo, "Id,Name\n1,Alice\n2,Bob\n").unwrap())
+        };
+
+        let mut state = State::new(Cli::parse_from(["csv_parser_rs", "-c", config.to_str().unwrap()])).unwrap();
+        assert!(state.config.reads_from_stream());
+        assert_eq!(state.config.source, fifo);
+        state.process_streaming().unwrap();
+        writer.join().unwrap();
+
+        assert_eq!(std::fs::read_to_string(output).unwrap(), "Name\nAlice\nBob\n");
+    }
+
+    #[test]
+    fn test_buffered_when_rows_are_needed_at_once() {
+        let dir = tempdir().unwrap();
+        assert!(!state_for(dir.path(), r#", "unique_fields": ["Id"]"#).streaming);
+        assert!(!state_for(dir.path(), r#", "dedup_all": true"#).streaming);
+        assert!(!state_for(dir.path(), r#", "sort_by": ["Name"]"#).streaming);
+        assert!(!state_for(dir.path(), r#", "tail": 1"#).streaming);
+        assert!(!state_for(dir.path(), r#", "output_type": "json""#).streaming);
+    }
+
+    #[test]
+    fn test_external_matches_in_memory() {
+        let dir = tempdir().unwrap();
+        let output_path = dir.path().join("output.csv");
+        // Large enough to spill several chunks at 1 MiB, with every Id occurring three times out of order.
+        // The Ids are zero padded, so the external key order and the numeric `sort_by` agree
+        let mut source = String::from("Id,Name,Amount\n");
+        for i in 0..30_000 {
+            source.push_str(&format!("{:05},Name {i},{}\n", (i * 7919) % 10_000, i % 100));
+        }
+        let extra = r#", "unique_fields": ["Id"], "sort_by": ["Id"], "external_memory_mb": 1"#;
+
+        let mut state = state_for_source(dir.path(), &source, extra);
+        state.process().unwrap();
+        state.deduplicate().unwrap();
+        state.sort().unwrap();
+        state.output().unwrap();
+        let in_memory = std::fs::read_to_string(&output_path).unwrap();
+
+        let mut state = state_for_source(dir.path(), &source, &format!(r#"{extra}, "external": true"#));
+        state.process_external().unwrap();
+        let external = std::fs::read_to_string(&output_path).unwrap();
+
+        assert_eq!(in_memory.lines().count(), 10_001);
+        assert_eq!(external, in_memory);
+    }
+
+    #[test]
+    fn test_external_rejects_output_type_before_reading() {
+        let dir = tempdir().unwrap();
+        // The data row is malformed, so reading any of the source would fail with a CSV error instead
+        let mut state = state_for_source(
+            dir.path(),
+            "Id,Name,Amount\n1,Alice\n",
+            r#", "external": true, "unique_fields": ["Id"], "output_type": "json""#,
+        );
+
+        let err = state.process_external().unwrap_err();
+        assert!(matches!(err, Error::Io(e) if e.kind() == std::io::ErrorKind::Unsupported));
+    }
 }